@@ -0,0 +1,22 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::influxdb2::utils::InfluxDb2SinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let influxdb_sink = InfluxDb2SinkBuilder::default()
+        .with_using_url("http://localhost:8086")
+        .with_org("loady")
+        .with_bucket("loady")
+        .with_token("my-token")
+        .build();
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(influxdb_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
@@ -0,0 +1,20 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::otlp::utils::OtlpSinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let otlp_sink = OtlpSinkBuilder::default()
+        .with_endpoint("http://localhost:4318")
+        .with_service_name("loady-sample")
+        .build();
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(otlp_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
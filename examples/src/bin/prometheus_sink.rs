@@ -0,0 +1,20 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::prometheus::utils::PrometheusSinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let prometheus_sink = PrometheusSinkBuilder::default()
+        .with_address("0.0.0.0:9185")
+        .build()
+        .expect("The Prometheus sink can't be built");
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(prometheus_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
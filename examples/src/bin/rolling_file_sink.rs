@@ -0,0 +1,24 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::file::utils::RollingFileSinkBuilder;
+use std::time::Duration;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let file_sink = RollingFileSinkBuilder::default()
+        .with_directory("reports")
+        .with_file_name("loady")
+        .with_max_size(10 * 1024 * 1024)
+        .with_max_age(Duration::from_secs(3600))
+        .with_gzip()
+        .build();
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(file_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
@@ -0,0 +1,19 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::sqlite::utils::SqliteSinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let sqlite_sink = SqliteSinkBuilder::default()
+        .with_path("loady-history.db")
+        .build();
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(sqlite_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
@@ -0,0 +1,23 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::statsd::utils::StatsdSinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let statsd_sink = StatsdSinkBuilder::default()
+        .with_address("127.0.0.1:8125")
+        .with_prefix("loady")
+        .with_dogstatsd_tags()
+        .with_tag("env", "local")
+        .build()
+        .expect("The StatsD sink can't be built");
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(statsd_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
@@ -0,0 +1,19 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::tui::utils::TuiSinkBuilder;
+use std::time::Duration;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let tui_sink = TuiSinkBuilder::default()
+        .with_refresh_rate(Duration::from_millis(200))
+        .with_history_size(60)
+        .build();
+
+    let runner = TestRunner::default().with_reporting_sink(tui_sink);
+
+    _ = runner.run(test_case).await;
+}
@@ -0,0 +1,33 @@
+use loady::core::runner::TestRunner;
+use loady_sinks::webhook::sink::{WebhookEvent, WebhookHook};
+use loady_sinks::webhook::utils::WebhookSinkBuilder;
+use support::Sample;
+
+mod support;
+
+#[tokio::main]
+async fn main() {
+    let test_case = Sample::build_test_case();
+    let webhook_sink = WebhookSinkBuilder::default()
+        .with_url("http://localhost:8080/hooks/loady")
+        .with_header("Authorization", "Bearer my-token")
+        .with_hooks(&[WebhookHook::StepEnded, WebhookHook::TestEnded])
+        .with_payload_transformer(|event| {
+            let mut payload = event.to_payload();
+            if let WebhookEvent::TestEnded(status) = event {
+                payload["text"] = format!(
+                    "{} finished: {} hits, {} errors",
+                    status.test_name, status.metrics.all_hits, status.metrics.negative_hits
+                )
+                .into();
+            }
+            Some(payload)
+        })
+        .build();
+
+    let runner = TestRunner::default()
+        .with_reporting_sink(webhook_sink)
+        .with_test_summary_std_out();
+
+    _ = runner.run(test_case).await;
+}
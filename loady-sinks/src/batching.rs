@@ -0,0 +1,139 @@
+//! Buffering and retried delivery shared by the sinks that send their statuses in batches.
+
+use async_trait::async_trait;
+use loady::core::reporting::{SinkError, SinkResult};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Failure of a whole delivery attempt.
+pub(crate) struct DeliveryFailure {
+    pub message: String,
+    pub retryable: bool,
+}
+
+impl DeliveryFailure {
+    /// A failure for an error response, where throttling and server errors are worth retrying.
+    pub fn from_status(message: String, status: u16) -> Self {
+        Self {
+            message,
+            retryable: is_retryable(status),
+        }
+    }
+
+    /// A failure to reach the target, which is always worth retrying.
+    pub fn unreachable(message: String) -> Self {
+        Self {
+            message,
+            retryable: true,
+        }
+    }
+}
+
+/// Items of an attempt that the target didn't accept, while it accepted the rest.
+#[derive(Debug, Default)]
+pub(crate) struct Rejections {
+    /// Positions within the batch of the items worth retrying.
+    pub retryable: Vec<usize>,
+    /// Number of items rejected for good.
+    pub rejected: usize,
+}
+
+pub(crate) fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// Where a batch is delivered to.
+#[async_trait]
+pub(crate) trait BatchTarget<T: Send + Sync>: Send + Sync {
+    async fn send(&self, batch: &[T]) -> Result<Rejections, DeliveryFailure>;
+
+    /// The error reported when items are discarded for the given reason.
+    fn discarded(&self, count: usize, reason: &str) -> SinkError;
+}
+
+/// Buffers the items until the batch is full or flushed, then delivers them, retrying the
+/// retryable failures with an exponential backoff. Discarded items are counted.
+pub(crate) struct BatchBuffer<T> {
+    items: Mutex<Vec<T>>,
+    batch_size: usize,
+    max_retries: u32,
+    retry_backoff: Duration,
+    failed: AtomicU64,
+}
+
+impl<T: Send + Sync> BatchBuffer<T> {
+    pub fn new(batch_size: usize, max_retries: u32, retry_backoff: Duration) -> Self {
+        Self {
+            items: Mutex::new(Vec::with_capacity(batch_size)),
+            batch_size,
+            max_retries,
+            retry_backoff,
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of items that couldn't be delivered.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Adds the items and delivers the batch once it's full, or right away when flushing.
+    pub async fn push<B: BatchTarget<T>>(
+        &self,
+        target: &B,
+        items: impl IntoIterator<Item = T>,
+        flush: bool,
+    ) -> SinkResult {
+        let batch = {
+            let mut buffer = self.items.lock().await;
+            buffer.extend(items);
+            if buffer.is_empty() || (!flush && buffer.len() < self.batch_size) {
+                return Ok(());
+            }
+            std::mem::take(&mut *buffer)
+        };
+
+        self.deliver(target, batch).await
+    }
+
+    async fn deliver<B: BatchTarget<T>>(&self, target: &B, mut batch: Vec<T>) -> SinkResult {
+        let mut attempt = 0;
+        let mut result = Ok(());
+
+        loop {
+            let reason = match target.send(&batch).await {
+                Ok(rejections) => {
+                    if rejections.rejected > 0 {
+                        result =
+                            Err(self.discard(target, rejections.rejected, "they were rejected"));
+                    }
+                    batch = batch
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(index, _)| rejections.retryable.contains(index))
+                        .map(|(_, item)| item)
+                        .collect();
+                    if batch.is_empty() {
+                        return result;
+                    }
+                    format!("{} of them were throttled", batch.len())
+                }
+                Err(failure) if failure.retryable => failure.message,
+                Err(failure) => return Err(self.discard(target, batch.len(), &failure.message)),
+            };
+
+            if attempt >= self.max_retries {
+                return Err(self.discard(target, batch.len(), &reason));
+            }
+
+            tokio::time::sleep(self.retry_backoff * 2u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    fn discard<B: BatchTarget<T>>(&self, target: &B, count: usize, reason: &str) -> SinkError {
+        self.failed.fetch_add(count as u64, Ordering::Relaxed);
+        target.discarded(count, reason)
+    }
+}
@@ -1,65 +1,287 @@
 use async_trait::async_trait;
 use chrono::prelude::{DateTime, Utc};
-use elasticsearch::{Elasticsearch, IndexParts};
-use loady::core::reporting::ReportingSink;
+use elasticsearch::http::request::JsonBody;
+use elasticsearch::{BulkParts, Elasticsearch};
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
 use loady::core::stats::*;
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use tokio::sync::Mutex;
 
+use super::utils::ElasticSinkBuilder;
+use crate::batching::{is_retryable, BatchBuffer, BatchTarget, DeliveryFailure, Rejections};
+
 #[derive(Serialize)]
-struct DocumentDto {
+struct DocumentDto<T: Serialize> {
     created_at: String,
-    status: StepStatus,
+    kind: &'static str,
+    status: T,
 }
 
-impl DocumentDto {
-    fn new(step_status: StepStatus) -> Self {
+impl<T: Serialize> DocumentDto<T> {
+    fn new(kind: &'static str, status: T) -> Self {
         let now: DateTime<Utc> = SystemTime::now().into();
 
         Self {
             created_at: now.to_rfc3339(),
-            status: step_status,
+            kind,
+            status,
+        }
+    }
+}
+
+struct BulkDocument {
+    index: String,
+    body: Value,
+}
+
+impl BulkDocument {
+    fn new<T: Serialize>(session_id: &str, document: DocumentDto<T>) -> Self {
+        Self {
+            index: String::from("reporting-") + session_id,
+            body: json!(document),
         }
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct ElasticSink {
     pub client: Arc<Mutex<Elasticsearch>>,
+    buffer: Arc<BatchBuffer<BulkDocument>>,
+}
+
+impl Default for ElasticSink {
+    fn default() -> Self {
+        ElasticSinkBuilder::default().build()
+    }
 }
 
 impl ElasticSink {
-    async fn index(&self, step_status: StepStatus) {
+    pub(crate) fn new(
+        client: Elasticsearch,
+        batch_size: usize,
+        max_retries: u32,
+        retry_backoff: Duration,
+    ) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+            buffer: Arc::new(BatchBuffer::new(batch_size, max_retries, retry_backoff)),
+        }
+    }
+
+    /// Number of documents that could not be indexed after exhausting the retries.
+    pub fn failed_documents(&self) -> u64 {
+        self.buffer.failed()
+    }
+
+    async fn enqueue(&self, document: BulkDocument, force_flush: bool) -> SinkResult {
+        self.buffer.push(self, [document], force_flush).await
+    }
+}
+
+#[async_trait]
+impl BatchTarget<BulkDocument> for ElasticSink {
+    /// Sends a bulk request and returns the positions of the documents worth retrying,
+    /// along with the number of documents rejected for good.
+    async fn send(&self, documents: &[BulkDocument]) -> Result<Rejections, DeliveryFailure> {
+        let body = documents
+            .iter()
+            .flat_map(|document| {
+                [
+                    JsonBody::new(json!({ "index": { "_index": document.index } })),
+                    JsonBody::new(document.body.clone()),
+                ]
+            })
+            .collect::<Vec<JsonBody<Value>>>();
+
         let client = self.client.lock().await;
-        let index_name = String::from("reporting-") + &step_status.session_id;
-        let doc = DocumentDto::new(step_status);
         let response = client
-            .index(IndexParts::Index(&index_name))
-            .body(json!(doc))
+            .bulk(BulkParts::None)
+            .body(body)
             .send()
             .await
-            .expect("Something went wrong while sending the request to ElasticSearch");
+            .map_err(|error| DeliveryFailure::unreachable(error.to_string()))?;
 
-        if !response.status_code().is_success() {
-            panic!("The request to ElasticSearch has failed");
+        let status = response.status_code();
+        if !status.is_success() {
+            return Err(DeliveryFailure::from_status(
+                format!("ElasticSearch responded with {}", status),
+                status.as_u16(),
+            ));
         }
+
+        let content = response
+            .json::<Value>()
+            .await
+            .map_err(|error| DeliveryFailure {
+                message: error.to_string(),
+                retryable: false,
+            })?;
+        if !content["errors"].as_bool().unwrap_or_default() {
+            return Ok(Rejections::default());
+        }
+
+        let mut rejections = Rejections::default();
+        let items = content["items"].as_array().cloned().unwrap_or_default();
+        for (index, item) in items.iter().enumerate() {
+            let status = item["index"]["status"].as_u64().unwrap_or_default() as u16;
+            if (200..300).contains(&status) {
+                continue;
+            }
+            if is_retryable(status) {
+                rejections.retryable.push(index);
+            } else {
+                rejections.rejected += 1;
+            }
+        }
+
+        Ok(rejections)
+    }
+
+    fn discarded(&self, count: usize, reason: &str) -> SinkError {
+        SinkError::new(format!(
+            "Unable to index {} documents into ElasticSearch: {}",
+            count, reason
+        ))
     }
 }
 
 #[async_trait]
 impl ReportingSink for ElasticSink {
-    async fn on_test_ended(&self, _: TestStatus) {}
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        let session_id = test_status.session_id.clone();
+        let document = BulkDocument::new(&session_id, DocumentDto::new("test", test_status));
+        self.enqueue(document, true).await
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        let session_id = step_status.session_id.clone();
+        let document = BulkDocument::new(&session_id, DocumentDto::new("step", step_status));
+        self.enqueue(document, true).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        let session_id = step_status.session_id.clone();
+        let document = BulkDocument::new(&session_id, DocumentDto::new("action", step_status));
+        self.enqueue(document, false).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::{self, step_status, test_status, Requests};
+    use tokio::net::TcpListener;
+
+    const BULK_OK: &str = r#"{"errors":false,"items":[]}"#;
+
+    async fn serve(responses: Vec<(u16, &'static str)>) -> (String, Requests) {
+        test_utils::serve(responses, (200, BULK_OK)).await
+    }
+
+    fn build_sink(url: &str, batch_size: usize) -> ElasticSink {
+        ElasticSinkBuilder::default()
+            .with_using_url(url)
+            .with_batch_size(batch_size)
+            .with_max_retries(2)
+            .with_retry_backoff(Duration::from_millis(1))
+            .build()
+    }
 
-    async fn on_load_step_ended(&self, step_status: StepStatus) {
-        self.index(step_status).await;
+    #[tokio::test]
+    async fn given_batch_size_when_actions_end_then_flushes_through_bulk_api() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = build_sink(&url, 2);
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        assert!(requests.lock().await.is_empty());
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /_bulk"));
+        assert_eq!(
+            requests[0]
+                .matches(r#""_index":"reporting-session""#)
+                .count(),
+            2
+        );
+        assert!(requests[1].contains(r#""kind":"test""#));
+        assert_eq!(sink.failed_documents(), 0);
     }
 
-    async fn on_load_action_ended(&self, step_status: StepStatus) {
-        self.index(step_status).await;
+    #[tokio::test]
+    async fn given_custom_metrics_when_step_ends_then_indexes_them_with_the_step() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = build_sink(&url, 1);
+        let mut step_status = step_status();
+        step_status
+            .custom_metrics
+            .counters
+            .insert(String::from("cache_hits"), 12);
+
+        sink.on_load_step_ended(step_status).await.unwrap();
+
+        let requests = requests.lock().await;
+        assert!(requests[0].contains(r#""custom_metrics":{"counters":{"cache_hits":12}"#));
     }
 
-    async fn on_internal_step_ended(&self, _: &str) {}
+    #[tokio::test]
+    async fn given_throttled_documents_when_flushing_then_retries_only_those() {
+        let (url, requests) = serve(vec![
+            (503, "{}"),
+            (
+                200,
+                r#"{"errors":true,"items":[{"index":{"status":201}},{"index":{"status":429}}]}"#,
+            ),
+        ])
+        .await;
+        let sink = build_sink(&url, 2);
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_step_ended(step_status()).await.unwrap();
+
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].matches(r#""_index""#).count(), 1);
+        assert!(requests[2].contains(r#""kind":"step""#));
+        assert_eq!(sink.failed_documents(), 0);
+    }
+
+    #[tokio::test]
+    async fn given_rejected_documents_when_flushing_then_counts_failures() {
+        let (url, requests) = serve(vec![(
+            200,
+            r#"{"errors":true,"items":[{"index":{"status":400}}]}"#,
+        )])
+        .await;
+        let sink = build_sink(&url, 1);
+
+        let result = sink.on_load_action_ended(step_status()).await;
+
+        assert!(result.is_err());
+        assert_eq!(requests.lock().await.len(), 1);
+        assert_eq!(sink.failed_documents(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_unreachable_cluster_when_flushing_then_returns_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let sink = build_sink(&url, 1);
+
+        assert!(sink.on_load_step_ended(step_status()).await.is_err());
+        assert!(sink.on_test_ended(test_status()).await.is_err());
+        assert_eq!(sink.failed_documents(), 2);
+    }
 }
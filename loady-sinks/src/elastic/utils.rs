@@ -1,14 +1,20 @@
 use super::sink::ElasticSink;
 use elasticsearch::{auth::Credentials, http::transport::Transport, Elasticsearch};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
 
 #[derive(Default)]
 pub struct ElasticSinkBuilder {
     client: Option<Elasticsearch>,
+    batch_size: Option<usize>,
+    max_retries: Option<u32>,
+    retry_backoff: Option<Duration>,
 }
 
 impl ElasticSinkBuilder {
+    const DEFAULT_BATCH_SIZE: usize = 50;
+    const DEFAULT_MAX_RETRIES: u32 = 3;
+    const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
     pub fn with_using_url(mut self, url: &str) -> Self {
         let transport = Transport::single_node(url)
             .expect("The url provided for the Elastic sink is malformed");
@@ -26,10 +32,30 @@ impl ElasticSinkBuilder {
         self
     }
 
-    pub fn build(self) -> ElasticSink {
-        let client = self.client.unwrap_or(Elasticsearch::default());
-        ElasticSink {
-            client: Arc::new(Mutex::new(client)),
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        if batch_size == 0 {
+            panic!("The batch size of the Elastic sink must be greater than zero");
         }
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = Some(retry_backoff);
+        self
+    }
+
+    pub fn build(self) -> ElasticSink {
+        ElasticSink::new(
+            self.client.unwrap_or(Elasticsearch::default()),
+            self.batch_size.unwrap_or(Self::DEFAULT_BATCH_SIZE),
+            self.max_retries.unwrap_or(Self::DEFAULT_MAX_RETRIES),
+            self.retry_backoff.unwrap_or(Self::DEFAULT_RETRY_BACKOFF),
+        )
     }
 }
@@ -0,0 +1,387 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{InternalStepStatus, StepStatus, TestStatus};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Serialize)]
+struct LineDto<'a, T: Serialize> {
+    created_at: String,
+    kind: &'static str,
+    status: &'a T,
+}
+
+/// Rotation settings of the rolling file. A segment is rotated as soon as any of the limits is hit.
+#[derive(Clone, Debug, Default)]
+pub struct RotationPolicy {
+    pub max_size: Option<u64>,
+    pub max_age: Option<Duration>,
+    pub gzip: bool,
+}
+
+struct ActiveSegment {
+    file: File,
+    size: u64,
+    opened_at: Instant,
+}
+
+struct RollingFile {
+    directory: PathBuf,
+    file_name: String,
+    policy: RotationPolicy,
+    segment: Option<ActiveSegment>,
+    sequence: u32,
+}
+
+impl RollingFile {
+    const EXTENSION: &'static str = "ndjson";
+
+    fn active_path(&self) -> PathBuf {
+        self.directory
+            .join(format!("{}.{}", self.file_name, Self::EXTENSION))
+    }
+
+    fn segment(&mut self) -> io::Result<&mut ActiveSegment> {
+        if self.segment.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.active_path())?;
+            let size = file.metadata()?.len();
+            self.segment = Some(ActiveSegment {
+                file,
+                size,
+                opened_at: Instant::now(),
+            });
+        }
+
+        Ok(self.segment.as_mut().unwrap())
+    }
+
+    fn should_rotate(&mut self, incoming: u64) -> io::Result<bool> {
+        let policy = self.policy.clone();
+        let segment = self.segment()?;
+        if segment.size == 0 {
+            return Ok(false);
+        }
+
+        let exceeds_size = policy
+            .max_size
+            .is_some_and(|max_size| segment.size + incoming > max_size);
+        let exceeds_age = policy
+            .max_age
+            .is_some_and(|max_age| segment.opened_at.elapsed() >= max_age);
+
+        Ok(exceeds_size || exceeds_age)
+    }
+
+    /// Renames the active segment after the rotation time and a sequence, which is increased
+    /// until the name is free, as other sinks or runs may have rotated into the same directory.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.segment = None;
+
+        let now: DateTime<Utc> = SystemTime::now().into();
+        let rotated = loop {
+            self.sequence += 1;
+            let rotated = self.directory.join(format!(
+                "{}-{}-{}.{}",
+                self.file_name,
+                now.format("%Y%m%dT%H%M%S%3f"),
+                self.sequence,
+                Self::EXTENSION
+            ));
+            if !rotated.exists() && !Self::compressed_path(&rotated).exists() {
+                break rotated;
+            }
+        };
+        fs::rename(self.active_path(), &rotated)?;
+
+        if self.policy.gzip {
+            Self::compress(&rotated)?;
+        }
+
+        Ok(())
+    }
+
+    fn compressed_path(path: &Path) -> PathBuf {
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(".gz");
+        PathBuf::from(compressed)
+    }
+
+    fn compress(path: &Path) -> io::Result<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut encoder = GzEncoder::new(
+            File::create(Self::compressed_path(path))?,
+            Compression::default(),
+        );
+        io::copy(&mut reader, &mut encoder)?;
+        encoder.finish()?;
+        fs::remove_file(path)
+    }
+
+    fn append(&mut self, line: &str) -> io::Result<()> {
+        let incoming = line.len() as u64 + 1;
+        if self.should_rotate(incoming)? {
+            self.rotate()?;
+        }
+
+        let segment = self.segment()?;
+        segment.file.write_all(line.as_bytes())?;
+        segment.file.write_all(b"\n")?;
+        segment.size += incoming;
+        Ok(())
+    }
+
+    /// Writes are unbuffered, so syncing makes sure they reach the disk.
+    fn sync(&mut self) -> io::Result<()> {
+        match self.segment.as_mut() {
+            Some(segment) => segment.file.sync_data(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RollingFileSink {
+    pub path: PathBuf,
+    file: Arc<Mutex<RollingFile>>,
+}
+
+impl RollingFileSink {
+    pub(crate) fn new(directory: PathBuf, file_name: String, policy: RotationPolicy) -> Self {
+        let file = RollingFile {
+            directory,
+            file_name,
+            policy,
+            segment: None,
+            sequence: 0,
+        };
+
+        Self {
+            path: file.active_path(),
+            file: Arc::new(Mutex::new(file)),
+        }
+    }
+
+    async fn write<T: Serialize>(&self, kind: &'static str, status: &T, sync: bool) -> SinkResult {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        let line = LineDto {
+            created_at: now.to_rfc3339(),
+            kind,
+            status,
+        };
+        let line = serde_json::to_string(&line).map_err(|error| {
+            SinkError::new(format!("The status couldn't be serialized: {error}"))
+        })?;
+
+        let file = Arc::clone(&self.file);
+        let result = tokio::task::spawn_blocking(move || {
+            let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+            file.append(&line)?;
+            if sync {
+                file.sync()?;
+            }
+            Ok::<(), io::Error>(())
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(error)) => Err(SinkError::new(format!(
+                "The status couldn't be written into the file: {error}"
+            ))),
+            Err(error) => Err(SinkError::new(format!(
+                "The rolling file sink task has failed: {error}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl ReportingSink for RollingFileSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.write("test", &test_status, true).await
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.write("step", &step_status, true).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.write("action", &step_status, false).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::file::utils::RollingFileSinkBuilder;
+    use crate::test_utils::{step_status, test_status};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "loady-rolling-{}-{}-{}",
+            name,
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ))
+    }
+
+    fn files(directory: &Path) -> Vec<String> {
+        let mut files = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        files.sort();
+        files
+    }
+
+    #[tokio::test]
+    async fn given_statuses_when_reported_then_appends_ndjson_lines() {
+        let directory = temp_directory("lines");
+        let sink = RollingFileSinkBuilder::default()
+            .with_directory(directory.to_str().unwrap())
+            .with_file_name("run")
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_step_ended(step_status()).await.unwrap();
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let content = fs::read_to_string(&sink.path).unwrap();
+        let kinds = content
+            .lines()
+            .map(|line| {
+                let value = serde_json::from_str::<serde_json::Value>(line).unwrap();
+                value["kind"].as_str().unwrap().to_owned()
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(kinds, vec!["action", "step", "test"]);
+        assert_eq!(files(&directory), vec!["run.ndjson"]);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn given_max_size_when_exceeded_then_rotates_and_compresses_segments() {
+        let directory = temp_directory("size");
+        let sink = RollingFileSinkBuilder::default()
+            .with_directory(directory.to_str().unwrap())
+            .with_file_name("run")
+            .with_max_size(100)
+            .with_gzip()
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_step_ended(step_status()).await.unwrap();
+
+        let files = files(&directory);
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files
+                .iter()
+                .filter(|file| file.ends_with(".ndjson.gz"))
+                .count(),
+            2
+        );
+        assert_eq!(fs::read_to_string(&sink.path).unwrap().lines().count(), 1);
+
+        let mut decoded = String::new();
+        GzDecoder::new(File::open(directory.join(&files[0])).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert!(decoded.contains(r#""kind":"action""#));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn given_max_age_when_elapsed_then_rotates_segment() {
+        let directory = temp_directory("age");
+        let sink = RollingFileSinkBuilder::default()
+            .with_directory(directory.to_str().unwrap())
+            .with_file_name("run")
+            .with_max_age(Duration::from_millis(10))
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        sink.on_load_step_ended(step_status()).await.unwrap();
+
+        let files = files(&directory);
+        assert_eq!(files.len(), 2);
+        assert!(files[0].starts_with("run-") && files[0].ends_with(".ndjson"));
+        assert_eq!(files[1], "run.ndjson");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn given_sinks_sharing_file_when_rotating_then_keeps_every_segment() {
+        let directory = temp_directory("shared");
+        let build = || {
+            RollingFileSinkBuilder::default()
+                .with_directory(directory.to_str().unwrap())
+                .with_file_name("run")
+                .with_max_size(100)
+                .build()
+        };
+        let first = build();
+        let second = build();
+
+        first.on_load_action_ended(step_status()).await.unwrap();
+        first.on_load_action_ended(step_status()).await.unwrap();
+        second.on_load_action_ended(step_status()).await.unwrap();
+
+        let files = files(&directory);
+        assert_eq!(files.len(), 3);
+        let lines = files
+            .iter()
+            .map(|file| {
+                fs::read_to_string(directory.join(file))
+                    .unwrap()
+                    .lines()
+                    .count()
+            })
+            .sum::<usize>();
+        assert_eq!(lines, 3);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn given_poisoned_lock_when_reported_then_still_writes() {
+        let directory = temp_directory("poisoned");
+        let sink = RollingFileSinkBuilder::default()
+            .with_directory(directory.to_str().unwrap())
+            .with_file_name("run")
+            .build();
+        let file = Arc::clone(&sink.file);
+        _ = std::thread::spawn(move || {
+            let _guard = file.lock().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join();
+
+        sink.on_load_step_ended(step_status()).await.unwrap();
+
+        assert_eq!(fs::read_to_string(&sink.path).unwrap().lines().count(), 1);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+use super::sink::{RollingFileSink, RotationPolicy};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct RollingFileSinkBuilder {
+    directory: Option<PathBuf>,
+    file_name: Option<String>,
+    policy: RotationPolicy,
+}
+
+impl RollingFileSinkBuilder {
+    const DEFAULT_DIRECTORY: &str = ".";
+    const DEFAULT_FILE_NAME: &str = "loady";
+
+    pub fn with_directory(mut self, directory: &str) -> Self {
+        self.directory = Some(PathBuf::from(directory));
+        self
+    }
+
+    pub fn with_file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_owned());
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        if max_size == 0 {
+            panic!("The max size of the rolling file must be greater than zero");
+        }
+        self.policy.max_size = Some(max_size);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        if max_age.is_zero() {
+            panic!("The max age of the rolling file must be greater than zero");
+        }
+        self.policy.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_gzip(mut self) -> Self {
+        self.policy.gzip = true;
+        self
+    }
+
+    pub fn build(self) -> RollingFileSink {
+        let directory = self
+            .directory
+            .unwrap_or(PathBuf::from(Self::DEFAULT_DIRECTORY));
+        std::fs::create_dir_all(&directory)
+            .expect("The directory for the rolling file can't be created");
+
+        RollingFileSink::new(
+            directory,
+            self.file_name
+                .unwrap_or(String::from(Self::DEFAULT_FILE_NAME)),
+            self.policy,
+        )
+    }
+}
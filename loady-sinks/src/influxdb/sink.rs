@@ -2,8 +2,8 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use influxdb::Client;
 use influxdb::InfluxDbWriteable;
-use loady::core::reporting::ReportingSink;
-use loady::core::stats::{StepStatus, TestStatus};
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{CustomMetrics, InternalStepStatus, StepStatus, TestStatus};
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::Mutex;
@@ -60,7 +60,7 @@ impl MeticConverter for StepStatus {
         let step_name = &self.step_name;
         let test_name = &self.test_name;
 
-        let metrics = vec![
+        let mut metrics = vec![
             Metric::new(
                 created_at,
                 session_id,
@@ -167,6 +167,17 @@ impl MeticConverter for StepStatus {
             },
         ];
 
+        metrics.extend(custom_values(&self.custom_metrics).into_iter().map(
+            |(description, mesurement)| Metric {
+                time: created_at,
+                session_id: session_id.to_owned(),
+                step_name: step_name.to_owned(),
+                test_name: test_name.to_owned(),
+                description,
+                mesurement,
+            },
+        ));
+
         metrics
             .into_iter()
             .map(|metric| metric.into_query(query_name))
@@ -196,35 +207,73 @@ impl MeticConverter for StepStatus {
     }
 }
 
+/// Flattens the custom metrics into values described as `custom.<name>`, where every histogram
+/// is summarized by its count, min, mean, percentiles and max.
+fn custom_values(custom_metrics: &CustomMetrics) -> Vec<(String, f64)> {
+    let counters = custom_metrics
+        .counters
+        .iter()
+        .map(|(name, value)| (format!("custom.{}", name), *value as f64));
+    let gauges = custom_metrics
+        .gauges
+        .iter()
+        .map(|(name, value)| (format!("custom.{}", name), *value));
+    let histograms = custom_metrics
+        .histograms
+        .iter()
+        .flat_map(|(name, summary)| {
+            [
+                ("count", summary.count as f64),
+                ("min", summary.min),
+                ("mean", summary.mean),
+                ("p50", summary.p50),
+                ("p90", summary.p90),
+                ("p95", summary.p95),
+                ("p99", summary.p99),
+                ("max", summary.max),
+            ]
+            .map(|(suffix, value)| (format!("custom.{}.{}", name, suffix), value))
+        });
+
+    counters.chain(gauges).chain(histograms).collect()
+}
+
 impl InfluxDbSink {
-    async fn insert(&self, metrics: &Vec<influxdb::WriteQuery>) {
+    async fn insert(&self, metrics: &Vec<influxdb::WriteQuery>) -> SinkResult {
         let client = self.client.lock().await;
-        client
-            .query(metrics)
-            .await
-            .expect("The request to InfluxDb has failed");
+        match client.query(metrics).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(SinkError::new(format!(
+                "The request to InfluxDb has failed: {}",
+                error
+            ))),
+        }
     }
 
-    async fn track_metrics(&self, step_status: &StepStatus) {
+    async fn track_metrics(&self, step_status: &StepStatus) -> SinkResult {
         let created_at = SystemTime::now().into();
         let metrics = step_status.to_metrics(self.metrics_ts_name.as_str(), created_at);
         let errors = step_status.to_errors(self.errors_ts_name.as_str(), created_at);
-        self.insert(&metrics).await;
-        self.insert(&errors).await;
+        self.insert(&metrics).await?;
+        self.insert(&errors).await
     }
 }
 
 #[async_trait]
 impl ReportingSink for InfluxDbSink {
-    async fn on_test_ended(&self, _: TestStatus) {}
+    async fn on_test_ended(&self, _: TestStatus) -> SinkResult {
+        Ok(())
+    }
 
-    async fn on_load_step_ended(&self, step_status: StepStatus) {
-        self.track_metrics(&step_status).await;
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.track_metrics(&step_status).await
     }
 
-    async fn on_load_action_ended(&self, step_status: StepStatus) {
-        self.track_metrics(&step_status).await;
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.track_metrics(&step_status).await
     }
 
-    async fn on_internal_step_ended(&self, _: &str) {}
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
 }
@@ -0,0 +1,503 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{CustomMetrics, InternalStepStatus, Metrics, StepStatus, TestStatus};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::batching::{BatchBuffer, BatchTarget, DeliveryFailure, Rejections};
+
+/// A single point serialized with the InfluxDB line protocol.
+struct Point {
+    measurement: String,
+    tags: Vec<(&'static str, String)>,
+    fields: Vec<(&'static str, FieldValue)>,
+    timestamp: i64,
+}
+
+enum FieldValue {
+    Integer(u128),
+    Float(f64),
+}
+
+impl Point {
+    fn new(measurement: &str, timestamp: i64) -> Self {
+        Self {
+            measurement: measurement.to_owned(),
+            tags: Vec::new(),
+            fields: Vec::new(),
+            timestamp,
+        }
+    }
+
+    fn with_tag(mut self, key: &'static str, value: &str) -> Self {
+        if !value.is_empty() {
+            self.tags.push((key, value.to_owned()));
+        }
+        self
+    }
+
+    fn with_integer(mut self, key: &'static str, value: u128) -> Self {
+        self.fields.push((key, FieldValue::Integer(value)));
+        self
+    }
+
+    /// Skips NaN and infinite values, which the line protocol can't represent.
+    fn with_float(mut self, key: &'static str, value: f64) -> Self {
+        if value.is_finite() {
+            self.fields.push((key, FieldValue::Float(value)));
+        }
+        self
+    }
+
+    /// Serializes the point, unless it has no field left, as InfluxDb rejects the whole batch
+    /// when any of its lines has no field.
+    fn to_line(&self) -> Option<String> {
+        if self.fields.is_empty() {
+            return None;
+        }
+
+        let tags = self
+            .tags
+            .iter()
+            .map(|(key, value)| format!(",{}={}", key, escape(value, &[',', '=', ' '])))
+            .collect::<String>();
+
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| match value {
+                FieldValue::Integer(value) => format!("{}={}i", key, value),
+                FieldValue::Float(value) => format!("{}={}", key, value),
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        Some(format!(
+            "{}{} {} {}",
+            escape(&self.measurement, &[',', ' ']),
+            tags,
+            fields,
+            self.timestamp
+        ))
+    }
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    value
+        .chars()
+        .fold(String::with_capacity(value.len()), |mut escaped, c| {
+            if c == '\\' || special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DeliveryPolicy {
+    pub batch_size: usize,
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    pub timeout: Duration,
+}
+
+#[derive(Clone)]
+pub struct InfluxDb2Sink {
+    pub write_url: String,
+    pub metrics_measurement: String,
+    pub errors_measurement: String,
+    pub custom_measurement: String,
+    client: Client,
+    token: Option<String>,
+    buffer: Arc<BatchBuffer<String>>,
+}
+
+impl InfluxDb2Sink {
+    const LINE_PROTOCOL_CONTENT_TYPE: &'static str = "text/plain; charset=utf-8";
+
+    pub(crate) fn new(
+        write_url: String,
+        token: Option<String>,
+        metrics_measurement: String,
+        errors_measurement: String,
+        custom_measurement: String,
+        policy: DeliveryPolicy,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(policy.timeout)
+            .build()
+            .expect("The InfluxDb sink client can't be built");
+
+        Self {
+            write_url,
+            metrics_measurement,
+            errors_measurement,
+            custom_measurement,
+            client,
+            token,
+            buffer: Arc::new(BatchBuffer::new(
+                policy.batch_size,
+                policy.max_retries,
+                policy.retry_backoff,
+            )),
+        }
+    }
+
+    /// Number of points that InfluxDb didn't accept or couldn't be reached for.
+    pub fn failed_points(&self) -> u64 {
+        self.buffer.failed()
+    }
+
+    fn now() -> i64 {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        now.timestamp_nanos_opt().unwrap_or_default()
+    }
+
+    fn metrics_point(&self, metrics: &Metrics, timestamp: i64) -> Point {
+        Point::new(&self.metrics_measurement, timestamp)
+            .with_integer("test_duration", metrics.test_duration)
+            .with_integer("load_duration", metrics.load_duration)
+            .with_integer("mean_time", metrics.mean_time)
+            .with_integer("max_time", metrics.max_time)
+            .with_integer("min_time", metrics.min_time)
+            .with_integer("std_dev", metrics.std_dev)
+            .with_integer("p90_time", metrics.p90_time)
+            .with_integer("p95_time", metrics.p95_time)
+            .with_integer("p99_time", metrics.p99_time)
+            .with_integer("positive_hits", metrics.positive_hits)
+            .with_integer("negative_hits", metrics.negative_hits)
+            .with_integer("all_hits", metrics.all_hits)
+            .with_integer("in_flight", metrics.in_flight)
+            .with_float("request_per_sec", metrics.request_per_sec)
+            .with_integer("response_p95_time", metrics.response_time.p95)
+            .with_integer("response_p99_time", metrics.response_time.p99)
+            .with_integer("response_max_time", metrics.response_time.max)
+            .with_integer("schedule_lag_p99", metrics.schedule_lag.p99)
+            .with_integer("schedule_lag_max", metrics.schedule_lag.max)
+            .with_integer("spawn_lag_p99", metrics.generator.spawn_lag.p99)
+            .with_integer("delayed_hits", metrics.generator.delayed_hits)
+            .with_integer("dropped_hits", metrics.generator.dropped_hits)
+            .with_integer("dropped_events", metrics.generator.dropped_events)
+            .with_integer("max_in_flight", metrics.generator.max_in_flight)
+    }
+
+    fn error_points(&self, metrics: &Metrics, timestamp: i64) -> Vec<Point> {
+        let mut errors = metrics.errors.iter().collect::<Vec<(&i32, &u128)>>();
+        errors.sort();
+        errors
+            .into_iter()
+            .map(|(code, count)| {
+                Point::new(&self.errors_measurement, timestamp)
+                    .with_tag("code", &code.to_string())
+                    .with_integer("count", *count)
+            })
+            .collect()
+    }
+
+    /// Writes a point per custom metric, tagged with its name and kind. Counters and histograms
+    /// share the integer `count` field, while gauges use the float `value` field.
+    fn custom_points(&self, custom_metrics: &CustomMetrics, timestamp: i64) -> Vec<Point> {
+        let point = |name: &str, kind: &str| {
+            Point::new(&self.custom_measurement, timestamp)
+                .with_tag("metric", name)
+                .with_tag("kind", kind)
+        };
+
+        let counters = custom_metrics
+            .counters
+            .iter()
+            .map(|(name, value)| point(name, "counter").with_integer("count", *value as u128));
+        let gauges = custom_metrics
+            .gauges
+            .iter()
+            .map(|(name, value)| point(name, "gauge").with_float("value", *value));
+        let histograms = custom_metrics.histograms.iter().map(|(name, summary)| {
+            point(name, "histogram")
+                .with_integer("count", summary.count as u128)
+                .with_float("sum", summary.sum)
+                .with_float("min", summary.min)
+                .with_float("max", summary.max)
+                .with_float("mean", summary.mean)
+                .with_float("p50", summary.p50)
+                .with_float("p90", summary.p90)
+                .with_float("p95", summary.p95)
+                .with_float("p99", summary.p99)
+        });
+
+        counters.chain(gauges).chain(histograms).collect()
+    }
+
+    fn step_lines(&self, step_status: &StepStatus) -> Vec<String> {
+        let timestamp = Self::now();
+        let tag = |point: Point| {
+            point
+                .with_tag("session_id", &step_status.session_id)
+                .with_tag("test_name", &step_status.test_name)
+                .with_tag("test_suite", &step_status.test_suite)
+                .with_tag("step_name", &step_status.step_name)
+                .with_tag("stage_name", &step_status.stage_name)
+                .to_line()
+        };
+
+        std::iter::once(self.metrics_point(&step_status.metrics, timestamp))
+            .chain(self.error_points(&step_status.metrics, timestamp))
+            .chain(self.custom_points(&step_status.custom_metrics, timestamp))
+            .filter_map(tag)
+            .collect()
+    }
+
+    fn test_lines(&self, test_status: &TestStatus) -> Vec<String> {
+        let timestamp = Self::now();
+        let tag = |point: Point| {
+            point
+                .with_tag("session_id", &test_status.session_id)
+                .with_tag("test_name", &test_status.test_name)
+                .with_tag("test_suite", &test_status.test_suite)
+                .to_line()
+        };
+
+        std::iter::once(self.metrics_point(&test_status.metrics, timestamp))
+            .chain(self.error_points(&test_status.metrics, timestamp))
+            .filter_map(tag)
+            .collect()
+    }
+
+    async fn enqueue(&self, lines: Vec<String>, force_flush: bool) -> SinkResult {
+        self.buffer.push(self, lines, force_flush).await
+    }
+}
+
+#[async_trait]
+impl BatchTarget<String> for InfluxDb2Sink {
+    async fn send(&self, lines: &[String]) -> Result<Rejections, DeliveryFailure> {
+        let mut request = self
+            .client
+            .post(&self.write_url)
+            .header(CONTENT_TYPE, Self::LINE_PROTOCOL_CONTENT_TYPE)
+            .body(lines.join("\n"));
+
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Token {}", token));
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => Ok(Rejections::default()),
+            Ok(response) => {
+                let status = response.status();
+                let message = response.text().await.unwrap_or_default();
+                Err(DeliveryFailure::from_status(
+                    format!("InfluxDb responded with {} {}", status, message),
+                    status.as_u16(),
+                ))
+            }
+            Err(error) => Err(DeliveryFailure::unreachable(error.to_string())),
+        }
+    }
+
+    fn discarded(&self, count: usize, reason: &str) -> SinkError {
+        SinkError::new(format!(
+            "Unable to write {} points into InfluxDb: {}",
+            count, reason
+        ))
+    }
+}
+
+#[async_trait]
+impl ReportingSink for InfluxDb2Sink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.enqueue(self.test_lines(&test_status), true).await
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.enqueue(self.step_lines(&step_status), true).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.enqueue(self.step_lines(&step_status), false).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::influxdb2::utils::InfluxDb2SinkBuilder;
+    use crate::test_utils::{self, serve};
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    fn step_status() -> StepStatus {
+        StepStatus {
+            test_name: String::from("checkout test"),
+            stage_name: String::from("stage=1"),
+            metrics: Metrics {
+                positive_hits: 9,
+                negative_hits: 1,
+                request_per_sec: 11.5,
+                in_flight: 2,
+                errors: HashMap::from([(503, 1)]),
+                ..test_utils::metrics()
+            },
+            ..test_utils::step_status()
+        }
+    }
+
+    fn build_sink(url: &str, batch_size: usize) -> InfluxDb2Sink {
+        InfluxDb2SinkBuilder::default()
+            .with_using_url(url)
+            .with_org("acme")
+            .with_bucket("loady")
+            .with_token("secret")
+            .with_batch_size(batch_size)
+            .with_retry_backoff(Duration::from_millis(1))
+            .build()
+    }
+
+    #[test]
+    fn given_special_characters_when_serializing_point_then_escapes_them() {
+        let line = Point::new("loady metrics", 1)
+            .with_tag("test_name", "a,b=c d")
+            .with_tag("stage_name", "")
+            .with_integer("all_hits", 10)
+            .with_float("request_per_sec", 1.5)
+            .to_line()
+            .unwrap();
+
+        assert_eq!(
+            line,
+            r"loady\ metrics,test_name=a\,b\=c\ d all_hits=10i,request_per_sec=1.5 1"
+        );
+    }
+
+    #[test]
+    fn given_non_finite_floats_when_serializing_point_then_skips_them() {
+        let line = Point::new("loady-custom", 1)
+            .with_integer("count", 2)
+            .with_float("sum", f64::NAN)
+            .with_float("min", f64::NEG_INFINITY)
+            .with_float("max", 4.5)
+            .to_line()
+            .unwrap();
+
+        assert_eq!(line, "loady-custom count=2i,max=4.5 1");
+    }
+
+    #[test]
+    fn given_nan_gauge_when_serializing_step_then_skips_its_point() {
+        let sink = build_sink("http://localhost:8086", 100);
+        let mut status = step_status();
+        status.custom_metrics.gauges = std::collections::BTreeMap::from([
+            (String::from("broken"), f64::NAN),
+            (String::from("queue"), 3.0),
+        ]);
+
+        let lines = sink.step_lines(&status);
+
+        assert!(lines.iter().all(|line| !line.contains("metric=broken")));
+        assert!(lines.iter().any(
+            |line| line.starts_with("loady-custom,metric=queue,kind=gauge")
+                && line.contains(" value=3 ")
+        ));
+    }
+
+    #[tokio::test]
+    async fn given_batch_size_when_actions_end_then_writes_line_protocol_batches() {
+        let (url, requests) = serve(Vec::new(), (204, "")).await;
+        let sink = build_sink(&url, 4);
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        assert!(requests.lock().await.is_empty());
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+
+        let requests = requests.lock().await;
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert!(request.starts_with("POST /api/v2/write?org=acme&bucket=loady&precision=ns"));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: token secret"));
+
+        let body = request.split("\r\n\r\n").nth(1).unwrap();
+        let lines = body.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(
+            r"loady-metrics,session_id=session,test_name=checkout\ test,test_suite=suite,step_name=step,stage_name=stage\=1 "
+        ));
+        assert!(lines[0].contains("all_hits=10i"));
+        assert!(lines[0].contains("request_per_sec=11.5"));
+        assert!(lines[0].contains("schedule_lag_max=0i"));
+        assert!(lines[1].starts_with("loady-errors,code=503,session_id=session"));
+        assert!(lines[1].contains(" count=1i "));
+        assert_eq!(sink.failed_points(), 0);
+    }
+
+    #[tokio::test]
+    async fn given_rejected_write_when_flushing_then_counts_failed_points() {
+        let (url, requests) = serve(Vec::new(), (401, "")).await;
+        let sink = build_sink(&url, 100);
+
+        let result = sink.on_load_step_ended(step_status()).await;
+
+        assert!(result.is_err());
+        assert_eq!(requests.lock().await.len(), 1);
+        assert_eq!(sink.failed_points(), 2);
+    }
+
+    #[tokio::test]
+    async fn given_unreachable_server_when_flushing_then_returns_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let sink = build_sink(&url, 100);
+
+        let result = sink
+            .on_test_ended(TestStatus {
+                session_id: String::from("session"),
+                test_name: String::from("test"),
+                test_suite: String::from("suite"),
+                metrics: step_status().metrics,
+                sinks: Vec::new(),
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(sink.failed_points(), 2);
+    }
+
+    #[tokio::test]
+    async fn given_custom_metrics_when_step_ends_then_writes_custom_points() {
+        let (url, requests) = serve(Vec::new(), (204, "")).await;
+        let sink = build_sink(&url, 100);
+        let mut step_status = step_status();
+        step_status
+            .custom_metrics
+            .counters
+            .insert(String::from("cache_hits"), 12);
+        step_status
+            .custom_metrics
+            .gauges
+            .insert(String::from("queue_depth"), 2.5);
+
+        sink.on_load_step_ended(step_status).await.unwrap();
+
+        let requests = requests.lock().await;
+        let body = requests[0].split("\r\n\r\n").nth(1).unwrap();
+        let lines = body.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[2].starts_with("loady-custom,metric=cache_hits,kind=counter,session_id=session")
+        );
+        assert!(lines[2].contains(" count=12i "));
+        assert!(lines[3].starts_with("loady-custom,metric=queue_depth,kind=gauge"));
+        assert!(lines[3].contains(" value=2.5 "));
+    }
+}
@@ -0,0 +1,118 @@
+use super::sink::{DeliveryPolicy, InfluxDb2Sink};
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct InfluxDb2SinkBuilder {
+    url: Option<String>,
+    org: Option<String>,
+    bucket: Option<String>,
+    token: Option<String>,
+    metrics_measurement: Option<String>,
+    errors_measurement: Option<String>,
+    custom_measurement: Option<String>,
+    batch_size: Option<usize>,
+    max_retries: Option<u32>,
+    retry_backoff: Option<Duration>,
+    timeout: Option<Duration>,
+}
+
+impl InfluxDb2SinkBuilder {
+    const DEFAULT_METRICS_MEASUREMENT_NAME: &str = "loady-metrics";
+    const DEFAULT_ERRORS_MEASUREMENT_NAME: &str = "loady-errors";
+    const DEFAULT_CUSTOM_MEASUREMENT_NAME: &str = "loady-custom";
+    const DEFAULT_BATCH_SIZE: usize = 500;
+    const DEFAULT_MAX_RETRIES: u32 = 3;
+    const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+    const WRITE_PATH: &str = "/api/v2/write";
+
+    pub fn with_using_url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_owned());
+        self
+    }
+
+    pub fn with_org(mut self, org: &str) -> Self {
+        self.org = Some(org.to_owned());
+        self
+    }
+
+    pub fn with_bucket(mut self, bucket: &str) -> Self {
+        self.bucket = Some(bucket.to_owned());
+        self
+    }
+
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_owned());
+        self
+    }
+
+    pub fn with_measurement_names(
+        mut self,
+        metrics_measurement: &str,
+        errors_measurement: &str,
+    ) -> Self {
+        self.metrics_measurement = Some(metrics_measurement.to_owned());
+        self.errors_measurement = Some(errors_measurement.to_owned());
+        self
+    }
+
+    pub fn with_custom_measurement_name(mut self, custom_measurement: &str) -> Self {
+        self.custom_measurement = Some(custom_measurement.to_owned());
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        if batch_size == 0 {
+            panic!("The batch size of the InfluxDb sink must be greater than zero");
+        }
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = Some(retry_backoff);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> InfluxDb2Sink {
+        let url = self.url.expect("InfluxDb url not provided");
+        let org = self.org.expect("InfluxDb org not provided");
+        let bucket = self.bucket.expect("InfluxDb bucket not provided");
+        let write_url = reqwest::Url::parse_with_params(
+            &format!("{}{}", url.trim_end_matches('/'), Self::WRITE_PATH),
+            &[
+                ("org", org),
+                ("bucket", bucket),
+                ("precision", String::from("ns")),
+            ],
+        )
+        .expect("The url provided for the InfluxDb sink is malformed");
+
+        InfluxDb2Sink::new(
+            write_url.to_string(),
+            self.token,
+            self.metrics_measurement
+                .unwrap_or(String::from(Self::DEFAULT_METRICS_MEASUREMENT_NAME)),
+            self.errors_measurement
+                .unwrap_or(String::from(Self::DEFAULT_ERRORS_MEASUREMENT_NAME)),
+            self.custom_measurement
+                .unwrap_or(String::from(Self::DEFAULT_CUSTOM_MEASUREMENT_NAME)),
+            DeliveryPolicy {
+                batch_size: self.batch_size.unwrap_or(Self::DEFAULT_BATCH_SIZE),
+                max_retries: self.max_retries.unwrap_or(Self::DEFAULT_MAX_RETRIES),
+                retry_backoff: self.retry_backoff.unwrap_or(Self::DEFAULT_RETRY_BACKOFF),
+                timeout: self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT),
+            },
+        )
+    }
+}
@@ -1,2 +1,24 @@
+#[cfg(any(feature = "elastic", feature = "influxdb2", feature = "webhook"))]
+mod batching;
+#[cfg(feature = "elastic")]
 pub mod elastic;
+#[cfg(feature = "file")]
+pub mod file;
+#[cfg(feature = "influxdb")]
 pub mod influxdb;
+#[cfg(feature = "influxdb2")]
+pub mod influxdb2;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "statsd")]
+pub mod statsd;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "webhook")]
+pub mod webhook;
@@ -0,0 +1,494 @@
+use async_trait::async_trait;
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{InternalStepStatus, Metrics, StepStatus, TestStatus};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric, number_data_point, summary_data_point, AggregationTemporality, Gauge, Histogram,
+    HistogramDataPoint, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum, Summary,
+    SummaryDataPoint,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+#[derive(Clone)]
+pub struct OtlpSink {
+    pub endpoint: String,
+    pub service_name: String,
+    client: Client,
+    headers: HeaderMap,
+    start_times: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+struct DataPointContext {
+    attributes: Vec<KeyValue>,
+    start_time: u64,
+    time: u64,
+}
+
+impl DataPointContext {
+    fn number(&self, value: number_data_point::Value, extra: Vec<KeyValue>) -> NumberDataPoint {
+        NumberDataPoint {
+            attributes: [self.attributes.to_owned(), extra].concat(),
+            start_time_unix_nano: self.start_time,
+            time_unix_nano: self.time,
+            value: Some(value),
+            ..Default::default()
+        }
+    }
+
+    fn gauge(&self, name: &str, unit: &str, value: f64) -> Metric {
+        Metric {
+            name: name.to_owned(),
+            unit: unit.to_owned(),
+            data: Some(metric::Data::Gauge(Gauge {
+                data_points: vec![self.number(number_data_point::Value::AsDouble(value), vec![])],
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn counter(&self, name: &str, values: Vec<(Vec<KeyValue>, u128)>) -> Metric {
+        Metric {
+            name: name.to_owned(),
+            unit: String::from("1"),
+            data: Some(metric::Data::Sum(Sum {
+                data_points: values
+                    .into_iter()
+                    .map(|(extra, value)| {
+                        self.number(number_data_point::Value::AsInt(value as i64), extra)
+                    })
+                    .collect(),
+                aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                is_monotonic: true,
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+impl OtlpSink {
+    const SCOPE_NAME: &'static str = "loady";
+    const PROTOBUF_CONTENT_TYPE: &'static str = "application/x-protobuf";
+
+    pub(crate) fn new(
+        endpoint: String,
+        service_name: String,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("The OTLP sink client can't be built");
+
+        Self {
+            endpoint,
+            service_name,
+            client,
+            headers,
+            start_times: Arc::new(Mutex::new(HashMap::default())),
+        }
+    }
+
+    async fn export(&self, step_status: &StepStatus) -> SinkResult {
+        let time = Self::now();
+        let start_time = *self
+            .start_times
+            .lock()
+            .await
+            .entry(step_status.session_id.to_owned())
+            .or_insert(time.saturating_sub(
+                (step_status.metrics.test_duration as u64).saturating_mul(1_000_000),
+            ));
+
+        let request = self.to_request(step_status, start_time, time);
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .headers(self.headers.to_owned())
+            .header(CONTENT_TYPE, Self::PROTOBUF_CONTENT_TYPE)
+            .body(request.encode_to_vec())
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(SinkError::new(format!(
+                "The OTLP collector has rejected the metrics with status {}",
+                response.status()
+            ))),
+            Err(error) => Err(SinkError::new(format!(
+                "The metrics couldn't be sent to the OTLP collector: {error}"
+            ))),
+        }
+    }
+
+    fn to_request(
+        &self,
+        step_status: &StepStatus,
+        start_time: u64,
+        time: u64,
+    ) -> ExportMetricsServiceRequest {
+        let resource = Resource {
+            attributes: vec![
+                Self::attribute("service.name", &self.service_name),
+                Self::attribute("loady.test.name", &step_status.test_name),
+                Self::attribute("loady.test.suite", &step_status.test_suite),
+                Self::attribute("loady.session.id", &step_status.session_id),
+            ],
+            ..Default::default()
+        };
+
+        let mut attributes = vec![Self::attribute("loady.step", &step_status.step_name)];
+        if !step_status.stage_name.is_empty() {
+            attributes.push(Self::attribute("loady.stage", &step_status.stage_name));
+        }
+
+        let context = DataPointContext {
+            attributes,
+            start_time,
+            time,
+        };
+
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(resource),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(InstrumentationScope {
+                        name: Self::SCOPE_NAME.to_owned(),
+                        version: env!("CARGO_PKG_VERSION").to_owned(),
+                        ..Default::default()
+                    }),
+                    metrics: Self::to_metrics(&step_status.metrics, &context),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn to_metrics(metrics: &Metrics, context: &DataPointContext) -> Vec<Metric> {
+        let errors = metrics
+            .errors
+            .iter()
+            .map(|(code, count)| {
+                (
+                    vec![Self::attribute("error.code", &code.to_string())],
+                    *count,
+                )
+            })
+            .collect();
+
+        vec![
+            context.counter(
+                "loady.hits",
+                vec![
+                    (
+                        vec![Self::attribute("outcome", "success")],
+                        metrics.positive_hits,
+                    ),
+                    (
+                        vec![Self::attribute("outcome", "failure")],
+                        metrics.negative_hits,
+                    ),
+                ],
+            ),
+            context.counter("loady.errors", errors),
+            Self::to_histogram(metrics, context),
+            Self::to_summary(metrics, context),
+            context.gauge("loady.latency.std_dev", "ms", metrics.std_dev as f64),
+            context.gauge(
+                "loady.requests_per_second",
+                "{request}/s",
+                metrics.request_per_sec,
+            ),
+            context.gauge("loady.in_flight", "{request}", metrics.in_flight as f64),
+            context.gauge("loady.test.duration", "ms", metrics.test_duration as f64),
+            context.gauge("loady.load.duration", "ms", metrics.load_duration as f64),
+        ]
+    }
+
+    fn to_histogram(metrics: &Metrics, context: &DataPointContext) -> Metric {
+        let histogram = &metrics.latency_histogram;
+        let mut previous = 0u128;
+        let mut bucket_counts = histogram
+            .buckets
+            .iter()
+            .map(|bucket| {
+                let count = bucket.count.saturating_sub(previous);
+                previous = bucket.count;
+                count as u64
+            })
+            .collect::<Vec<u64>>();
+        bucket_counts.push(histogram.count.saturating_sub(previous) as u64);
+
+        Metric {
+            name: String::from("loady.latency"),
+            unit: String::from("ms"),
+            data: Some(metric::Data::Histogram(Histogram {
+                data_points: vec![HistogramDataPoint {
+                    attributes: context.attributes.to_owned(),
+                    start_time_unix_nano: context.start_time,
+                    time_unix_nano: context.time,
+                    count: histogram.count as u64,
+                    sum: Some(histogram.sum as f64),
+                    bucket_counts,
+                    explicit_bounds: histogram
+                        .buckets
+                        .iter()
+                        .map(|bucket| bucket.le as f64)
+                        .collect(),
+                    min: Some(metrics.min_time as f64),
+                    max: Some(metrics.max_time as f64),
+                    ..Default::default()
+                }],
+                aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn to_summary(metrics: &Metrics, context: &DataPointContext) -> Metric {
+        let quantile = |quantile: f64, value: u128| summary_data_point::ValueAtQuantile {
+            quantile,
+            value: value as f64,
+        };
+
+        Metric {
+            name: String::from("loady.latency.summary"),
+            unit: String::from("ms"),
+            data: Some(metric::Data::Summary(Summary {
+                data_points: vec![SummaryDataPoint {
+                    attributes: context.attributes.to_owned(),
+                    start_time_unix_nano: context.start_time,
+                    time_unix_nano: context.time,
+                    count: metrics.latency_histogram.count as u64,
+                    sum: metrics.latency_histogram.sum as f64,
+                    quantile_values: vec![
+                        quantile(0f64, metrics.min_time),
+                        quantile(0.9, metrics.p90_time),
+                        quantile(0.95, metrics.p95_time),
+                        quantile(0.99, metrics.p99_time),
+                        quantile(1f64, metrics.max_time),
+                    ],
+                    ..Default::default()
+                }],
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn attribute(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_owned(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_owned())),
+            }),
+        }
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl ReportingSink for OtlpSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.start_times
+            .lock()
+            .await
+            .remove(&test_status.session_id);
+        Ok(())
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.export(&step_status).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.export(&step_status).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::otlp::utils::OtlpSinkBuilder;
+    use loady::core::stats::{CustomMetrics, LatencyHistogram};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn step_status() -> StepStatus {
+        let mut latency_histogram = LatencyHistogram::default();
+        [4, 20, 20, 300]
+            .iter()
+            .for_each(|time| latency_histogram.add(*time));
+
+        StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("step"),
+            stage_name: String::from("stage"),
+            metrics: Metrics {
+                test_duration: 1000,
+                load_duration: 900,
+                mean_time: 86,
+                max_time: 300,
+                min_time: 4,
+                std_dev: 120,
+                p90_time: 236,
+                p95_time: 268,
+                p99_time: 293,
+                positive_hits: 3,
+                negative_hits: 1,
+                all_hits: 4,
+                request_per_sec: 4.4,
+                in_flight: 1,
+                errors: HashMap::from([(503, 1)]),
+                latency_histogram,
+                transactions: Vec::new(),
+                response_time: Default::default(),
+                schedule_lag: Default::default(),
+                generator: Default::default(),
+            },
+            actions: Vec::new(),
+            custom_metrics: CustomMetrics::default(),
+        }
+    }
+
+    async fn collect(listener: TcpListener) -> (String, ExportMetricsServiceRequest) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+
+        let header_end = loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            if let Some(index) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break index + 4;
+            }
+        };
+
+        let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+        let content_length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap();
+
+        while request.len() < header_end + content_length {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .await
+            .unwrap();
+
+        let body = &request[header_end..header_end + content_length];
+        (headers, ExportMetricsServiceRequest::decode(body).unwrap())
+    }
+
+    fn find_metric<'a>(request: &'a ExportMetricsServiceRequest, name: &str) -> &'a Metric {
+        request.resource_metrics[0].scope_metrics[0]
+            .metrics
+            .iter()
+            .find(|metric| metric.name == name)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn given_collector_when_exporting_then_sends_protobuf_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let collector = tokio::spawn(collect(listener));
+        let sink = OtlpSinkBuilder::default()
+            .with_endpoint(&endpoint)
+            .with_service_name("checkout")
+            .with_header("x-api-key", "secret")
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        let (headers, request) = collector.await.unwrap();
+
+        assert!(headers.starts_with("post /v1/metrics"));
+        assert!(headers.contains("content-type: application/x-protobuf"));
+        assert!(headers.contains("x-api-key: secret"));
+
+        let resource = request.resource_metrics[0].resource.as_ref().unwrap();
+        let resource_attributes = resource
+            .attributes
+            .iter()
+            .map(|attribute| attribute.key.as_str())
+            .collect::<Vec<&str>>();
+        assert!(resource
+            .attributes
+            .contains(&OtlpSink::attribute("service.name", "checkout")));
+        assert!(resource_attributes.contains(&"loady.session.id"));
+        assert!(resource_attributes.contains(&"loady.test.suite"));
+
+        match &find_metric(&request, "loady.hits").data {
+            Some(metric::Data::Sum(sum)) => {
+                assert!(sum.is_monotonic);
+                assert_eq!(sum.data_points.len(), 2);
+                assert_eq!(
+                    sum.data_points[0].value,
+                    Some(number_data_point::Value::AsInt(3))
+                );
+            }
+            _ => panic!("loady.hits must be a sum"),
+        }
+
+        match &find_metric(&request, "loady.latency").data {
+            Some(metric::Data::Histogram(histogram)) => {
+                let point = &histogram.data_points[0];
+                assert_eq!(point.count, 4);
+                assert_eq!(point.bucket_counts.iter().sum::<u64>(), 4);
+                assert_eq!(point.bucket_counts.len(), point.explicit_bounds.len() + 1);
+            }
+            _ => panic!("loady.latency must be a histogram"),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_unreachable_collector_when_exporting_then_returns_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let sink = OtlpSinkBuilder::default()
+            .with_endpoint(&endpoint)
+            .with_timeout(Duration::from_secs(1))
+            .build();
+
+        let result = sink.on_load_step_ended(step_status()).await;
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_endpoint_with_metrics_path_when_building_then_keeps_endpoint() {
+        let sink = OtlpSinkBuilder::default()
+            .with_endpoint("http://collector:4318/v1/metrics")
+            .build();
+
+        assert_eq!(sink.endpoint, "http://collector:4318/v1/metrics");
+    }
+}
@@ -0,0 +1,60 @@
+use super::sink::OtlpSink;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct OtlpSinkBuilder {
+    endpoint: Option<String>,
+    service_name: Option<String>,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+}
+
+impl OtlpSinkBuilder {
+    const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+    const DEFAULT_SERVICE_NAME: &str = "loady";
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+    const METRICS_PATH: &str = "/v1/metrics";
+
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_owned());
+        self
+    }
+
+    pub fn with_service_name(mut self, service_name: &str) -> Self {
+        self.service_name = Some(service_name.to_owned());
+        self
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        let key = HeaderName::from_bytes(key.as_bytes())
+            .expect("The header name provided for the OTLP sink is malformed");
+        let value = HeaderValue::from_str(value)
+            .expect("The header value provided for the OTLP sink is malformed");
+        self.headers.insert(key, value);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> OtlpSink {
+        let endpoint = self
+            .endpoint
+            .unwrap_or(String::from(Self::DEFAULT_ENDPOINT));
+        let endpoint = match endpoint.trim_end_matches('/') {
+            url if url.ends_with(Self::METRICS_PATH) => url.to_owned(),
+            url => format!("{}{}", url, Self::METRICS_PATH),
+        };
+
+        OtlpSink::new(
+            endpoint,
+            self.service_name
+                .unwrap_or(String::from(Self::DEFAULT_SERVICE_NAME)),
+            self.headers,
+            self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT),
+        )
+    }
+}
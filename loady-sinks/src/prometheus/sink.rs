@@ -0,0 +1,558 @@
+use async_trait::async_trait;
+use loady::core::reporting::{ReportingSink, SinkResult};
+use loady::core::stats::{InternalStepStatus, Metrics, StepStatus, TestStatus};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+#[derive(Clone)]
+pub struct PrometheusSink {
+    pub address: SocketAddr,
+    registry: Arc<Mutex<Registry>>,
+    _server: Arc<Server>,
+}
+
+struct Server {
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct Registry {
+    namespace: String,
+    snapshots: HashMap<(String, String), Metrics>,
+    hits: BTreeMap<(SeriesLabels, &'static str), u128>,
+    errors: BTreeMap<(SeriesLabels, i32), u128>,
+    latency: BTreeMap<SeriesLabels, Histogram>,
+    in_flight: BTreeMap<SeriesLabels, u128>,
+    stages: BTreeMap<SeriesLabels, u8>,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesLabels {
+    test_name: String,
+    test_suite: String,
+    step_name: String,
+    stage_name: String,
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: BTreeMap<u128, u128>,
+    sum: u128,
+    count: u128,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl SeriesLabels {
+    fn new(step_status: &StepStatus) -> Self {
+        Self {
+            test_name: step_status.test_name.to_owned(),
+            test_suite: step_status.test_suite.to_owned(),
+            step_name: step_status.step_name.to_owned(),
+            stage_name: step_status.stage_name.to_owned(),
+        }
+    }
+
+    fn without_stage(&self) -> Self {
+        Self {
+            stage_name: String::default(),
+            ..self.to_owned()
+        }
+    }
+
+    fn is_same_step(&self, other: &Self) -> bool {
+        self.test_name == other.test_name
+            && self.test_suite == other.test_suite
+            && self.step_name == other.step_name
+    }
+
+    fn format(&self, extra: &[(&str, String)]) -> String {
+        let mut labels = vec![
+            ("test_name", self.test_name.to_owned()),
+            ("test_suite", self.test_suite.to_owned()),
+            ("step", self.step_name.to_owned()),
+        ];
+
+        if !self.stage_name.is_empty() {
+            labels.push(("stage", self.stage_name.to_owned()));
+        }
+
+        labels.extend(extra.iter().map(|(key, value)| (*key, value.to_owned())));
+
+        let content = labels
+            .iter()
+            .map(|(key, value)| format!("{}=\"{}\"", key, Self::escape(value)))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!("{{{}}}", content)
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
+}
+
+impl Registry {
+    const SUCCESS: &'static str = "success";
+    const FAILURE: &'static str = "failure";
+
+    fn new(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn track(&mut self, step_status: &StepStatus) {
+        let labels = SeriesLabels::new(step_status);
+        let current = &step_status.metrics;
+        let key = (
+            step_status.session_id.to_owned(),
+            step_status.step_name.to_owned(),
+        );
+        let previous = self.snapshots.get(&key);
+        let delta = |current: u128, previous: Option<u128>| {
+            current.saturating_sub(previous.unwrap_or_default())
+        };
+
+        let successful = delta(current.positive_hits, previous.map(|m| m.positive_hits));
+        let unsuccessful = delta(current.negative_hits, previous.map(|m| m.negative_hits));
+        *self
+            .hits
+            .entry((labels.to_owned(), Self::SUCCESS))
+            .or_default() += successful;
+        *self
+            .hits
+            .entry((labels.to_owned(), Self::FAILURE))
+            .or_default() += unsuccessful;
+
+        for (code, count) in &current.errors {
+            let previous = previous.and_then(|m| m.errors.get(code).copied());
+            *self.errors.entry((labels.to_owned(), *code)).or_default() += delta(*count, previous);
+        }
+
+        let histogram = self.latency.entry(labels.to_owned()).or_default();
+        for bucket in &current.latency_histogram.buckets {
+            let previous = previous.and_then(|m| {
+                m.latency_histogram
+                    .buckets
+                    .iter()
+                    .find(|previous| previous.le == bucket.le)
+                    .map(|previous| previous.count)
+            });
+            *histogram.buckets.entry(bucket.le).or_default() += delta(bucket.count, previous);
+        }
+        histogram.sum += delta(
+            current.latency_histogram.sum,
+            previous.map(|m| m.latency_histogram.sum),
+        );
+        histogram.count += delta(
+            current.latency_histogram.count,
+            previous.map(|m| m.latency_histogram.count),
+        );
+
+        self.in_flight
+            .insert(labels.without_stage(), current.in_flight);
+        self.set_current_stage(&labels, true);
+        self.snapshots.insert(key, current.to_owned());
+    }
+
+    fn end_step(&mut self, step_status: &StepStatus) {
+        let labels = SeriesLabels::new(step_status);
+        self.in_flight.insert(labels.without_stage(), 0);
+        self.set_current_stage(&labels, false);
+    }
+
+    fn end_test(&mut self, test_status: &TestStatus) {
+        self.snapshots
+            .retain(|(session_id, _), _| *session_id != test_status.session_id);
+    }
+
+    fn set_current_stage(&mut self, labels: &SeriesLabels, is_active: bool) {
+        self.stages
+            .iter_mut()
+            .filter(|(stage, _)| stage.is_same_step(labels))
+            .for_each(|(_, value)| *value = 0);
+
+        if !labels.stage_name.is_empty() {
+            self.stages.insert(labels.to_owned(), is_active as u8);
+        }
+    }
+
+    fn render(&self) -> String {
+        let namespace = &self.namespace;
+        let mut content = String::default();
+
+        content += &format!(
+            "# HELP {namespace}_hits_total Number of hits by outcome.\n# TYPE {namespace}_hits_total counter\n"
+        );
+        for ((labels, outcome), value) in &self.hits {
+            content += &format!(
+                "{namespace}_hits_total{} {}\n",
+                labels.format(&[("outcome", outcome.to_string())]),
+                value
+            );
+        }
+
+        content += &format!(
+            "# HELP {namespace}_errors_total Number of unsuccessful hits by error code.\n# TYPE {namespace}_errors_total counter\n"
+        );
+        for ((labels, code), value) in &self.errors {
+            content += &format!(
+                "{namespace}_errors_total{} {}\n",
+                labels.format(&[("code", code.to_string())]),
+                value
+            );
+        }
+
+        content += &format!(
+            "# HELP {namespace}_latency_seconds Latency of the hits.\n# TYPE {namespace}_latency_seconds histogram\n"
+        );
+        for (labels, histogram) in &self.latency {
+            for (le, count) in &histogram.buckets {
+                content += &format!(
+                    "{namespace}_latency_seconds_bucket{} {}\n",
+                    labels.format(&[("le", Self::as_seconds(*le).to_string())]),
+                    count
+                );
+            }
+            content += &format!(
+                "{namespace}_latency_seconds_bucket{} {}\n",
+                labels.format(&[("le", String::from("+Inf"))]),
+                histogram.count
+            );
+            content += &format!(
+                "{namespace}_latency_seconds_sum{} {}\n",
+                labels.format(&[]),
+                Self::as_seconds(histogram.sum)
+            );
+            content += &format!(
+                "{namespace}_latency_seconds_count{} {}\n",
+                labels.format(&[]),
+                histogram.count
+            );
+        }
+
+        content += &format!(
+            "# HELP {namespace}_in_flight_requests Number of hits waiting for a response.\n# TYPE {namespace}_in_flight_requests gauge\n"
+        );
+        for (labels, value) in &self.in_flight {
+            content += &format!(
+                "{namespace}_in_flight_requests{} {}\n",
+                labels.format(&[]),
+                value
+            );
+        }
+
+        content += &format!(
+            "# HELP {namespace}_current_stage Whether the stage is currently running.\n# TYPE {namespace}_current_stage gauge\n"
+        );
+        for (labels, value) in &self.stages {
+            content += &format!(
+                "{namespace}_current_stage{} {}\n",
+                labels.format(&[]),
+                value
+            );
+        }
+
+        content
+    }
+
+    fn as_seconds(millis: u128) -> f64 {
+        millis as f64 / 1000f64
+    }
+}
+
+impl PrometheusSink {
+    const METRICS_PATH: &'static str = "/metrics";
+
+    pub(crate) fn start(listener: TcpListener, namespace: &str) -> std::io::Result<Self> {
+        let address = listener.local_addr()?;
+        let registry = Arc::new(Mutex::new(Registry::new(namespace)));
+        let handle = tokio::spawn(Self::serve(listener, Arc::clone(&registry)));
+
+        Ok(Self {
+            address,
+            registry,
+            _server: Arc::new(Server { handle }),
+        })
+    }
+
+    pub async fn render(&self) -> String {
+        self.registry.lock().await.render()
+    }
+
+    async fn serve(listener: TcpListener, registry: Arc<Mutex<Registry>>) {
+        loop {
+            if let Ok((stream, _)) = listener.accept().await {
+                let registry = Arc::clone(&registry);
+                tokio::spawn(async move {
+                    _ = Self::respond(stream, registry).await;
+                });
+            }
+        }
+    }
+
+    async fn respond(mut stream: TcpStream, registry: Arc<Mutex<Registry>>) -> std::io::Result<()> {
+        let mut buffer = [0u8; 1024];
+        let read = stream.read(&mut buffer).await?;
+        let request = String::from_utf8_lossy(&buffer[..read]);
+        let mut request_line = request.split_whitespace();
+
+        let response = match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some(path)) if path.split('?').next() == Some(Self::METRICS_PATH) => {
+                let content = registry.lock().await.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    content.len(),
+                    content
+                )
+            }
+            _ => String::from(
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            ),
+        };
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+#[async_trait]
+impl ReportingSink for PrometheusSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.registry.lock().await.end_test(&test_status);
+        Ok(())
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        let mut registry = self.registry.lock().await;
+        registry.track(&step_status);
+        registry.end_step(&step_status);
+        Ok(())
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.registry.lock().await.track(&step_status);
+        Ok(())
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::prometheus::utils::PrometheusSinkBuilder;
+    use crate::test_utils::run_sequential_steps;
+    use loady::core::stats::{CustomMetrics, LatencyHistogram};
+
+    fn step_status(stage_name: &str, positive_hits: u128, errors: &[(i32, u128)]) -> StepStatus {
+        let mut latency_histogram = LatencyHistogram::default();
+        let negative_hits = errors.iter().map(|(_, count)| count).sum::<u128>();
+        (0..positive_hits + negative_hits).for_each(|_| latency_histogram.add(20));
+
+        StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("step"),
+            stage_name: stage_name.to_owned(),
+            metrics: Metrics {
+                test_duration: 0,
+                load_duration: 0,
+                mean_time: 20,
+                max_time: 20,
+                min_time: 20,
+                std_dev: 0,
+                p90_time: 20,
+                p95_time: 20,
+                p99_time: 20,
+                positive_hits,
+                negative_hits,
+                all_hits: positive_hits + negative_hits,
+                request_per_sec: 0f64,
+                in_flight: 3,
+                errors: errors.iter().copied().collect(),
+                latency_histogram,
+                transactions: Vec::new(),
+                response_time: Default::default(),
+                schedule_lag: Default::default(),
+                generator: Default::default(),
+            },
+            actions: Vec::new(),
+            custom_metrics: CustomMetrics::default(),
+        }
+    }
+
+    #[test]
+    fn given_snapshots_when_tracking_then_counters_increase_by_delta() {
+        let mut registry = Registry::new("loady");
+
+        registry.track(&step_status("first", 5, &[(500, 1)]));
+        registry.track(&step_status("second", 8, &[(500, 3)]));
+
+        let content = registry.render();
+
+        assert!(content.contains(
+            "loady_hits_total{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"first\",outcome=\"success\"} 5"
+        ));
+        assert!(content.contains(
+            "loady_hits_total{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"second\",outcome=\"success\"} 3"
+        ));
+        assert!(content.contains(
+            "loady_errors_total{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"second\",code=\"500\"} 2"
+        ));
+        assert!(content.contains(
+            "loady_latency_seconds_bucket{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"second\",le=\"0.025\"} 5"
+        ));
+        assert!(content.contains(
+            "loady_in_flight_requests{test_name=\"test\",test_suite=\"suite\",step=\"step\"} 3"
+        ));
+        assert!(content.contains(
+            "loady_current_stage{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"first\"} 0"
+        ));
+        assert!(content.contains(
+            "loady_current_stage{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"second\"} 1"
+        ));
+    }
+
+    #[test]
+    fn given_interleaved_steps_when_tracking_then_counters_increase_by_delta_per_step() {
+        let other_step = |positive_hits| StepStatus {
+            step_name: String::from("other"),
+            ..step_status("first", positive_hits, &[])
+        };
+        let mut registry = Registry::new("loady");
+
+        registry.track(&step_status("first", 5, &[]));
+        registry.track(&other_step(2));
+        registry.track(&step_status("first", 8, &[]));
+        registry.track(&other_step(3));
+
+        let content = registry.render();
+
+        assert!(content.contains(
+            "loady_hits_total{test_name=\"test\",test_suite=\"suite\",step=\"step\",stage=\"first\",outcome=\"success\"} 8"
+        ));
+        assert!(content.contains(
+            "loady_hits_total{test_name=\"test\",test_suite=\"suite\",step=\"other\",stage=\"first\",outcome=\"success\"} 3"
+        ));
+    }
+
+    #[tokio::test]
+    async fn given_sequential_load_steps_when_running_then_counts_every_hit_once() {
+        let sink = PrometheusSinkBuilder::default()
+            .with_address("127.0.0.1:0")
+            .build()
+            .unwrap();
+
+        let test_status = run_sequential_steps(sink.clone()).await;
+        let content = sink.render().await;
+
+        let total = |prefix: &str| {
+            content
+                .lines()
+                .filter(|line| line.starts_with(prefix))
+                .filter_map(|line| line.rsplit(' ').next()?.parse::<u128>().ok())
+                .sum::<u128>()
+        };
+        let metrics = &test_status.metrics;
+        assert_eq!(total("loady_hits_total{"), metrics.all_hits);
+        assert_eq!(total("loady_errors_total{"), metrics.negative_hits);
+        assert_eq!(total("loady_latency_seconds_count{"), metrics.all_hits);
+        assert!(content.contains("step=\"second\""));
+    }
+
+    #[tokio::test]
+    async fn given_address_in_use_when_building_then_returns_error() {
+        let sink = PrometheusSinkBuilder::default()
+            .with_address("127.0.0.1:0")
+            .build()
+            .unwrap();
+
+        let actual = PrometheusSinkBuilder::default()
+            .with_address(&sink.address.to_string())
+            .build();
+
+        assert_eq!(actual.err().unwrap().kind(), std::io::ErrorKind::AddrInUse);
+    }
+
+    #[test]
+    fn given_no_runtime_when_building_then_returns_error() {
+        let actual = PrometheusSinkBuilder::default()
+            .with_address("127.0.0.1:0")
+            .build();
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn given_label_with_quotes_when_formatting_then_escapes_value() {
+        let labels = SeriesLabels {
+            test_name: String::from("a \"quoted\" name"),
+            ..Default::default()
+        };
+
+        assert!(labels
+            .format(&[])
+            .contains("test_name=\"a \\\"quoted\\\" name\""));
+    }
+
+    #[tokio::test]
+    async fn given_running_sink_when_scraping_then_returns_exposition_format() {
+        let sink = PrometheusSinkBuilder::default()
+            .with_address("127.0.0.1:0")
+            .build()
+            .unwrap();
+        sink.on_load_action_ended(step_status("first", 2, &[]))
+            .await
+            .unwrap();
+
+        let mut stream = TcpStream::connect(sink.address).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::default();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE loady_hits_total counter"));
+        assert!(response.contains("outcome=\"success\"} 2"));
+    }
+
+    #[tokio::test]
+    async fn given_running_sink_when_requesting_unknown_path_then_returns_not_found() {
+        let sink = PrometheusSinkBuilder::default()
+            .with_address("127.0.0.1:0")
+            .build()
+            .unwrap();
+
+        let mut stream = TcpStream::connect(sink.address).await.unwrap();
+        stream
+            .write_all(b"GET /other HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::default();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
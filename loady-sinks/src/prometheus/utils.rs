@@ -0,0 +1,45 @@
+use super::sink::PrometheusSink;
+use std::io::Error;
+use tokio::net::TcpListener;
+use tokio::runtime::Handle;
+
+#[derive(Default)]
+pub struct PrometheusSinkBuilder {
+    address: Option<String>,
+    namespace: Option<String>,
+}
+
+impl PrometheusSinkBuilder {
+    const DEFAULT_ADDRESS: &str = "0.0.0.0:9185";
+    const DEFAULT_NAMESPACE: &str = "loady";
+
+    pub fn with_address(mut self, address: &str) -> Self {
+        self.address = Some(address.to_owned());
+        self
+    }
+
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_owned());
+        self
+    }
+
+    /// Builds the sink and starts serving the metrics, failing when the address can't be bound or
+    /// when it isn't built within a Tokio runtime.
+    pub fn build(self) -> std::io::Result<PrometheusSink> {
+        if Handle::try_current().is_err() {
+            return Err(Error::other(
+                "The Prometheus sink must be built within a Tokio runtime",
+            ));
+        }
+
+        let address = self.address.unwrap_or(String::from(Self::DEFAULT_ADDRESS));
+        let namespace = self
+            .namespace
+            .unwrap_or(String::from(Self::DEFAULT_NAMESPACE));
+        let listener = std::net::TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+
+        PrometheusSink::start(listener, &namespace)
+    }
+}
@@ -0,0 +1,752 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{
+    ActionStatus, CustomMetrics, GeneratorHealth, InternalStepStatus, LatencyHistogram, Metrics,
+    StepStatus, TestStatus, TimingSummary, TransactionStatus,
+};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+#[derive(Clone)]
+pub struct SqliteSink {
+    connection: Arc<Mutex<Connection>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    pub created_at: String,
+    pub test_status: TestStatus,
+}
+
+#[derive(Clone, Debug)]
+pub struct SnapshotRecord {
+    pub created_at: String,
+    pub is_final: bool,
+    pub step_status: StepStatus,
+}
+
+#[derive(Clone, Debug)]
+pub struct RunHistory {
+    pub run: RunRecord,
+    pub snapshots: Vec<SnapshotRecord>,
+}
+
+/// Parts of a status without a column of their own, stored as JSON.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StatusDetails {
+    transactions: Vec<TransactionStatus>,
+    response_time: TimingSummary,
+    schedule_lag: TimingSummary,
+    generator: GeneratorHealth,
+    actions: Vec<ActionStatus>,
+    custom_metrics: CustomMetrics,
+}
+
+impl StatusDetails {
+    fn new(metrics: &Metrics, actions: &[ActionStatus], custom_metrics: &CustomMetrics) -> Self {
+        Self {
+            transactions: metrics.transactions.to_owned(),
+            response_time: metrics.response_time.to_owned(),
+            schedule_lag: metrics.schedule_lag.to_owned(),
+            generator: metrics.generator.to_owned(),
+            actions: actions.to_vec(),
+            custom_metrics: custom_metrics.to_owned(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl SqliteSink {
+    pub const SCHEMA_VERSION: i64 = 2;
+
+    const SCHEMA: &'static str = "
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS runs (
+            session_id TEXT PRIMARY KEY,
+            test_name TEXT NOT NULL,
+            test_suite TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            test_duration INTEGER NOT NULL,
+            load_duration INTEGER NOT NULL,
+            mean_time INTEGER NOT NULL,
+            max_time INTEGER NOT NULL,
+            min_time INTEGER NOT NULL,
+            std_dev INTEGER NOT NULL,
+            p90_time INTEGER NOT NULL,
+            p95_time INTEGER NOT NULL,
+            p99_time INTEGER NOT NULL,
+            positive_hits INTEGER NOT NULL,
+            negative_hits INTEGER NOT NULL,
+            all_hits INTEGER NOT NULL,
+            request_per_sec REAL NOT NULL,
+            in_flight INTEGER NOT NULL,
+            latency_histogram TEXT NOT NULL,
+            details TEXT NOT NULL DEFAULT '{}'
+        );
+        CREATE TABLE IF NOT EXISTS step_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            test_name TEXT NOT NULL,
+            test_suite TEXT NOT NULL,
+            step_name TEXT NOT NULL,
+            stage_name TEXT NOT NULL,
+            is_final INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            test_duration INTEGER NOT NULL,
+            load_duration INTEGER NOT NULL,
+            mean_time INTEGER NOT NULL,
+            max_time INTEGER NOT NULL,
+            min_time INTEGER NOT NULL,
+            std_dev INTEGER NOT NULL,
+            p90_time INTEGER NOT NULL,
+            p95_time INTEGER NOT NULL,
+            p99_time INTEGER NOT NULL,
+            positive_hits INTEGER NOT NULL,
+            negative_hits INTEGER NOT NULL,
+            all_hits INTEGER NOT NULL,
+            request_per_sec REAL NOT NULL,
+            in_flight INTEGER NOT NULL,
+            latency_histogram TEXT NOT NULL,
+            details TEXT NOT NULL DEFAULT '{}'
+        );
+        CREATE TABLE IF NOT EXISTS errors (
+            session_id TEXT NOT NULL,
+            snapshot_id INTEGER NULL REFERENCES step_snapshots(id),
+            code INTEGER NOT NULL,
+            count INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS step_snapshots_session_id ON step_snapshots(session_id);
+        CREATE INDEX IF NOT EXISTS errors_session_id ON errors(session_id);
+    ";
+
+    /// Adds the details of the statuses to a database created with the first schema version.
+    const DETAILS_MIGRATION: &'static str = "
+        ALTER TABLE runs ADD COLUMN details TEXT NOT NULL DEFAULT '{}';
+        ALTER TABLE step_snapshots ADD COLUMN details TEXT NOT NULL DEFAULT '{}';
+    ";
+
+    const METRIC_COLUMNS: &'static str = "test_duration, load_duration, mean_time, max_time, min_time, std_dev, p90_time, p95_time, p99_time, positive_hits, negative_hits, all_hits, request_per_sec, in_flight, latency_histogram, details";
+
+    pub(crate) fn new(connection: Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(Self::SCHEMA)?;
+        let version: Option<i64> = connection
+            .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+            .optional()?;
+
+        match version {
+            None => {
+                connection.execute(
+                    "INSERT INTO schema_version (version) VALUES (?1)",
+                    params![Self::SCHEMA_VERSION],
+                )?;
+            }
+            Some(1) => {
+                let transaction = connection.unchecked_transaction()?;
+                transaction.execute_batch(Self::DETAILS_MIGRATION)?;
+                transaction.execute(
+                    "UPDATE schema_version SET version = ?1",
+                    params![Self::SCHEMA_VERSION],
+                )?;
+                transaction.commit()?;
+            }
+            Some(_) => {}
+        }
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    pub fn list_runs(&self) -> rusqlite::Result<Vec<RunRecord>> {
+        let connection = Self::lock(&self.connection);
+        let mut statement = connection.prepare(&format!(
+            "SELECT session_id, test_name, test_suite, created_at, {} FROM runs ORDER BY created_at DESC",
+            Self::METRIC_COLUMNS
+        ))?;
+        let runs = statement
+            .query_map([], Self::to_run_record)?
+            .collect::<rusqlite::Result<Vec<RunRecord>>>()?;
+
+        runs.into_iter()
+            .map(|mut run| {
+                run.test_status.metrics.errors =
+                    Self::get_errors(&connection, &run.test_status.session_id, None)?;
+                Ok(run)
+            })
+            .collect()
+    }
+
+    pub fn get_run(&self, session_id: &str) -> rusqlite::Result<Option<RunHistory>> {
+        let connection = Self::lock(&self.connection);
+        let run = connection
+            .query_row(
+                &format!(
+                    "SELECT session_id, test_name, test_suite, created_at, {} FROM runs WHERE session_id = ?1",
+                    Self::METRIC_COLUMNS
+                ),
+                params![session_id],
+                Self::to_run_record,
+            )
+            .optional()?;
+
+        let Some(mut run) = run else {
+            return Ok(None);
+        };
+
+        run.test_status.metrics.errors = Self::get_errors(&connection, session_id, None)?;
+
+        let mut statement = connection.prepare(&format!(
+            "SELECT id, session_id, test_name, test_suite, step_name, stage_name, is_final, created_at, {} FROM step_snapshots WHERE session_id = ?1 ORDER BY id",
+            Self::METRIC_COLUMNS
+        ))?;
+        let snapshots = statement
+            .query_map(params![session_id], |row| {
+                Ok((row.get::<_, i64>(0)?, Self::to_snapshot_record(row)?))
+            })?
+            .collect::<rusqlite::Result<Vec<(i64, SnapshotRecord)>>>()?;
+
+        let snapshots = snapshots
+            .into_iter()
+            .map(|(id, mut snapshot)| {
+                snapshot.step_status.metrics.errors =
+                    Self::get_errors(&connection, session_id, Some(id))?;
+                Ok(snapshot)
+            })
+            .collect::<rusqlite::Result<Vec<SnapshotRecord>>>()?;
+
+        Ok(Some(RunHistory { run, snapshots }))
+    }
+
+    /// Stores the run, or updates it while keeping when it was first stored if its session was
+    /// already stored.
+    fn insert_run(connection: &Connection, test_status: &TestStatus) -> rusqlite::Result<()> {
+        let metrics = &test_status.metrics;
+        let updates = Self::METRIC_COLUMNS
+            .split(", ")
+            .map(|column| format!("{column} = excluded.{column}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        connection.execute(
+            &format!(
+                "INSERT INTO runs (session_id, test_name, test_suite, created_at, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20) ON CONFLICT (session_id) DO UPDATE SET test_name = excluded.test_name, test_suite = excluded.test_suite, {}",
+                Self::METRIC_COLUMNS,
+                updates
+            ),
+            params![
+                test_status.session_id,
+                test_status.test_name,
+                test_status.test_suite,
+                Self::now(),
+                metrics.test_duration as i64,
+                metrics.load_duration as i64,
+                metrics.mean_time as i64,
+                metrics.max_time as i64,
+                metrics.min_time as i64,
+                metrics.std_dev as i64,
+                metrics.p90_time as i64,
+                metrics.p95_time as i64,
+                metrics.p99_time as i64,
+                metrics.positive_hits as i64,
+                metrics.negative_hits as i64,
+                metrics.all_hits as i64,
+                metrics.request_per_sec,
+                metrics.in_flight as i64,
+                Self::to_json(&metrics.latency_histogram),
+                StatusDetails::new(metrics, &[], &CustomMetrics::default()).to_json(),
+            ],
+        )?;
+
+        connection.execute(
+            "DELETE FROM errors WHERE session_id = ?1 AND snapshot_id IS NULL",
+            params![test_status.session_id],
+        )?;
+        Self::insert_errors(connection, &test_status.session_id, None, metrics)
+    }
+
+    fn insert_snapshot(
+        connection: &Connection,
+        step_status: &StepStatus,
+        is_final: bool,
+    ) -> rusqlite::Result<()> {
+        let metrics = &step_status.metrics;
+        connection.execute(
+            &format!(
+                "INSERT INTO step_snapshots (session_id, test_name, test_suite, step_name, stage_name, is_final, created_at, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                Self::METRIC_COLUMNS
+            ),
+            params![
+                step_status.session_id,
+                step_status.test_name,
+                step_status.test_suite,
+                step_status.step_name,
+                step_status.stage_name,
+                is_final,
+                Self::now(),
+                metrics.test_duration as i64,
+                metrics.load_duration as i64,
+                metrics.mean_time as i64,
+                metrics.max_time as i64,
+                metrics.min_time as i64,
+                metrics.std_dev as i64,
+                metrics.p90_time as i64,
+                metrics.p95_time as i64,
+                metrics.p99_time as i64,
+                metrics.positive_hits as i64,
+                metrics.negative_hits as i64,
+                metrics.all_hits as i64,
+                metrics.request_per_sec,
+                metrics.in_flight as i64,
+                Self::to_json(&metrics.latency_histogram),
+                StatusDetails::new(metrics, &step_status.actions, &step_status.custom_metrics)
+                    .to_json(),
+            ],
+        )?;
+
+        let snapshot_id = connection.last_insert_rowid();
+        Self::insert_errors(
+            connection,
+            &step_status.session_id,
+            Some(snapshot_id),
+            metrics,
+        )
+    }
+
+    fn insert_errors(
+        connection: &Connection,
+        session_id: &str,
+        snapshot_id: Option<i64>,
+        metrics: &Metrics,
+    ) -> rusqlite::Result<()> {
+        let mut statement = connection.prepare(
+            "INSERT INTO errors (session_id, snapshot_id, code, count) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+
+        for (code, count) in &metrics.errors {
+            statement.execute(params![session_id, snapshot_id, code, *count as i64])?;
+        }
+
+        Ok(())
+    }
+
+    fn get_errors(
+        connection: &Connection,
+        session_id: &str,
+        snapshot_id: Option<i64>,
+    ) -> rusqlite::Result<HashMap<i32, u128>> {
+        let mut statement = connection.prepare(
+            "SELECT code, count FROM errors WHERE session_id = ?1 AND snapshot_id IS ?2",
+        )?;
+        let errors = statement
+            .query_map(params![session_id, snapshot_id], |row| {
+                Ok((row.get::<_, i32>(0)?, row.get::<_, i64>(1)? as u128))
+            })?
+            .collect::<rusqlite::Result<HashMap<i32, u128>>>()?;
+
+        Ok(errors)
+    }
+
+    fn to_run_record(row: &Row) -> rusqlite::Result<RunRecord> {
+        let (metrics, _) = Self::to_metrics(row, 4)?;
+
+        Ok(RunRecord {
+            created_at: row.get(3)?,
+            test_status: TestStatus {
+                session_id: row.get(0)?,
+                test_name: row.get(1)?,
+                test_suite: row.get(2)?,
+                metrics,
+                sinks: Vec::new(),
+            },
+        })
+    }
+
+    fn to_snapshot_record(row: &Row) -> rusqlite::Result<SnapshotRecord> {
+        let (metrics, details) = Self::to_metrics(row, 8)?;
+
+        Ok(SnapshotRecord {
+            is_final: row.get(6)?,
+            created_at: row.get(7)?,
+            step_status: StepStatus {
+                session_id: row.get(1)?,
+                test_name: row.get(2)?,
+                test_suite: row.get(3)?,
+                step_name: row.get(4)?,
+                stage_name: row.get(5)?,
+                metrics,
+                actions: details.actions,
+                custom_metrics: details.custom_metrics,
+            },
+        })
+    }
+
+    /// Reads the metrics along with the rest of the details of the status. The errors are stored
+    /// in their own table, so they're left empty.
+    fn to_metrics(row: &Row, offset: usize) -> rusqlite::Result<(Metrics, StatusDetails)> {
+        let number = |index: usize| -> rusqlite::Result<u128> {
+            Ok(row.get::<_, i64>(offset + index)? as u128)
+        };
+        let latency_histogram: String = row.get(offset + 14)?;
+        let details: String = row.get(offset + 15)?;
+        let mut details = serde_json::from_str::<StatusDetails>(&details).unwrap_or_default();
+
+        let metrics = Metrics {
+            test_duration: number(0)?,
+            load_duration: number(1)?,
+            mean_time: number(2)?,
+            max_time: number(3)?,
+            min_time: number(4)?,
+            std_dev: number(5)?,
+            p90_time: number(6)?,
+            p95_time: number(7)?,
+            p99_time: number(8)?,
+            positive_hits: number(9)?,
+            negative_hits: number(10)?,
+            all_hits: number(11)?,
+            request_per_sec: row.get(offset + 12)?,
+            in_flight: number(13)?,
+            errors: HashMap::default(),
+            latency_histogram: serde_json::from_str::<LatencyHistogram>(&latency_histogram)
+                .unwrap_or_default(),
+            transactions: std::mem::take(&mut details.transactions),
+            response_time: std::mem::take(&mut details.response_time),
+            schedule_lag: std::mem::take(&mut details.schedule_lag),
+            generator: std::mem::take(&mut details.generator),
+        };
+
+        Ok((metrics, details))
+    }
+
+    /// Keeps using the connection after a writer panicked, since every write runs within a
+    /// transaction that is rolled back unless the write completes.
+    fn lock(connection: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+        connection.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn to_json(latency_histogram: &LatencyHistogram) -> String {
+        serde_json::to_string(latency_histogram).unwrap_or_default()
+    }
+
+    fn now() -> String {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        now.to_rfc3339()
+    }
+
+    async fn write<F>(&self, operation: F) -> SinkResult
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<()> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        let result = tokio::task::spawn_blocking(move || {
+            let connection = Self::lock(&connection);
+            let transaction = connection.unchecked_transaction()?;
+            operation(&transaction)?;
+            transaction.commit()
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(error)) => Err(SinkError::new(format!(
+                "The status couldn't be stored into SQLite: {error}"
+            ))),
+            Err(error) => Err(SinkError::new(format!(
+                "The SQLite sink task has failed: {error}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl ReportingSink for SqliteSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.write(move |connection| Self::insert_run(connection, &test_status))
+            .await
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.write(move |connection| Self::insert_snapshot(connection, &step_status, true))
+            .await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.write(move |connection| Self::insert_snapshot(connection, &step_status, false))
+            .await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::sqlite::utils::SqliteSinkBuilder;
+
+    fn metrics(positive_hits: u128, errors: &[(i32, u128)]) -> Metrics {
+        let negative_hits = errors.iter().map(|(_, count)| count).sum::<u128>();
+        let mut latency_histogram = LatencyHistogram::default();
+        latency_histogram.add(42);
+
+        Metrics {
+            test_duration: 1000,
+            load_duration: 900,
+            mean_time: 42,
+            max_time: 42,
+            min_time: 42,
+            std_dev: 0,
+            p90_time: 42,
+            p95_time: 42,
+            p99_time: 42,
+            positive_hits,
+            negative_hits,
+            all_hits: positive_hits + negative_hits,
+            request_per_sec: 2.5,
+            in_flight: 0,
+            errors: errors.iter().copied().collect(),
+            latency_histogram,
+            transactions: Vec::new(),
+            response_time: Default::default(),
+            schedule_lag: Default::default(),
+            generator: Default::default(),
+        }
+    }
+
+    fn step_status(session_id: &str, positive_hits: u128, errors: &[(i32, u128)]) -> StepStatus {
+        StepStatus {
+            session_id: session_id.to_owned(),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("step"),
+            stage_name: String::from("stage"),
+            metrics: metrics(positive_hits, errors),
+            actions: Vec::new(),
+            custom_metrics: CustomMetrics::default(),
+        }
+    }
+
+    fn test_status(session_id: &str, errors: &[(i32, u128)]) -> TestStatus {
+        TestStatus {
+            session_id: session_id.to_owned(),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            metrics: metrics(10, errors),
+            sinks: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_run_when_fetching_by_session_id_then_returns_history() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+
+        sink.on_load_action_ended(step_status("first", 4, &[(500, 1)]))
+            .await
+            .unwrap();
+        sink.on_load_step_ended(step_status("first", 8, &[(500, 2), (404, 1)]))
+            .await
+            .unwrap();
+        sink.on_test_ended(test_status("first", &[(500, 2), (404, 1)]))
+            .await
+            .unwrap();
+
+        let actual = sink.get_run("first").unwrap().unwrap();
+
+        assert_eq!(actual.run.test_status.test_suite, "suite");
+        assert_eq!(actual.run.test_status.metrics.errors.get(&404), Some(&1));
+        assert_eq!(actual.run.test_status.metrics.latency_histogram.count, 1);
+        assert_eq!(actual.snapshots.len(), 2);
+        assert!(!actual.snapshots[0].is_final);
+        assert!(actual.snapshots[1].is_final);
+        assert_eq!(actual.snapshots[0].step_status.metrics.positive_hits, 4);
+        assert_eq!(actual.snapshots[0].step_status.metrics.errors.len(), 1);
+        assert_eq!(actual.snapshots[1].step_status.metrics.errors.len(), 2);
+        assert_eq!(actual.snapshots[1].step_status.stage_name, "stage");
+    }
+
+    #[tokio::test]
+    async fn given_several_runs_when_listing_then_returns_all_runs() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+
+        sink.on_test_ended(test_status("first", &[])).await.unwrap();
+        sink.on_test_ended(test_status("second", &[(500, 3)]))
+            .await
+            .unwrap();
+
+        let actual = sink.list_runs().unwrap();
+
+        assert_eq!(actual.len(), 2);
+        let second = actual
+            .iter()
+            .find(|run| run.test_status.session_id == "second")
+            .unwrap();
+        assert_eq!(second.test_status.metrics.errors.get(&500), Some(&3));
+    }
+
+    fn full_step_status(session_id: &str) -> StepStatus {
+        let timing = TimingSummary {
+            count: 12,
+            mean: 40,
+            p90: 45,
+            p95: 48,
+            p99: 50,
+            max: 52,
+        };
+        let mut metrics = metrics(12, &[(500, 2)]);
+        metrics.transactions = vec![TransactionStatus {
+            transaction_name: String::from("login"),
+            metrics: self::metrics(3, &[(401, 1)]),
+        }];
+        metrics.response_time = timing.to_owned();
+        metrics.schedule_lag = timing.to_owned();
+        metrics.generator = GeneratorHealth {
+            spawn_lag: timing,
+            delayed_hits: 2,
+            dropped_hits: 1,
+            dropped_events: 3,
+            max_in_flight: 7,
+            cpu_usage: Some(12.5),
+            rss_bytes: Some(1024),
+            warnings: vec![String::from("warning")],
+        };
+
+        let mut custom_metrics = CustomMetrics::default();
+        custom_metrics.counters.insert(String::from("orders"), 5);
+        custom_metrics.gauges.insert(String::from("queue"), 1.5);
+
+        StepStatus {
+            metrics,
+            actions: vec![ActionStatus {
+                action_name: String::from("checkout"),
+                metrics: self::metrics(6, &[(503, 1)]),
+            }],
+            custom_metrics,
+            ..step_status(session_id, 0, &[])
+        }
+    }
+
+    #[tokio::test]
+    async fn given_full_step_status_when_storing_then_round_trips_every_metric() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+        let expected = full_step_status("first");
+        let test_status = TestStatus {
+            metrics: expected.metrics.to_owned(),
+            ..test_status("first", &[])
+        };
+
+        sink.on_load_step_ended(expected.to_owned()).await.unwrap();
+        sink.on_test_ended(test_status.to_owned()).await.unwrap();
+
+        let actual = sink.get_run("first").unwrap().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&actual.run.test_status).unwrap(),
+            serde_json::to_value(&test_status).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&actual.snapshots[0].step_status).unwrap(),
+            serde_json::to_value(&expected).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn given_first_schema_version_when_opening_then_migrates_and_stores_details() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SqliteSink::SCHEMA).unwrap();
+        connection
+            .execute_batch(
+                "
+                ALTER TABLE runs DROP COLUMN details;
+                ALTER TABLE step_snapshots DROP COLUMN details;
+                INSERT INTO schema_version (version) VALUES (1);
+                ",
+            )
+            .unwrap();
+
+        let sink = SqliteSink::new(connection).unwrap();
+        sink.on_load_step_ended(full_step_status("first"))
+            .await
+            .unwrap();
+
+        let connection = SqliteSink::lock(&sink.connection);
+        let version: i64 = connection
+            .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        let details: String = connection
+            .query_row("SELECT details FROM step_snapshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SqliteSink::SCHEMA_VERSION);
+        assert!(details.contains("checkout"));
+    }
+
+    #[tokio::test]
+    async fn given_stored_run_when_storing_it_again_then_keeps_creation_time_and_replaces_errors() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+
+        sink.on_test_ended(test_status("first", &[(500, 1), (404, 2)]))
+            .await
+            .unwrap();
+        let created_at = sink.get_run("first").unwrap().unwrap().run.created_at;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        sink.on_test_ended(test_status("first", &[(500, 3)]))
+            .await
+            .unwrap();
+
+        let actual = sink.get_run("first").unwrap().unwrap().run;
+
+        assert_eq!(actual.created_at, created_at);
+        assert_eq!(actual.test_status.metrics.errors, HashMap::from([(500, 3)]));
+    }
+
+    #[tokio::test]
+    async fn given_failing_write_when_storing_then_rolls_back_every_statement() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+        sink.on_test_ended(test_status("first", &[(500, 1)]))
+            .await
+            .unwrap();
+        SqliteSink::lock(&sink.connection)
+            .execute_batch(
+                "CREATE TRIGGER reject_errors BEFORE INSERT ON errors
+                 BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+            )
+            .unwrap();
+
+        let result = sink.on_test_ended(test_status("first", &[(503, 2)])).await;
+
+        assert!(result.is_err());
+        let actual = sink.get_run("first").unwrap().unwrap().run;
+        assert_eq!(actual.test_status.metrics.errors, HashMap::from([(500, 1)]));
+        assert_eq!(actual.test_status.metrics.negative_hits, 1);
+    }
+
+    #[test]
+    fn given_poisoned_connection_when_listing_then_still_reads_runs() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+        let connection = Arc::clone(&sink.connection);
+        _ = std::thread::spawn(move || {
+            let _guard = connection.lock().unwrap();
+            panic!("writer panicked");
+        })
+        .join();
+
+        assert!(sink.connection.is_poisoned());
+        assert!(sink.list_runs().unwrap().is_empty());
+    }
+
+    #[test]
+    fn given_unknown_session_id_when_fetching_then_returns_none() {
+        let sink = SqliteSinkBuilder::default().with_in_memory().build();
+
+        assert!(sink.get_run("unknown").unwrap().is_none());
+    }
+}
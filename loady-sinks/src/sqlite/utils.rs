@@ -0,0 +1,29 @@
+use super::sink::SqliteSink;
+use rusqlite::Connection;
+
+#[derive(Default)]
+pub struct SqliteSinkBuilder {
+    path: Option<String>,
+}
+
+impl SqliteSinkBuilder {
+    const DEFAULT_PATH: &str = "loady.db";
+    const IN_MEMORY_PATH: &str = ":memory:";
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    pub fn with_in_memory(mut self) -> Self {
+        self.path = Some(Self::IN_MEMORY_PATH.to_owned());
+        self
+    }
+
+    pub fn build(self) -> SqliteSink {
+        let path = self.path.unwrap_or(String::from(Self::DEFAULT_PATH));
+        let connection = Connection::open(path).expect("The SQLite database can't be opened");
+
+        SqliteSink::new(connection).expect("The SQLite database schema can't be created")
+    }
+}
@@ -0,0 +1,461 @@
+use async_trait::async_trait;
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{InternalStepStatus, Metrics, StepStatus, TestStatus};
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[derive(Clone)]
+pub struct StatsdSink {
+    pub address: SocketAddr,
+    pub prefix: String,
+    pub tags: Vec<(String, String)>,
+    pub sample_rate: f64,
+    pub use_dogstatsd_tags: bool,
+    socket: Arc<UdpSocket>,
+    snapshots: Arc<Mutex<HashMap<(String, String), Metrics>>>,
+}
+
+/// Only counters and gauges are sent, as the timings are already summarized. Sent as timers,
+/// StatsD would summarize them again, such as a percentile of the percentiles.
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+struct Datagram {
+    name: String,
+    value: String,
+    metric_type: MetricType,
+    tags: Vec<String>,
+}
+
+impl MetricType {
+    fn get_symbol(&self) -> &'static str {
+        match self {
+            Self::Counter => "c",
+            Self::Gauge => "g",
+        }
+    }
+
+    fn is_sampled(&self) -> bool {
+        !matches!(self, Self::Gauge)
+    }
+}
+
+impl Datagram {
+    fn new(name: &str, value: impl ToString, metric_type: MetricType) -> Self {
+        Self {
+            name: name.to_owned(),
+            value: value.to_string(),
+            metric_type,
+            tags: Vec::default(),
+        }
+    }
+
+    fn with_tag(mut self, key: &str, value: impl ToString) -> Self {
+        self.tags.push(format!("{}:{}", key, value.to_string()));
+        self
+    }
+}
+
+impl StatsdSink {
+    const MAX_PACKET_SIZE: usize = 1432;
+
+    pub(crate) fn new(
+        socket: UdpSocket,
+        address: SocketAddr,
+        prefix: String,
+        tags: Vec<(String, String)>,
+        sample_rate: f64,
+        use_dogstatsd_tags: bool,
+    ) -> Self {
+        Self {
+            address,
+            prefix,
+            tags,
+            sample_rate,
+            use_dogstatsd_tags,
+            socket: Arc::new(socket),
+            snapshots: Arc::new(Mutex::new(HashMap::default())),
+        }
+    }
+
+    async fn track(&self, step_status: &StepStatus) -> SinkResult {
+        let metrics = &step_status.metrics;
+        let key = (
+            step_status.session_id.to_owned(),
+            step_status.step_name.to_owned(),
+        );
+        let previous = self.snapshots.lock().await.insert(key, metrics.to_owned());
+        let delta = |current: u128, previous: Option<u128>| {
+            current.saturating_sub(previous.unwrap_or_default())
+        };
+
+        let mut datagrams = vec![
+            Datagram::new(
+                "hits.success",
+                delta(
+                    metrics.positive_hits,
+                    previous.as_ref().map(|m| m.positive_hits),
+                ),
+                MetricType::Counter,
+            ),
+            Datagram::new(
+                "hits.failure",
+                delta(
+                    metrics.negative_hits,
+                    previous.as_ref().map(|m| m.negative_hits),
+                ),
+                MetricType::Counter,
+            ),
+            Datagram::new("latency.min", metrics.min_time, MetricType::Gauge),
+            Datagram::new("latency.mean", metrics.mean_time, MetricType::Gauge),
+            Datagram::new("latency.max", metrics.max_time, MetricType::Gauge),
+            Datagram::new("latency.p90", metrics.p90_time, MetricType::Gauge),
+            Datagram::new("latency.p95", metrics.p95_time, MetricType::Gauge),
+            Datagram::new("latency.p99", metrics.p99_time, MetricType::Gauge),
+            Datagram::new(
+                "requests_per_sec",
+                format!("{:.2}", metrics.request_per_sec),
+                MetricType::Gauge,
+            ),
+            Datagram::new("in_flight", metrics.in_flight, MetricType::Gauge),
+            Datagram::new("all_hits", metrics.all_hits, MetricType::Gauge),
+        ];
+
+        for (code, count) in &metrics.errors {
+            let previous = previous.as_ref().and_then(|m| m.errors.get(code).copied());
+            let count = delta(*count, previous);
+            let datagram = match self.use_dogstatsd_tags {
+                true => Datagram::new("errors", count, MetricType::Counter).with_tag("code", code),
+                false => Datagram::new(&format!("errors.{}", code), count, MetricType::Counter),
+            };
+            datagrams.push(datagram);
+        }
+
+        let lines = datagrams
+            .iter()
+            .filter_map(|datagram| self.format(step_status, datagram))
+            .collect::<Vec<String>>();
+
+        self.send(&lines).map_err(|error| {
+            SinkError::new(format!("The metrics couldn't be sent to StatsD: {error}"))
+        })
+    }
+
+    fn format(&self, step_status: &StepStatus, datagram: &Datagram) -> Option<String> {
+        let name = &datagram.name;
+        let mut line = match self.use_dogstatsd_tags {
+            true => format!("{}.{}", self.prefix, name),
+            false => format!(
+                "{}.{}.{}.{}",
+                self.prefix,
+                Self::sanitize(&step_status.test_name),
+                Self::sanitize(&step_status.step_name),
+                name
+            ),
+        };
+
+        line += &format!(":{}|{}", datagram.value, datagram.metric_type.get_symbol());
+
+        if datagram.metric_type.is_sampled() && self.sample_rate < 1f64 {
+            if rand::thread_rng().gen::<f64>() >= self.sample_rate {
+                return None;
+            }
+
+            line += &format!("|@{}", self.sample_rate);
+        }
+
+        if self.use_dogstatsd_tags {
+            let mut tags = vec![
+                format!("test_name:{}", Self::sanitize(&step_status.test_name)),
+                format!("test_suite:{}", Self::sanitize(&step_status.test_suite)),
+                format!("step:{}", Self::sanitize(&step_status.step_name)),
+            ];
+
+            if !step_status.stage_name.is_empty() {
+                tags.push(format!("stage:{}", Self::sanitize(&step_status.stage_name)));
+            }
+
+            tags.extend(datagram.tags.iter().cloned());
+            tags.extend(
+                self.tags
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", key, Self::sanitize(value))),
+            );
+
+            line += &format!("|#{}", tags.join(","));
+        }
+
+        Some(line)
+    }
+
+    fn send(&self, lines: &[String]) -> std::io::Result<()> {
+        let mut packet = String::default();
+
+        for line in lines {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > Self::MAX_PACKET_SIZE {
+                self.socket.send_to(packet.as_bytes(), self.address)?;
+                packet.clear();
+            }
+
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+
+            packet += line;
+        }
+
+        if !packet.is_empty() {
+            self.socket.send_to(packet.as_bytes(), self.address)?;
+        }
+
+        Ok(())
+    }
+
+    fn sanitize(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                ':' | '|' | '@' | '#' | ',' | '\n' | ' ' => '_',
+                _ => c,
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ReportingSink for StatsdSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.snapshots
+            .lock()
+            .await
+            .retain(|(session_id, _), _| *session_id != test_status.session_id);
+        Ok(())
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.track(&step_status).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.track(&step_status).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::statsd::utils::StatsdSinkBuilder;
+    use crate::test_utils::run_sequential_steps;
+    use loady::core::stats::CustomMetrics;
+    use std::time::Duration;
+
+    fn step_status(positive_hits: u128, errors: &[(i32, u128)]) -> StepStatus {
+        let negative_hits = errors.iter().map(|(_, count)| count).sum::<u128>();
+
+        StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("load step"),
+            stage_name: String::from("first"),
+            metrics: Metrics {
+                test_duration: 0,
+                load_duration: 0,
+                mean_time: 20,
+                max_time: 30,
+                min_time: 10,
+                std_dev: 0,
+                p90_time: 25,
+                p95_time: 28,
+                p99_time: 30,
+                positive_hits,
+                negative_hits,
+                all_hits: positive_hits + negative_hits,
+                request_per_sec: 1.5,
+                in_flight: 2,
+                errors: errors.iter().copied().collect(),
+                latency_histogram: Default::default(),
+                transactions: Vec::new(),
+                response_time: Default::default(),
+                schedule_lag: Default::default(),
+                generator: Default::default(),
+            },
+            actions: Vec::new(),
+            custom_metrics: CustomMetrics::default(),
+        }
+    }
+
+    fn listen() -> UdpSocket {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        listener
+    }
+
+    fn receive(listener: &UdpSocket) -> String {
+        let mut buffer = [0u8; 2048];
+        let read = listener.recv(&mut buffer).unwrap();
+        String::from_utf8_lossy(&buffer[..read]).to_string()
+    }
+
+    #[tokio::test]
+    async fn given_dogstatsd_sink_when_tracking_then_sends_tagged_metrics() {
+        let listener = listen();
+        let sink = StatsdSinkBuilder::default()
+            .with_address(&listener.local_addr().unwrap().to_string())
+            .with_prefix("perf")
+            .with_dogstatsd_tags()
+            .with_tag("env", "ci")
+            .build()
+            .unwrap();
+
+        sink.on_load_action_ended(step_status(5, &[(500, 1)]))
+            .await
+            .unwrap();
+        let content = receive(&listener);
+
+        let tags = "#test_name:test,test_suite:suite,step:load_step,stage:first";
+        assert!(content.contains(&format!("perf.hits.success:5|c|{},env:ci", tags)));
+        assert!(content.contains(&format!("perf.latency.p95:28|g|{},env:ci", tags)));
+        assert!(content.contains(&format!("perf.in_flight:2|g|{},env:ci", tags)));
+        assert!(content.contains(&format!("perf.errors:1|c|{},code:500,env:ci", tags)));
+    }
+
+    #[tokio::test]
+    async fn given_plain_statsd_sink_when_tracking_twice_then_sends_counter_deltas() {
+        let listener = listen();
+        let sink = StatsdSinkBuilder::default()
+            .with_address(&listener.local_addr().unwrap().to_string())
+            .build()
+            .unwrap();
+
+        sink.on_load_action_ended(step_status(5, &[(500, 1)]))
+            .await
+            .unwrap();
+        _ = receive(&listener);
+        sink.on_load_step_ended(step_status(8, &[(500, 4)]))
+            .await
+            .unwrap();
+        let content = receive(&listener);
+
+        assert!(content.contains("loady.test.load_step.hits.success:3|c"));
+        assert!(content.contains("loady.test.load_step.errors.500:3|c"));
+        assert!(!content.contains('#'));
+    }
+
+    #[tokio::test]
+    async fn given_interleaved_steps_when_tracking_then_sends_counter_deltas_per_step() {
+        let listener = listen();
+        let sink = StatsdSinkBuilder::default()
+            .with_address(&listener.local_addr().unwrap().to_string())
+            .build()
+            .unwrap();
+        let other_step = |positive_hits| StepStatus {
+            step_name: String::from("other"),
+            ..step_status(positive_hits, &[])
+        };
+
+        sink.on_load_action_ended(step_status(5, &[]))
+            .await
+            .unwrap();
+        _ = receive(&listener);
+        sink.on_load_action_ended(other_step(2)).await.unwrap();
+        let other = receive(&listener);
+        sink.on_load_action_ended(step_status(8, &[]))
+            .await
+            .unwrap();
+        let content = receive(&listener);
+
+        assert!(other.contains("loady.test.other.hits.success:2|c"));
+        assert!(content.contains("loady.test.load_step.hits.success:3|c"));
+    }
+
+    #[tokio::test]
+    async fn given_sequential_load_steps_when_running_then_counts_every_hit_once() {
+        let listener = listen();
+        let sink = StatsdSinkBuilder::default()
+            .with_address(&listener.local_addr().unwrap().to_string())
+            .build()
+            .unwrap();
+
+        let test_status = run_sequential_steps(sink).await;
+        listener
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        let mut buffer = [0u8; 2048];
+        let mut content = String::default();
+        while let Ok(read) = listener.recv(&mut buffer) {
+            content += &String::from_utf8_lossy(&buffer[..read]);
+            content.push('\n');
+        }
+
+        let total = |suffix: &str| {
+            content
+                .lines()
+                .filter_map(|line| line.strip_suffix("|c"))
+                .filter_map(|line| line.split_once(':'))
+                .filter(|(name, _)| name.ends_with(suffix))
+                .filter_map(|(_, value)| value.parse::<u128>().ok())
+                .sum::<u128>()
+        };
+        let metrics = &test_status.metrics;
+        assert_eq!(total(".hits.success"), metrics.positive_hits);
+        assert_eq!(total(".hits.failure"), metrics.negative_hits);
+        assert_eq!(total(".errors.500"), metrics.negative_hits);
+        assert!(content.contains("loady.test.second.hits.success"));
+    }
+
+    #[tokio::test]
+    async fn given_sample_rate_when_tracking_then_gauges_are_not_sampled() {
+        let listener = listen();
+        let sink = StatsdSinkBuilder::default()
+            .with_address(&listener.local_addr().unwrap().to_string())
+            .with_sample_rate(0.000001)
+            .build()
+            .unwrap();
+
+        sink.on_load_action_ended(step_status(5, &[]))
+            .await
+            .unwrap();
+        let content = receive(&listener);
+
+        assert!(content.contains("loady.test.load_step.in_flight:2|g"));
+        assert!(!content.contains("|c"));
+    }
+
+    #[test]
+    fn given_tags_without_dogstatsd_tags_when_building_then_returns_error() {
+        let actual = StatsdSinkBuilder::default().with_tag("env", "ci").build();
+
+        assert_eq!(
+            actual.err().unwrap().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn given_unresolvable_address_when_building_then_returns_error() {
+        let actual = StatsdSinkBuilder::default()
+            .with_address("not an address")
+            .build();
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn given_invalid_sample_rate_when_building_then_panics() {
+        _ = StatsdSinkBuilder::default().with_sample_rate(1.5);
+    }
+}
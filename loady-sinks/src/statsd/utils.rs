@@ -0,0 +1,83 @@
+use super::sink::StatsdSink;
+use std::io::{Error, ErrorKind};
+use std::net::{ToSocketAddrs, UdpSocket};
+
+#[derive(Default)]
+pub struct StatsdSinkBuilder {
+    address: Option<String>,
+    prefix: Option<String>,
+    tags: Vec<(String, String)>,
+    sample_rate: Option<f64>,
+    use_dogstatsd_tags: bool,
+}
+
+impl StatsdSinkBuilder {
+    const DEFAULT_ADDRESS: &str = "127.0.0.1:8125";
+    const DEFAULT_PREFIX: &str = "loady";
+
+    pub fn with_address(mut self, address: &str) -> Self {
+        self.address = Some(address.to_owned());
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_owned());
+        self
+    }
+
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: f64) -> Self {
+        if sample_rate <= 0f64 || sample_rate > 1f64 {
+            panic!("The sample rate must be greater than 0 and lower or equal than 1");
+        }
+
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    pub fn with_dogstatsd_tags(mut self) -> Self {
+        self.use_dogstatsd_tags = true;
+        self
+    }
+
+    /// Builds the sink, failing when the address can't be resolved, the socket can't be bound or
+    /// tags are set without using DogStatsD tags.
+    pub fn build(self) -> std::io::Result<StatsdSink> {
+        if !self.tags.is_empty() && !self.use_dogstatsd_tags {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tags are only supported when using DogStatsD tags",
+            ));
+        }
+
+        let address = self
+            .address
+            .unwrap_or(String::from(Self::DEFAULT_ADDRESS))
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "The address provided for the StatsD sink is malformed",
+                )
+            })?;
+        let bind_address = match address.is_ipv4() {
+            true => "0.0.0.0:0",
+            false => "[::]:0",
+        };
+        let socket = UdpSocket::bind(bind_address)?;
+
+        Ok(StatsdSink::new(
+            socket,
+            address,
+            self.prefix.unwrap_or(String::from(Self::DEFAULT_PREFIX)),
+            self.tags,
+            self.sample_rate.unwrap_or(1f64),
+            self.use_dogstatsd_tags,
+        ))
+    }
+}
@@ -0,0 +1,174 @@
+//! Fixtures and an HTTP stand-in shared by the sink tests. Each sink only uses some of them,
+//! depending on the enabled features.
+#![allow(dead_code)]
+
+use loady::core::functions::LoadResult;
+use loady::core::reporting::ReportingSink;
+use loady::core::runner::TestRunner;
+use loady::core::stats::{CustomMetrics, LatencyHistogram, Metrics, StepStatus, TestStatus};
+use loady::utils::TestCaseBuilder;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+
+/// The raw requests received by the stand-in, in order.
+pub(crate) type Requests = Arc<Mutex<Vec<String>>>;
+
+pub(crate) fn metrics() -> Metrics {
+    Metrics {
+        test_duration: 1000,
+        load_duration: 900,
+        mean_time: 20,
+        max_time: 40,
+        min_time: 10,
+        std_dev: 5,
+        p90_time: 30,
+        p95_time: 35,
+        p99_time: 40,
+        positive_hits: 10,
+        negative_hits: 0,
+        all_hits: 10,
+        request_per_sec: 11.1,
+        in_flight: 0,
+        errors: HashMap::new(),
+        latency_histogram: LatencyHistogram::default(),
+        transactions: Vec::new(),
+        response_time: Default::default(),
+        schedule_lag: Default::default(),
+        generator: Default::default(),
+    }
+}
+
+pub(crate) fn step_status() -> StepStatus {
+    StepStatus {
+        session_id: String::from("session"),
+        test_name: String::from("test"),
+        test_suite: String::from("suite"),
+        step_name: String::from("step"),
+        stage_name: String::from("stage"),
+        metrics: metrics(),
+        actions: Vec::new(),
+        custom_metrics: CustomMetrics::default(),
+    }
+}
+
+pub(crate) fn test_status() -> TestStatus {
+    TestStatus {
+        session_id: String::from("session"),
+        test_name: String::from("test"),
+        test_suite: String::from("suite"),
+        metrics: metrics(),
+        sinks: Vec::new(),
+    }
+}
+
+/// Runs a test with two load steps, one after another, reporting into the sink. One in three
+/// hits fails with a 500 error.
+pub(crate) async fn run_sequential_steps<S: ReportingSink + 'static>(sink: S) -> TestStatus {
+    #[derive(Clone, Debug, Default)]
+    struct Hits(Arc<AtomicU64>);
+
+    fn load(hits: Arc<Hits>) -> LoadResult<'static> {
+        Box::pin(async move {
+            match hits.0.fetch_add(1, Ordering::SeqCst) % 3 {
+                2 => Err(500),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    let test_case = TestCaseBuilder::new("test", "suite", &Hits::default())
+        .with_load_step("first", Box::new(load))
+        .with_stage(
+            "ramp",
+            Duration::from_millis(200),
+            Duration::from_millis(50),
+            2,
+        )
+        .with_load_step("second", Box::new(load))
+        .with_stage(
+            "ramp",
+            Duration::from_millis(200),
+            Duration::from_millis(50),
+            3,
+        )
+        .build();
+
+    TestRunner::default()
+        .with_reporting_sink(sink)
+        .run(test_case)
+        .await
+        .unwrap()
+}
+
+/// Splits a raw request into its lowercased head and its body.
+pub(crate) fn split_request(request: &str) -> (String, &str) {
+    let (head, body) = request.split_once("\r\n\r\n").unwrap_or((request, ""));
+    (head.to_lowercase(), body)
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 4096];
+
+    let header_end = loop {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if let Some(index) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break index + 4;
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or_default();
+
+    while request.len() < header_end + content_length {
+        let read = stream.read(&mut buffer).await.ok()?;
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    Some(String::from_utf8_lossy(&request).into_owned())
+}
+
+/// Starts an HTTP server on a free local port and returns its url. It records every request and
+/// answers them with the given status and JSON body pairs in order, then with the fallback.
+pub(crate) async fn serve(
+    responses: Vec<(u16, &'static str)>,
+    fallback: (u16, &'static str),
+) -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Requests::default();
+    let received = requests.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            while let Some(request) = read_request(&mut stream).await {
+                let mut requests = received.lock().await;
+                let (status, body) = responses.get(requests.len()).copied().unwrap_or(fallback);
+                requests.push(request);
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        }
+    });
+
+    (url, requests)
+}
@@ -0,0 +1,715 @@
+use async_trait::async_trait;
+use loady::core::cancellation::Cancellation;
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{
+    InternalStepStatus, Metrics, StageStatus, StepOutcome, StepStatus, TestPlan, TestStatus,
+};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::{Block, Borders, Gauge, Paragraph, Row, Sparkline, Table};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Invoked once the user asks to abort the run from the dashboard.
+pub type AbortHandler = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TuiOutput {
+    /// Draws a live dashboard on the alternate screen of the terminal.
+    Dashboard,
+    /// Logs a plain line per event, used when stdout isn't a TTY.
+    Lines,
+}
+
+#[derive(Clone)]
+pub struct TuiSink {
+    pub output: TuiOutput,
+    pub refresh_rate: Duration,
+    dashboard: Arc<Mutex<Dashboard>>,
+    renderer: Arc<Mutex<Option<Renderer>>>,
+    abort_handler: Option<AbortHandler>,
+    /// Cancellation of the runner the sink is attached to, used when there's no abort handler.
+    cancellation: Arc<Mutex<Cancellation>>,
+}
+
+struct Renderer {
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct Dashboard {
+    test_name: String,
+    session_id: String,
+    started_at: Option<Instant>,
+    step_name: String,
+    stage_name: String,
+    target_rps: Option<f64>,
+    metrics: Option<Metrics>,
+    latencies: VecDeque<u64>,
+    history_size: usize,
+    last_failure: Option<String>,
+}
+
+impl Renderer {
+    fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Dashboard {
+    fn new(history_size: usize) -> Self {
+        Self {
+            history_size,
+            ..Default::default()
+        }
+    }
+
+    fn start(&mut self, plan: &TestPlan) {
+        *self = Self::new(self.history_size);
+        self.test_name = plan.test_name.to_owned();
+        self.session_id = plan.session_id.to_owned();
+        self.started_at = Some(Instant::now());
+    }
+
+    fn start_stage(&mut self, status: &StageStatus) {
+        self.step_name = status.step_name.to_owned();
+        self.stage_name = status.stage.stage_name.to_owned();
+        self.target_rps = match status.stage.interval {
+            0 => None,
+            interval => Some(status.stage.rate as f64 * 1000f64 / interval as f64),
+        };
+    }
+
+    fn track(&mut self, status: &StepStatus) {
+        self.step_name = status.step_name.to_owned();
+        if !status.stage_name.is_empty() {
+            self.stage_name = status.stage_name.to_owned();
+        }
+
+        self.latencies.push_back(status.metrics.mean_time as u64);
+        while self.latencies.len() > self.history_size {
+            self.latencies.pop_front();
+        }
+
+        self.metrics = Some(status.metrics.to_owned());
+    }
+
+    fn end_internal_step(&mut self, status: &InternalStepStatus) {
+        if let StepOutcome::Failed(message) = &status.outcome {
+            self.last_failure = Some(format!("{}: {}", status.step_name, message));
+        }
+    }
+
+    fn get_errors(&self) -> Vec<(i32, u128)> {
+        let mut errors: Vec<(i32, u128)> = self
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.errors.iter().map(|(k, v)| (*k, *v)).collect())
+            .unwrap_or_default();
+
+        errors.sort_by(|(a_code, a_count), (b_code, b_count)| {
+            b_count.cmp(a_count).then(a_code.cmp(b_code))
+        });
+        errors
+    }
+
+    /// The histogram only keeps cumulative bucket counts, so the median is interpolated within
+    /// its bucket, the same way Prometheus estimates quantiles.
+    fn get_median(metrics: &Metrics) -> Option<u128> {
+        let histogram = &metrics.latency_histogram;
+        let rank = histogram.count as f64 / 2f64;
+        let mut lower = (0u128, 0u128);
+
+        for bucket in histogram.buckets.iter().filter(|_| histogram.count > 0) {
+            if bucket.count as f64 >= rank {
+                let (lower_bound, lower_count) = lower;
+                let width = (bucket.le - lower_bound) as f64;
+                let share = match bucket.count - lower_count {
+                    0 => 0f64,
+                    count => (rank - lower_count as f64) / count as f64,
+                };
+                let median = lower_bound as f64 + width * share;
+                return Some((median.round() as u128).clamp(metrics.min_time, metrics.max_time));
+            }
+
+            lower = (bucket.le, bucket.count);
+        }
+
+        None
+    }
+
+    fn format_median(metrics: &Metrics) -> String {
+        match Self::get_median(metrics) {
+            Some(median) => format!("{}ms", median),
+            None => String::from("-"),
+        }
+    }
+
+    fn format_target(&self) -> String {
+        match self.target_rps {
+            Some(target) => format!("{:.2}", target),
+            None => String::from("-"),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "[{}] {}/{}",
+            self.test_name, self.step_name, self.stage_name
+        );
+
+        if let Some(metrics) = &self.metrics {
+            line += &format!(
+                " rps: {:.2}/{} in flight: {} p50: {} p95: {}ms p99: {}ms ok: {} ko: {}",
+                metrics.request_per_sec,
+                self.format_target(),
+                metrics.in_flight,
+                Self::format_median(metrics),
+                metrics.p95_time,
+                metrics.p99_time,
+                metrics.positive_hits,
+                metrics.negative_hits
+            );
+        }
+
+        let errors = self.get_errors();
+        if !errors.is_empty() {
+            let errors = errors
+                .iter()
+                .map(|(code, count)| format!("{}={}", code, count))
+                .collect::<Vec<String>>()
+                .join(", ");
+            line += &format!(" errors: {}", errors);
+        }
+
+        line
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [header, throughput, details, latency] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(8),
+        ])
+        .areas(frame.area());
+        let [stats, errors] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(details);
+
+        let elapsed = self
+            .started_at
+            .map(|started_at| started_at.elapsed().as_secs())
+            .unwrap_or_default();
+        let mut lines = vec![format!(
+            "Step: {}  Stage: {}  Elapsed: {}s",
+            self.step_name, self.stage_name, elapsed
+        )
+        .into()];
+        if let Some(failure) = &self.last_failure {
+            lines.push(format!("Last failure: {}", failure).red().into());
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} · {} ", self.test_name, self.session_id))
+                    .title_bottom(" q / esc / ctrl+c: abort "),
+            ),
+            header,
+        );
+
+        let rps = self
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.request_per_sec)
+            .unwrap_or_default();
+        let ratio = match self.target_rps {
+            Some(target) if target > 0f64 => (rps / target).clamp(0f64, 1f64),
+            _ => 0f64,
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Achieved vs target RPS "),
+                )
+                .gauge_style(Style::default().fg(Color::Green))
+                .ratio(ratio)
+                .label(format!("{:.2} / {} rps", rps, self.format_target())),
+            throughput,
+        );
+
+        let stats_lines = match &self.metrics {
+            Some(metrics) => vec![
+                format!("In flight: {}", metrics.in_flight),
+                format!("Successful hits: {}", metrics.positive_hits),
+                format!("Unsuccessful hits: {}", metrics.negative_hits),
+                format!("p50: {}", Self::format_median(metrics)),
+                format!("p95: {}ms", metrics.p95_time),
+                format!("p99: {}ms", metrics.p99_time),
+            ],
+            None => vec![String::from("Waiting for metrics...")],
+        };
+        frame.render_widget(
+            Paragraph::new(stats_lines.join("\n"))
+                .block(Block::default().borders(Borders::ALL).title(" Metrics ")),
+            stats,
+        );
+
+        let rows = self
+            .get_errors()
+            .into_iter()
+            .map(|(code, count)| Row::new(vec![code.to_string(), count.to_string()]));
+        frame.render_widget(
+            Table::new(rows, [Constraint::Length(8), Constraint::Min(8)])
+                .header(Row::new(vec!["Code", "Count"]).bold())
+                .block(Block::default().borders(Borders::ALL).title(" Errors ")),
+            errors,
+        );
+
+        let data = self.latencies.iter().copied().collect::<Vec<u64>>();
+        frame.render_widget(
+            Sparkline::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Latency (mean ms) "),
+                )
+                .style(Style::default().fg(Color::Cyan))
+                .data(&data),
+            latency,
+        );
+    }
+}
+
+impl TuiSink {
+    pub(crate) fn new(
+        output: TuiOutput,
+        refresh_rate: Duration,
+        history_size: usize,
+        abort_handler: Option<AbortHandler>,
+    ) -> Self {
+        Self {
+            output,
+            refresh_rate,
+            dashboard: Arc::new(Mutex::new(Dashboard::new(history_size))),
+            renderer: Arc::new(Mutex::new(None)),
+            abort_handler,
+            cancellation: Arc::new(Mutex::new(Cancellation::default())),
+        }
+    }
+
+    /// Returns the abort handler, which cancels the run by default so that the runner stops the
+    /// load and still reports the results.
+    fn get_abort_handler(&self) -> AbortHandler {
+        if let Some(abort_handler) = &self.abort_handler {
+            return Arc::clone(abort_handler);
+        }
+
+        let cancellation = self
+            .cancellation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .to_owned();
+        Arc::new(move || cancellation.cancel())
+    }
+
+    fn get_dashboard(&self) -> MutexGuard<'_, Dashboard> {
+        self.dashboard
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn start_renderer(&self) {
+        let mut renderer = self.renderer.lock().unwrap_or_else(PoisonError::into_inner);
+        if renderer.is_some() {
+            return;
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = std::thread::spawn({
+            let dashboard = Arc::clone(&self.dashboard);
+            let running = Arc::clone(&running);
+            let abort_handler = self.get_abort_handler();
+            let refresh_rate = self.refresh_rate;
+            move || Self::render(dashboard, running, abort_handler, refresh_rate)
+        });
+
+        *renderer = Some(Renderer {
+            running,
+            handle: Some(handle),
+        });
+    }
+
+    async fn stop_renderer(&self) -> SinkResult {
+        let renderer = self
+            .renderer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(mut renderer) = renderer {
+            tokio::task::spawn_blocking(move || renderer.stop())
+                .await
+                .map_err(|error| {
+                    SinkError::new(format!("The dashboard couldn't be closed: {error}"))
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn render(
+        dashboard: Arc<Mutex<Dashboard>>,
+        running: Arc<AtomicBool>,
+        abort_handler: AbortHandler,
+        refresh_rate: Duration,
+    ) {
+        let mut terminal = ratatui::init();
+        let mut aborted = false;
+
+        while running.load(Ordering::Relaxed) {
+            _ = terminal.draw(|frame| {
+                dashboard
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .render(frame)
+            });
+
+            if Self::wait_for_abort(refresh_rate) {
+                aborted = true;
+                break;
+            }
+        }
+
+        ratatui::restore();
+
+        if aborted {
+            abort_handler();
+        }
+    }
+
+    fn wait_for_abort(timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if !event::poll(remaining).unwrap_or(false) {
+                return false;
+            }
+
+            if let Ok(Event::Key(key)) = event::read() {
+                if Self::is_abort_key(&key) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn is_abort_key(key: &KeyEvent) -> bool {
+        if key.kind != KeyEventKind::Press {
+            return false;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => true,
+            KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+            _ => false,
+        }
+    }
+
+    fn log(&self, line: String) {
+        if self.output == TuiOutput::Lines {
+            println!("{}", line);
+        }
+    }
+}
+
+#[async_trait]
+impl ReportingSink for TuiSink {
+    fn on_attached(&self, cancellation: Cancellation) {
+        *self
+            .cancellation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = cancellation;
+    }
+
+    async fn on_test_started(&self, plan: TestPlan) -> SinkResult {
+        self.get_dashboard().start(&plan);
+        self.log(format!(
+            "[{}] started, session {}",
+            plan.test_name, plan.session_id
+        ));
+
+        if self.output == TuiOutput::Dashboard {
+            self.start_renderer();
+        }
+
+        Ok(())
+    }
+
+    async fn on_stage_started(&self, status: StageStatus) -> SinkResult {
+        let target = {
+            let mut dashboard = self.get_dashboard();
+            dashboard.start_stage(&status);
+            dashboard.format_target()
+        };
+
+        self.log(format!(
+            "[{}] {}/{} started, target {} rps",
+            status.test_name, status.step_name, status.stage.stage_name, target
+        ));
+        Ok(())
+    }
+
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.stop_renderer().await?;
+
+        println!(
+            "[{}] ended in {}ms rps: {:.2} p95: {}ms p99: {}ms ok: {} ko: {}",
+            test_status.test_name,
+            test_status.metrics.test_duration,
+            test_status.metrics.request_per_sec,
+            test_status.metrics.p95_time,
+            test_status.metrics.p99_time,
+            test_status.metrics.positive_hits,
+            test_status.metrics.negative_hits
+        );
+        Ok(())
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        let line = {
+            let mut dashboard = self.get_dashboard();
+            dashboard.track(&step_status);
+            dashboard.to_line()
+        };
+
+        self.log(line);
+        Ok(())
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        let line = {
+            let mut dashboard = self.get_dashboard();
+            dashboard.track(&step_status);
+            dashboard.to_line()
+        };
+
+        self.log(line);
+        Ok(())
+    }
+
+    async fn on_internal_step_ended(&self, status: InternalStepStatus) -> SinkResult {
+        self.get_dashboard().end_internal_step(&status);
+
+        let outcome = match &status.outcome {
+            StepOutcome::Succeeded => String::from("succeeded"),
+            StepOutcome::Failed(message) => format!("failed: {}", message),
+        };
+        self.log(format!(
+            "[{}] {} {} in {}ms",
+            status.test_name, status.step_name, outcome, status.duration
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils;
+    use crate::tui::utils::TuiSinkBuilder;
+    use loady::core::stats::{LatencyHistogram, StagePlan, StepKind};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::collections::HashMap;
+
+    fn step_status(mean_time: u128) -> StepStatus {
+        let mut latency_histogram = LatencyHistogram::default();
+        [8, 20, 22, 40]
+            .iter()
+            .for_each(|time| latency_histogram.add(*time));
+
+        StepStatus {
+            step_name: String::from("load"),
+            stage_name: String::from("ramp"),
+            metrics: Metrics {
+                load_duration: 1000,
+                mean_time,
+                min_time: 8,
+                p90_time: 35,
+                p95_time: 38,
+                positive_hits: 4,
+                negative_hits: 3,
+                all_hits: 7,
+                request_per_sec: 7.5,
+                in_flight: 2,
+                errors: HashMap::from([(500, 1), (503, 2)]),
+                latency_histogram,
+                ..test_utils::metrics()
+            },
+            ..test_utils::step_status()
+        }
+    }
+
+    fn stage_status() -> StageStatus {
+        StageStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("load"),
+            stage: StagePlan {
+                stage_name: String::from("ramp"),
+                during: 10000,
+                interval: 500,
+                rate: 5,
+                arrivals: String::from("burst"),
+            },
+            elapsed: 0,
+        }
+    }
+
+    fn render(dashboard: &Dashboard) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn given_snapshots_when_tracking_then_keeps_bounded_latency_history() {
+        let mut dashboard = Dashboard::new(2);
+
+        dashboard.start_stage(&stage_status());
+        [10, 20, 30]
+            .iter()
+            .for_each(|mean| dashboard.track(&step_status(*mean)));
+
+        assert_eq!(dashboard.latencies, VecDeque::from([20, 30]));
+        assert_eq!(dashboard.target_rps, Some(10f64));
+        assert_eq!(dashboard.get_errors(), vec![(503, 2), (500, 1)]);
+        assert_eq!(
+            Dashboard::get_median(dashboard.metrics.as_ref().unwrap()),
+            Some(18)
+        );
+    }
+
+    #[test]
+    fn given_snapshot_when_formatting_line_then_contains_metrics_and_errors() {
+        let mut dashboard = Dashboard::new(10);
+        dashboard.test_name = String::from("test");
+
+        dashboard.start_stage(&stage_status());
+        dashboard.track(&step_status(20));
+
+        assert_eq!(
+            dashboard.to_line(),
+            "[test] load/ramp rps: 7.50/10.00 in flight: 2 p50: 18ms p95: 38ms p99: 40ms ok: 4 ko: 3 errors: 503=2, 500=1"
+        );
+    }
+
+    #[test]
+    fn given_snapshot_when_rendering_then_draws_every_panel() {
+        let mut dashboard = Dashboard::new(10);
+        dashboard.test_name = String::from("test");
+
+        dashboard.start_stage(&stage_status());
+        dashboard.track(&step_status(20));
+        dashboard.end_internal_step(&InternalStepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("clean up"),
+            kind: StepKind::CleanUp,
+            duration: 5,
+            outcome: StepOutcome::Failed(String::from("boom")),
+        });
+        let content = render(&dashboard);
+
+        assert!(content.contains("Stage: ramp"));
+        assert!(content.contains("7.50 / 10.00 rps"));
+        assert!(content.contains("In flight: 2"));
+        assert!(content.contains("p99: 40ms"));
+        assert!(content.contains("503"));
+        assert!(content.contains("Latency (mean ms)"));
+        assert!(content.contains("Last failure: clean up: boom"));
+    }
+
+    #[test]
+    fn given_abort_keys_when_pressed_then_requests_abort() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+
+        assert!(TuiSink::is_abort_key(&ctrl_c));
+        assert!(TuiSink::is_abort_key(&q));
+        assert!(!TuiSink::is_abort_key(&c));
+    }
+
+    #[test]
+    fn given_attached_sink_when_aborting_then_cancels_the_run() {
+        let sink = TuiSinkBuilder::default().with_plain_output().build();
+        let cancellation = Cancellation::default();
+
+        sink.on_attached(cancellation.to_owned());
+        (sink.get_abort_handler())();
+
+        assert!(cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn given_abort_handler_when_aborting_then_does_not_cancel_the_run() {
+        let aborted = Arc::new(AtomicBool::new(false));
+        let sink = TuiSinkBuilder::default()
+            .with_plain_output()
+            .with_abort_handler({
+                let aborted = Arc::clone(&aborted);
+                move || aborted.store(true, Ordering::Relaxed)
+            })
+            .build();
+        let cancellation = Cancellation::default();
+
+        sink.on_attached(cancellation.to_owned());
+        (sink.get_abort_handler())();
+
+        assert!(aborted.load(Ordering::Relaxed));
+        assert!(!cancellation.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn given_plain_output_when_reporting_then_does_not_start_dashboard() {
+        let sink = TuiSinkBuilder::default().with_plain_output().build();
+
+        sink.on_stage_started(stage_status()).await.unwrap();
+        sink.on_load_action_ended(step_status(20)).await.unwrap();
+
+        assert_eq!(sink.output, TuiOutput::Lines);
+        assert!(sink.renderer.lock().unwrap().is_none());
+        assert_eq!(sink.get_dashboard().stage_name, "ramp");
+    }
+}
@@ -0,0 +1,71 @@
+use super::sink::{AbortHandler, TuiOutput, TuiSink};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct TuiSinkBuilder {
+    refresh_rate: Option<Duration>,
+    history_size: Option<usize>,
+    use_plain_output: bool,
+    abort_handler: Option<AbortHandler>,
+}
+
+impl TuiSinkBuilder {
+    const DEFAULT_REFRESH_RATE: Duration = Duration::from_millis(250);
+    const DEFAULT_HISTORY_SIZE: usize = 120;
+    const ABORT_EXIT_CODE: i32 = 130;
+
+    pub fn with_refresh_rate(mut self, refresh_rate: Duration) -> Self {
+        if refresh_rate.is_zero() {
+            panic!("The refresh rate must be greater than zero");
+        }
+
+        self.refresh_rate = Some(refresh_rate);
+        self
+    }
+
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        if history_size == 0 {
+            panic!("The history size must be greater than zero");
+        }
+
+        self.history_size = Some(history_size);
+        self
+    }
+
+    /// Logs plain lines even when stdout is a TTY.
+    pub fn with_plain_output(mut self) -> Self {
+        self.use_plain_output = true;
+        self
+    }
+
+    /// Replaces the default abort behaviour, which cancels the run so that the runner stops the
+    /// load, runs the clean up steps and reports the results.
+    pub fn with_abort_handler<F>(mut self, abort_handler: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.abort_handler = Some(Arc::new(abort_handler));
+        self
+    }
+
+    /// Exits the process with code 130 on abort, skipping the clean up steps and the reports.
+    pub fn with_exit_on_abort(self) -> Self {
+        self.with_abort_handler(|| std::process::exit(Self::ABORT_EXIT_CODE))
+    }
+
+    pub fn build(self) -> TuiSink {
+        let output = match self.use_plain_output || !std::io::stdout().is_terminal() {
+            true => TuiOutput::Lines,
+            false => TuiOutput::Dashboard,
+        };
+
+        TuiSink::new(
+            output,
+            self.refresh_rate.unwrap_or(Self::DEFAULT_REFRESH_RATE),
+            self.history_size.unwrap_or(Self::DEFAULT_HISTORY_SIZE),
+            self.abort_handler,
+        )
+    }
+}
@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use loady::core::reporting::{ReportingSink, SinkError, SinkResult};
+use loady::core::stats::{InternalStepStatus, StepStatus, TestStatus};
+use reqwest::header::HeaderMap;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::batching::{BatchBuffer, BatchTarget, DeliveryFailure, Rejections};
+
+/// The reporting hooks a webhook can be subscribed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookHook {
+    TestEnded,
+    StepEnded,
+    ActionEnded,
+}
+
+/// The event handed to the payload transformer.
+#[derive(Clone, Debug)]
+pub enum WebhookEvent {
+    TestEnded(TestStatus),
+    StepEnded(StepStatus),
+    ActionEnded(StepStatus),
+}
+
+impl WebhookEvent {
+    pub fn hook(&self) -> WebhookHook {
+        match self {
+            WebhookEvent::TestEnded(_) => WebhookHook::TestEnded,
+            WebhookEvent::StepEnded(_) => WebhookHook::StepEnded,
+            WebhookEvent::ActionEnded(_) => WebhookHook::ActionEnded,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::TestEnded(_) => "test_ended",
+            WebhookEvent::StepEnded(_) => "step_ended",
+            WebhookEvent::ActionEnded(_) => "action_ended",
+        }
+    }
+
+    /// Default payload: the event name, its creation time and the reported status.
+    pub fn to_payload(&self) -> Value {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        let status = match self {
+            WebhookEvent::TestEnded(status) => json!(status),
+            WebhookEvent::StepEnded(status) | WebhookEvent::ActionEnded(status) => json!(status),
+        };
+
+        json!({
+            "event": self.name(),
+            "created_at": now.to_rfc3339(),
+            "status": status,
+        })
+    }
+}
+
+/// Turns an event into its payload, or skips the event by returning `None`.
+pub type PayloadTransformer = Arc<dyn Fn(&WebhookEvent) -> Option<Value> + Send + Sync>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct DeliveryPolicy {
+    /// Number of payloads sent together as a JSON array, or `None` to send every payload alone.
+    pub batch_size: Option<usize>,
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    pub timeout: Duration,
+}
+
+#[derive(Clone)]
+pub struct WebhookSink {
+    pub url: String,
+    client: Client,
+    headers: HeaderMap,
+    hooks: Vec<WebhookHook>,
+    transformer: PayloadTransformer,
+    batched: bool,
+    buffer: Arc<BatchBuffer<Value>>,
+}
+
+impl WebhookSink {
+    pub(crate) fn new(
+        url: String,
+        headers: HeaderMap,
+        hooks: Vec<WebhookHook>,
+        transformer: PayloadTransformer,
+        policy: DeliveryPolicy,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(policy.timeout)
+            .build()
+            .expect("The webhook sink client can't be built");
+
+        Self {
+            url,
+            client,
+            headers,
+            hooks,
+            transformer,
+            batched: policy.batch_size.is_some(),
+            buffer: Arc::new(BatchBuffer::new(
+                policy.batch_size.unwrap_or(1),
+                policy.max_retries,
+                policy.retry_backoff,
+            )),
+        }
+    }
+
+    /// Number of payloads that couldn't be delivered after exhausting the retries.
+    pub fn failed_payloads(&self) -> u64 {
+        self.buffer.failed()
+    }
+
+    async fn publish(&self, event: WebhookEvent) -> SinkResult {
+        let payload = match self.hooks.contains(&event.hook()) {
+            true => (self.transformer)(&event),
+            false => None,
+        };
+
+        let force_flush = event.hook() == WebhookHook::TestEnded;
+        self.buffer.push(self, payload, force_flush).await
+    }
+}
+
+#[async_trait]
+impl BatchTarget<Value> for WebhookSink {
+    async fn send(&self, payloads: &[Value]) -> Result<Rejections, DeliveryFailure> {
+        let request = self.client.post(&self.url).headers(self.headers.to_owned());
+        let request = match (self.batched, payloads) {
+            (false, [payload]) => request.json(payload),
+            _ => request.json(payloads),
+        };
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => Ok(Rejections::default()),
+            Ok(response) => Err(DeliveryFailure::from_status(
+                format!("the webhook responded with {}", response.status()),
+                response.status().as_u16(),
+            )),
+            Err(error) => Err(DeliveryFailure::unreachable(error.to_string())),
+        }
+    }
+
+    fn discarded(&self, count: usize, reason: &str) -> SinkError {
+        SinkError::new(format!("Unable to deliver {} payloads: {}", count, reason))
+    }
+}
+
+#[async_trait]
+impl ReportingSink for WebhookSink {
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        self.publish(WebhookEvent::TestEnded(test_status)).await
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.publish(WebhookEvent::StepEnded(step_status)).await
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.publish(WebhookEvent::ActionEnded(step_status)).await
+    }
+
+    async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::{self, split_request, step_status, test_status, Requests};
+    use crate::webhook::utils::WebhookSinkBuilder;
+
+    async fn serve(statuses: Vec<u16>) -> (String, Requests) {
+        let responses = statuses.into_iter().map(|status| (status, "")).collect();
+        let (url, requests) = test_utils::serve(responses, (200, "")).await;
+        (format!("{}/hooks/loady", url), requests)
+    }
+
+    /// Returns the lowercased head and the JSON body of every received request.
+    async fn received(requests: &Requests) -> Vec<(String, Value)> {
+        requests
+            .lock()
+            .await
+            .iter()
+            .map(|request| {
+                let (head, body) = split_request(request);
+                (head, serde_json::from_str(body).unwrap())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn given_default_hooks_when_reporting_then_only_posts_test_ended() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_header("x-api-key", "secret")
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_step_ended(step_status()).await.unwrap();
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = received(&requests).await;
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = &requests[0];
+        assert!(headers.starts_with("post /hooks/loady"));
+        assert!(headers.contains("x-api-key: secret"));
+        assert!(headers.contains("content-type: application/json"));
+        assert_eq!(body["event"], "test_ended");
+        assert_eq!(body["status"]["test_suite"], "suite");
+    }
+
+    #[tokio::test]
+    async fn given_batch_size_when_reporting_then_posts_arrays_and_flushes_at_the_end() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_hooks(&[WebhookHook::StepEnded, WebhookHook::ActionEnded])
+            .with_batch_size(2)
+            .build();
+
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_action_ended(step_status()).await.unwrap();
+        sink.on_load_step_ended(step_status()).await.unwrap();
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = received(&requests).await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].1.as_array().unwrap().len(), 2);
+        assert_eq!(requests[1].1.as_array().unwrap().len(), 1);
+        assert_eq!(requests[1].1[0]["event"], "step_ended");
+    }
+
+    #[tokio::test]
+    async fn given_batch_size_of_one_when_reporting_then_posts_arrays() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_batch_size(1)
+            .build();
+
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = received(&requests).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1.as_array().unwrap().len(), 1);
+        assert_eq!(requests[0].1[0]["event"], "test_ended");
+    }
+
+    #[tokio::test]
+    async fn given_transformer_skipping_events_when_reporting_then_posts_the_rest() {
+        let (url, requests) = serve(Vec::new()).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_hooks(&[WebhookHook::StepEnded, WebhookHook::TestEnded])
+            .with_payload_transformer(|event| match event {
+                WebhookEvent::StepEnded(_) => None,
+                _ => Some(event.to_payload()),
+            })
+            .build();
+
+        sink.on_load_step_ended(step_status()).await.unwrap();
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = received(&requests).await;
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1["event"], "test_ended");
+    }
+
+    #[tokio::test]
+    async fn given_transformer_and_unavailable_webhook_when_reporting_then_retries() {
+        let (url, requests) = serve(vec![503]).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_retry_backoff(Duration::from_millis(1))
+            .with_payload_transformer(|event| match event {
+                WebhookEvent::TestEnded(status) => Some(json!({
+                    "text": format!("{} finished with {} hits", status.test_name, status.metrics.all_hits)
+                })),
+                _ => None,
+            })
+            .build();
+
+        sink.on_test_ended(test_status()).await.unwrap();
+
+        let requests = received(&requests).await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].1,
+            json!({ "text": "test finished with 10 hits" })
+        );
+        assert_eq!(sink.failed_payloads(), 0);
+    }
+
+    #[tokio::test]
+    async fn given_rejected_payload_when_reporting_then_does_not_retry() {
+        let (url, requests) = serve(vec![400]).await;
+        let sink = WebhookSinkBuilder::default()
+            .with_url(&url)
+            .with_retry_backoff(Duration::from_millis(1))
+            .build();
+
+        let result = sink.on_test_ended(test_status()).await;
+
+        assert!(result.is_err());
+        assert_eq!(requests.lock().await.len(), 1);
+        assert_eq!(sink.failed_payloads(), 1);
+    }
+}
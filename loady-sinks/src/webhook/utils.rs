@@ -0,0 +1,93 @@
+use super::sink::{DeliveryPolicy, PayloadTransformer, WebhookEvent, WebhookHook, WebhookSink};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct WebhookSinkBuilder {
+    url: Option<String>,
+    headers: HeaderMap,
+    hooks: Option<Vec<WebhookHook>>,
+    transformer: Option<PayloadTransformer>,
+    batch_size: Option<usize>,
+    max_retries: Option<u32>,
+    retry_backoff: Option<Duration>,
+    timeout: Option<Duration>,
+}
+
+impl WebhookSinkBuilder {
+    const DEFAULT_MAX_RETRIES: u32 = 3;
+    const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn with_url(mut self, url: &str) -> Self {
+        reqwest::Url::parse(url).expect("The url provided for the webhook sink is malformed");
+        self.url = Some(url.to_owned());
+        self
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        let key = HeaderName::from_bytes(key.as_bytes())
+            .expect("The header name provided for the webhook sink is malformed");
+        let value = HeaderValue::from_str(value)
+            .expect("The header value provided for the webhook sink is malformed");
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Hooks that produce a payload. By default, only the end of the test is published.
+    pub fn with_hooks(mut self, hooks: &[WebhookHook]) -> Self {
+        self.hooks = Some(hooks.to_vec());
+        self
+    }
+
+    pub fn with_payload_transformer<F>(mut self, transformer: F) -> Self
+    where
+        F: Fn(&WebhookEvent) -> Option<Value> + Send + Sync + 'static,
+    {
+        self.transformer = Some(Arc::new(transformer));
+        self
+    }
+
+    /// Number of payloads sent together as a JSON array, even when it's one. Pending payloads are
+    /// always sent once the test ends. By default, every payload is sent alone as a JSON object.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        if batch_size == 0 {
+            panic!("The batch size of the webhook sink must be greater than zero");
+        }
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = Some(retry_backoff);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn build(self) -> WebhookSink {
+        WebhookSink::new(
+            self.url.expect("Webhook url not provided"),
+            self.headers,
+            self.hooks.unwrap_or(vec![WebhookHook::TestEnded]),
+            self.transformer
+                .unwrap_or(Arc::new(|event: &WebhookEvent| Some(event.to_payload()))),
+            DeliveryPolicy {
+                batch_size: self.batch_size,
+                max_retries: self.max_retries.unwrap_or(Self::DEFAULT_MAX_RETRIES),
+                retry_backoff: self.retry_backoff.unwrap_or(Self::DEFAULT_RETRY_BACKOFF),
+                timeout: self.timeout.unwrap_or(Self::DEFAULT_TIMEOUT),
+            },
+        )
+    }
+}
@@ -0,0 +1,229 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+pub type ArrivalFunction = Arc<dyn Fn(&mut StdRng, Duration) -> Duration + Send + Sync>;
+
+/// How the hits of a stage are spread within every interval. By default every interval starts
+/// with a burst of `rate` hits.
+#[derive(Clone)]
+pub struct Arrivals {
+    distribution: ArrivalDistribution,
+    seed: Option<u64>,
+}
+
+#[derive(Clone)]
+enum ArrivalDistribution {
+    Burst,
+    Uniform,
+    Poisson,
+    Custom(ArrivalFunction),
+}
+
+/// Arrival times of the hits of a stage, as offsets from the start of the stage.
+pub(crate) struct ArrivalSchedule {
+    distribution: ArrivalDistribution,
+    rng: StdRng,
+    rate: u32,
+    interval: Duration,
+    count: u32,
+    last_arrival: Duration,
+}
+
+impl Default for Arrivals {
+    fn default() -> Self {
+        Self::burst()
+    }
+}
+
+impl Arrivals {
+    /// Releases all the hits of an interval at its start.
+    pub fn burst() -> Self {
+        Self::new(ArrivalDistribution::Burst)
+    }
+
+    /// Paces the hits evenly, one every `interval / rate`.
+    pub fn uniform() -> Self {
+        Self::new(ArrivalDistribution::Uniform)
+    }
+
+    /// Spaces the hits with exponential inter-arrival times averaging `interval / rate`, as
+    /// independent users arriving at random do.
+    pub fn poisson() -> Self {
+        Self::new(ArrivalDistribution::Poisson)
+    }
+
+    /// Spaces the hits with the inter-arrival times returned by the function, which receives the
+    /// random generator of the stage along with the mean inter-arrival time `interval / rate`.
+    /// Inter-arrival times of zero are raised to one nanosecond, so that the arrivals always move
+    /// forward.
+    pub fn custom<F>(function: F) -> Self
+    where
+        F: Fn(&mut StdRng, Duration) -> Duration + Send + Sync + 'static,
+    {
+        Self::new(ArrivalDistribution::Custom(Arc::new(function)))
+    }
+
+    /// Seeds the random generator, so that every run produces the same arrival times.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self.distribution {
+            ArrivalDistribution::Burst => "burst",
+            ArrivalDistribution::Uniform => "uniform",
+            ArrivalDistribution::Poisson => "poisson",
+            ArrivalDistribution::Custom(_) => "custom",
+        }
+    }
+
+    pub(crate) fn schedule(&self, rate: u32, interval: Duration) -> ArrivalSchedule {
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        ArrivalSchedule {
+            distribution: self.distribution.clone(),
+            rng,
+            rate,
+            interval,
+            count: 0,
+            last_arrival: Duration::ZERO,
+        }
+    }
+
+    fn new(distribution: ArrivalDistribution) -> Self {
+        Self {
+            distribution,
+            seed: None,
+        }
+    }
+}
+
+impl ArrivalSchedule {
+    /// Shortest time between two arrivals, which keeps the schedule moving forward when the
+    /// interval is shorter than the rate or a custom distribution returns zero.
+    const MIN_GAP: Duration = Duration::from_nanos(1);
+
+    /// Returns the offset of the next hit from the start of the stage, or none when the stage
+    /// has no hits.
+    pub(crate) fn next_arrival(&mut self) -> Option<Duration> {
+        if self.rate == 0 {
+            return None;
+        }
+
+        let mean_gap = (self.interval / self.rate).max(Self::MIN_GAP);
+        let arrival = match &self.distribution {
+            ArrivalDistribution::Burst => self.interval * (self.count / self.rate),
+            ArrivalDistribution::Uniform => mean_gap * self.count,
+            ArrivalDistribution::Poisson => {
+                let sample = self.rng.gen::<f64>();
+                let gap = mean_gap.mul_f64(-(1f64 - sample).ln());
+                self.last_arrival + gap.max(Self::MIN_GAP)
+            }
+            ArrivalDistribution::Custom(function) => {
+                let gap = function(&mut self.rng, mean_gap);
+                self.last_arrival + gap.max(Self::MIN_GAP)
+            }
+        };
+
+        self.count = self.count.saturating_add(1);
+        self.last_arrival = arrival;
+        Some(arrival)
+    }
+
+    /// Counts the arrivals left before the given offset, which are the hits the generator did
+    /// not get to spawn. A stage without interval has no schedule to fall behind of.
+    pub(crate) fn count_until(&mut self, end: Duration) -> u128 {
+        if self.interval.is_zero() {
+            return 0;
+        }
+
+        let mut count = 0;
+        while self.next_arrival().is_some_and(|arrival| arrival < end) {
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arrivals(arrivals: Arrivals, rate: u32, count: usize) -> Vec<u128> {
+        let mut schedule = arrivals.schedule(rate, Duration::from_millis(1000));
+        (0..count)
+            .map(|_| schedule.next_arrival().unwrap().as_millis())
+            .collect()
+    }
+
+    #[test]
+    fn given_burst_arrivals_when_scheduling_then_releases_rate_at_every_interval() {
+        let actual = arrivals(Arrivals::burst(), 2, 5);
+
+        assert_eq!(actual, vec![0, 0, 1000, 1000, 2000]);
+    }
+
+    #[test]
+    fn given_uniform_arrivals_when_scheduling_then_paces_hits_evenly() {
+        let actual = arrivals(Arrivals::uniform(), 4, 6);
+
+        assert_eq!(actual, vec![0, 250, 500, 750, 1000, 1250]);
+    }
+
+    #[test]
+    fn given_seeded_poisson_arrivals_when_scheduling_then_is_reproducible() {
+        let first = arrivals(Arrivals::poisson().with_seed(7), 10, 1000);
+        let second = arrivals(Arrivals::poisson().with_seed(7), 10, 1000);
+
+        assert_eq!(first, second);
+        assert!(first.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!((90_000..110_000).contains(&first[999]));
+    }
+
+    #[test]
+    fn given_custom_arrivals_when_scheduling_then_adds_returned_gaps() {
+        let actual = arrivals(Arrivals::custom(|_, mean_gap| mean_gap * 2), 5, 3);
+
+        assert_eq!(actual, vec![400, 800, 1200]);
+    }
+
+    #[test]
+    fn given_remaining_arrivals_when_counting_until_end_then_returns_missed_hits() {
+        let mut schedule = Arrivals::uniform().schedule(4, Duration::from_millis(1000));
+        schedule.next_arrival();
+        schedule.next_arrival();
+
+        assert_eq!(schedule.count_until(Duration::from_millis(2000)), 6);
+    }
+
+    #[test]
+    fn given_custom_arrivals_returning_zero_when_counting_until_end_then_ends() {
+        let mut schedule =
+            Arrivals::custom(|_, _| Duration::ZERO).schedule(4, Duration::from_millis(1000));
+
+        assert_eq!(schedule.count_until(Duration::from_micros(1)), 999);
+    }
+
+    #[test]
+    fn given_interval_shorter_than_rate_when_scheduling_uniform_arrivals_then_moves_forward() {
+        let mut schedule = Arrivals::uniform().schedule(1000, Duration::from_nanos(10));
+        schedule.next_arrival();
+
+        assert_eq!(schedule.next_arrival(), Some(Duration::from_nanos(1)));
+        assert_eq!(schedule.count_until(Duration::from_nanos(100)), 98);
+    }
+
+    #[test]
+    fn given_zero_rate_when_scheduling_then_returns_no_arrivals() {
+        let mut schedule = Arrivals::uniform().schedule(0, Duration::from_secs(1));
+
+        assert!(schedule.next_arrival().is_none());
+    }
+}
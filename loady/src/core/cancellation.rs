@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Asks a running test to stop early, e.g. once the user aborts it from a sink.
+///
+/// A cancelled test ends its running stage and skips the remaining warm up and load steps, while
+/// its init and clean up steps still run and its results are reported as usual.
+#[derive(Clone, Default, Debug)]
+pub struct Cancellation {
+    state: Arc<CancellationState>,
+}
+
+#[derive(Default, Debug)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the test is cancelled.
+    pub async fn cancelled(&self) {
+        let notified = self.state.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+
+        notified.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use tokio::time::{timeout, Duration};
+
+    #[tokio::test]
+    async fn given_cancellation_when_cancelling_then_wakes_up_every_clone() {
+        let cancellation = Cancellation::default();
+        let waiter = cancellation.to_owned();
+        let handle = tokio::spawn(async move { waiter.cancelled().await });
+
+        tokio::task::yield_now().await;
+        cancellation.cancel();
+
+        assert!(timeout(Duration::from_secs(1), handle).await.is_ok());
+        assert!(cancellation.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn given_cancelled_token_when_waiting_then_returns_at_once() {
+        let cancellation = Cancellation::default();
+        cancellation.cancel();
+
+        let actual = timeout(Duration::from_millis(100), cancellation.cancelled()).await;
+
+        assert!(actual.is_ok());
+    }
+}
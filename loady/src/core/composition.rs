@@ -1,12 +1,29 @@
+use crate::core::arrivals::Arrivals;
+use crate::core::cancellation::Cancellation;
 use crate::core::context::TestContext;
+use crate::core::feeders::Feeder;
 use crate::core::functions::*;
+use crate::core::health::ProcessMonitor;
+use crate::core::registry::MetricsRegistry;
+use crate::core::reporting::LifecycleEvent;
+use crate::core::stats::{
+    GeneratorWarningStatus, InternalStepStatus, StagePlan, StageStatus, StepKind, StepOutcome,
+    StepPlan, StepStartedStatus, TestPlan,
+};
+use crate::core::transactions::Transactions;
+use crate::core::users;
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use std::fmt::Debug;
 use std::marker::Sync;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use tokio::time::sleep;
+use tokio::time::sleep_until;
 use tokio::time::{Duration, Instant};
 
 pub struct TestCase<'a, T: TestContext, U> {
@@ -15,6 +32,8 @@ pub struct TestCase<'a, T: TestContext, U> {
     pub test_context: Option<T>,
     pub test_steps: Vec<TestStep<'a, U>>,
     pub data: U,
+    pub metrics_registry: Option<MetricsRegistry>,
+    pub(crate) cancellation: Cancellation,
 }
 
 pub enum TestStep<'a, T> {
@@ -28,18 +47,224 @@ pub enum TestStep<'a, T> {
     Load {
         name: &'static str,
         stages: Vec<TestStepStage>,
-        action: Option<LoadFunction<'a, T>>,
+        action: Option<LoadAction<'a, T>>,
+        group: Option<&'static str>,
     },
     CleanUp {
         action: Option<CleanUpFunction<'a, T>>,
     },
 }
 
+#[derive(Clone)]
 pub struct TestStepStage {
     stage_name: &'static str,
     during: Duration,
     interval: Duration,
     rate: u32,
+    arrivals: Arrivals,
+}
+
+/// The action of a load step, optionally fed with the records of a feeder, recording
+/// sub-transactions or picked among several weighted actions.
+pub enum LoadAction<'a, T> {
+    Plain(LoadFunction<'a, T>),
+    Fed(Feeder, FeedLoadFunction<'a, T>),
+    Tracked(TransactionLoadFunction<'a, T>),
+    Mixed(Vec<WeightedAction<'a, T>>),
+    Users(UserHitFunction<'a, T>),
+}
+
+/// A hit ready to run, or the hit of a virtual user that is checked out, and initialised when
+/// needed, before the hit is timed.
+enum Hit<'a> {
+    Ready(LoadResult<'a>),
+    User(UserHitResult<'a>),
+}
+
+/// A named action of a mixed load step, picked for every hit in proportion to its weight.
+pub struct WeightedAction<'a, T> {
+    pub name: &'static str,
+    pub weight: u32,
+    pub action: LoadFunction<'a, T>,
+}
+
+/// Name, action, stages and started status of a load step that runs within a concurrent group.
+type ConcurrentLoad<U> = (
+    &'static str,
+    LoadAction<'static, U>,
+    Vec<TestStepStage>,
+    StepStartedStatus,
+);
+
+/// Contexts a load step records its hits into.
+#[derive(Clone)]
+struct LoadContexts<T> {
+    step: Arc<Mutex<T>>,
+    /// Context of the whole test, which accounts for the hits of every load step.
+    test: Option<Arc<Mutex<T>>>,
+}
+
+/// Notifies the boundaries of the stages of the step being executed, and the generator health
+/// thresholds the step crosses, while watching for the cancellation of the run.
+///
+/// The notifier never waits for the reporting pipeline, so a slow sink can't hold back the
+/// generator. Events that don't fit into the channel are dropped and counted instead.
+struct StageNotifier<'a> {
+    tx_event: &'a Sender<LifecycleEvent>,
+    step: &'a StepStartedStatus,
+    cancellation: &'a Cancellation,
+    crossed: Mutex<Vec<&'static str>>,
+    dropped: AtomicU64,
+}
+
+impl<'a> StageNotifier<'a> {
+    fn new(
+        tx_event: &'a Sender<LifecycleEvent>,
+        step: &'a StepStartedStatus,
+        cancellation: &'a Cancellation,
+    ) -> Self {
+        Self {
+            tx_event,
+            step,
+            cancellation,
+            crossed: Mutex::default(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Sleeps until the given time, or until the run is cancelled, and returns whether it was.
+    async fn wait_until(&self, time: Instant) -> bool {
+        tokio::select! {
+            _ = sleep_until(time) => {}
+            _ = self.cancellation.cancelled() => {}
+        }
+
+        self.is_cancelled()
+    }
+
+    fn started(&self, stage: &TestStepStage) {
+        let status = StageStatus::new(self.step, stage.to_plan(), 0);
+        self.notify(LifecycleEvent::StageStarted(status));
+    }
+
+    fn ended(&self, stage: &TestStepStage, elapsed: Duration) {
+        let status = StageStatus::new(self.step, stage.to_plan(), elapsed.as_millis());
+        self.notify(LifecycleEvent::StageEnded(status));
+    }
+
+    fn notify(&self, event: LifecycleEvent) {
+        if let Err(TrySendError::Full(_)) = self.tx_event.try_send(event) {
+            self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Moves the events dropped so far into the contexts of the step.
+    async fn record_dropped_events<T: TestContext>(&self, ctx: &LoadContexts<T>) {
+        let dropped_events = self.dropped.swap(0, Ordering::SeqCst) as u128;
+        if dropped_events == 0 {
+            return;
+        }
+
+        if let Some(test_ctx) = &ctx.test {
+            test_ctx.lock().await.add_dropped_events(dropped_events);
+        }
+        ctx.step.lock().await.add_dropped_events(dropped_events);
+    }
+
+    /// Warns only the first time the step crosses every threshold.
+    async fn check_health<T: TestContext>(&self, ctx: &LoadContexts<T>) {
+        self.record_dropped_events(ctx).await;
+        let health = ctx.step.lock().await.get_current_generator_health();
+        let mut crossed = self.crossed.lock().await;
+
+        for (threshold, warning) in health.get_crossed_thresholds() {
+            if crossed.contains(&threshold) {
+                continue;
+            }
+
+            crossed.push(threshold);
+            let status = GeneratorWarningStatus::new(self.step, threshold, warning);
+            self.notify(LifecycleEvent::GeneratorWarning(status));
+        }
+    }
+}
+
+impl<'a, T> LoadAction<'a, T> {
+    /// Returns the name of the picked action, if any, along with its hit. Returns `None` once the
+    /// feeder runs out of records, which ends the step.
+    fn invoke(
+        &self,
+        data: &Arc<T>,
+        transactions: &Transactions,
+    ) -> Option<(Option<&'static str>, Hit<'a>)> {
+        match self {
+            LoadAction::Plain(callback) => Some((None, Hit::Ready(callback(Arc::clone(data))))),
+            LoadAction::Tracked(callback) => Some((
+                None,
+                Hit::Ready(callback(
+                    Arc::clone(data),
+                    Transactions::clone(transactions),
+                )),
+            )),
+            LoadAction::Fed(feeder, callback) => feeder
+                .next_record()
+                .map(|record| (None, Hit::Ready(callback(Arc::clone(data), record)))),
+            LoadAction::Mixed(actions) => {
+                let action = Self::pick(
+                    actions,
+                    rand::thread_rng().gen_range(0..Self::total_weight(actions)),
+                );
+                Some((
+                    Some(action.name),
+                    Hit::Ready((action.action)(Arc::clone(data))),
+                ))
+            }
+            LoadAction::Users(callback) => Some((
+                None,
+                Hit::User(callback(
+                    Arc::clone(data),
+                    Transactions::clone(transactions),
+                )),
+            )),
+        }
+    }
+
+    fn total_weight(actions: &[WeightedAction<'a, T>]) -> u64 {
+        actions.iter().map(|action| action.weight as u64).sum()
+    }
+
+    /// Returns the action whose cumulative weight range holds the given point.
+    fn pick<'w>(actions: &'w [WeightedAction<'a, T>], mut point: u64) -> &'w WeightedAction<'a, T> {
+        for action in actions {
+            if point < action.weight as u64 {
+                return action;
+            }
+            point -= action.weight as u64;
+        }
+
+        unreachable!("The point is always lower than the total weight")
+    }
+}
+
+impl<'a, T> WeightedAction<'a, T> {
+    pub fn new(name: &'static str, weight: u32, action: LoadFunction<'a, T>) -> Self {
+        if weight == 0 {
+            panic!(
+                "The weight of the action {} must be greater than zero",
+                name
+            );
+        }
+
+        Self {
+            name,
+            weight,
+            action,
+        }
+    }
 }
 
 impl<'a, T, U> TestCase<'static, T, U>
@@ -54,9 +279,16 @@ where
             test_context: None,
             test_steps: Vec::default(),
             data,
+            metrics_registry: None,
+            cancellation: Cancellation::default(),
         }
     }
 
+    /// Reports the custom metrics recorded into the registry along with every step status.
+    pub fn with_metrics_registry(&mut self, registry: MetricsRegistry) {
+        self.metrics_registry = Some(registry);
+    }
+
     pub fn with_step(&mut self, test_step: TestStep<'static, U>) {
         match test_step {
             TestStep::Init { .. } => {
@@ -85,66 +317,197 @@ where
         &mut self,
         tx_action: &Sender<T>,
         tx_step: &Sender<T>,
-        tx_internal_step: &Sender<T>,
+        tx_event: &Sender<LifecycleEvent>,
     ) -> Result<(), &'static str> {
         if !self.has_load_steps() {
             return Err("No load steps have found!");
         }
 
         let mut data = self.data.clone();
-        let ctx = Arc::new(Mutex::new(T::new(self.test_name, self.test_suite)));
+        let mut ctx = T::new(self.test_name, self.test_suite);
+        if let Some(registry) = &self.metrics_registry {
+            ctx.set_metrics_registry(registry.to_owned());
+        }
+        let process_monitor = ProcessMonitor::start();
+        ctx.set_process_monitor(process_monitor.to_owned());
+        let ctx = Arc::new(Mutex::new(ctx));
         let mut load_start_time: Option<Instant> = None;
 
-        for test_step in &mut self.test_steps {
-            {
-                let mut ctx = ctx.lock().await;
-                ctx.set_current_step(test_step.get_name());
+        let plan = self.get_plan(ctx.lock().await.get_session_id());
+        _ = tx_event
+            .send(LifecycleEvent::TestStarted(plan.to_owned()))
+            .await;
+
+        let mut index = 0;
+        while index < self.test_steps.len() {
+            let is_load = matches!(
+                self.test_steps[index],
+                TestStep::WarmUp { .. } | TestStep::Load { .. }
+            );
+            if is_load && self.cancellation.is_cancelled() {
+                index += 1;
+                continue;
+            }
+
+            let group_size = self.get_concurrent_group_size(index);
+            if group_size > 1 {
+                let load_start_time = *load_start_time.get_or_insert(Instant::now());
+                let steps = self.test_steps[index..index + group_size]
+                    .iter_mut()
+                    .zip(&plan.steps[index..index + group_size])
+                    .filter_map(|(test_step, step_plan)| match test_step {
+                        TestStep::Load {
+                            name,
+                            stages,
+                            action,
+                            ..
+                        } => Some((
+                            *name,
+                            action.take().unwrap(),
+                            stages.to_owned(),
+                            StepStartedStatus {
+                                session_id: plan.session_id.to_owned(),
+                                test_name: plan.test_name.to_owned(),
+                                test_suite: plan.test_suite.to_owned(),
+                                step: step_plan.to_owned(),
+                            },
+                        )),
+                        _ => None,
+                    })
+                    .collect();
+                self.execute_concurrent_loads(
+                    steps,
+                    data.to_owned(),
+                    &ctx,
+                    tx_action,
+                    tx_step,
+                    tx_event,
+                    load_start_time,
+                )
+                .await;
+                index += group_size;
+                continue;
             }
 
-            match test_step {
+            let test_step = &mut self.test_steps[index];
+            let step_plan = plan.steps[index].to_owned();
+            index += 1;
+
+            let step = StepStartedStatus {
+                session_id: plan.session_id.to_owned(),
+                test_name: plan.test_name.to_owned(),
+                test_suite: plan.test_suite.to_owned(),
+                step: step_plan,
+            };
+            _ = tx_event
+                .send(LifecycleEvent::StepStarted(step.to_owned()))
+                .await;
+
+            let notifier = StageNotifier::new(tx_event, &step, &self.cancellation);
+            let step_start_time = Instant::now();
+
+            let outcome = match test_step {
                 TestStep::Init { action } => {
                     let action = action.take().unwrap();
-                    data = Self::execute_init(action, data.to_owned()).await;
-                    let ctx = ctx.lock().await;
-                    _ = tx_internal_step.send(ctx.to_owned()).await;
+                    match Self::execute_init(action, data.to_owned()).await {
+                        Ok(init_data) => {
+                            data = init_data;
+                            Some(StepOutcome::Succeeded)
+                        }
+                        Err(code) => {
+                            let reason = format!("Init operation has failed with code {}", code);
+                            let status = InternalStepStatus::new(
+                                &step,
+                                step_start_time.elapsed().as_millis(),
+                                StepOutcome::Failed(reason.to_owned()),
+                            );
+                            _ = tx_event
+                                .send(LifecycleEvent::InternalStepEnded(status))
+                                .await;
+                            panic!("{}", reason);
+                        }
+                    }
                 }
                 TestStep::WarmUp { action, stages } => {
                     let action = action.take().unwrap();
-                    Self::execute_warmup(action, data.to_owned(), stages).await;
-                    let ctx = ctx.lock().await;
-                    _ = tx_internal_step.send(ctx.to_owned()).await;
+                    let failures =
+                        Self::execute_warmup(action, data.to_owned(), stages, &notifier).await;
+                    let contexts = LoadContexts {
+                        step: Arc::clone(&ctx),
+                        test: None,
+                    };
+                    notifier.record_dropped_events(&contexts).await;
+                    match failures {
+                        0 => Some(StepOutcome::Succeeded),
+                        _ => Some(StepOutcome::Failed(format!(
+                            "{} warm up actions have panicked",
+                            failures
+                        ))),
+                    }
                 }
-                TestStep::Load { stages, action, .. } => {
+                TestStep::Load {
+                    name,
+                    stages,
+                    action,
+                    ..
+                } => {
                     let load_start_time = load_start_time.get_or_insert(Instant::now());
                     let action = action.take().unwrap();
+                    let step_ctx = ctx.lock().await.new_step(name);
+                    let contexts = LoadContexts {
+                        step: Arc::new(Mutex::new(step_ctx)),
+                        test: Some(Arc::clone(&ctx)),
+                    };
                     Self::execute_load(
                         action,
                         data.to_owned(),
                         stages,
-                        &ctx,
+                        &contexts,
                         tx_action,
+                        &notifier,
                         load_start_time.to_owned(),
                     )
                     .await;
-                    let ctx = ctx.lock().await;
-                    _ = tx_step.send(ctx.to_owned()).await;
+                    let step_ctx = contexts.step.lock().await;
+                    _ = tx_step.send(step_ctx.to_owned()).await;
+                    None
                 }
                 TestStep::CleanUp { action } => {
                     let action = action.take().unwrap();
                     Self::execute_cleanup(action, data.to_owned()).await;
-                    let step_ctx = ctx.lock().await;
-                    _ = tx_internal_step.send(step_ctx.to_owned()).await;
+                    Some(StepOutcome::Succeeded)
                 }
             };
+
+            if let Some(outcome) = outcome {
+                let status =
+                    InternalStepStatus::new(&step, step_start_time.elapsed().as_millis(), outcome);
+                _ = tx_event
+                    .send(LifecycleEvent::InternalStepEnded(status))
+                    .await;
+            }
         }
 
+        process_monitor.stop();
         let ctx = ctx.lock().await;
         self.test_context = Some(ctx.to_owned());
         Ok(())
     }
 
-    async fn execute_init(callback: InitFunction<'static, U>, data: U) -> U {
-        callback(data).await.expect("Init operation has failed")
+    fn get_plan(&self, session_id: String) -> TestPlan {
+        let now: DateTime<Utc> = SystemTime::now().into();
+
+        TestPlan {
+            session_id,
+            test_name: self.test_name.to_owned(),
+            test_suite: self.test_suite.to_owned(),
+            started_at: now.to_rfc3339(),
+            steps: self.test_steps.iter().map(TestStep::to_plan).collect(),
+        }
+    }
+
+    async fn execute_init(callback: InitFunction<'static, U>, data: U) -> Result<U, i32> {
+        callback(data).await
     }
 
     async fn execute_cleanup(callback: CleanUpFunction<'static, U>, data: U) {
@@ -155,16 +518,18 @@ where
         callback: WarmUpFunction<'static, U>,
         data: U,
         stages: &Vec<TestStepStage>,
-    ) {
+        notifier: &StageNotifier<'_>,
+    ) -> usize {
         let data = Arc::new(data);
         let callback = Arc::new(callback);
         let mut handles: Vec<JoinHandle<()>> = Vec::new();
 
         for test_stage in stages {
+            notifier.started(test_stage);
             let stage_start_time = Instant::now();
             let mut next_period = stage_start_time;
 
-            while stage_start_time.elapsed() <= test_stage.during {
+            while !notifier.is_cancelled() && stage_start_time.elapsed() <= test_stage.during {
                 for _ in 0..test_stage.rate {
                     let data = Arc::clone(&data);
                     let callback = Arc::clone(&callback);
@@ -176,61 +541,223 @@ where
                     handles.push(handle);
                 }
 
-                next_period =
-                    Self::sleep_for(&stage_start_time, &next_period, &test_stage.interval).await;
+                next_period = next_period
+                    .checked_add(test_stage.interval)
+                    .unwrap_or(stage_start_time);
+                notifier.wait_until(next_period).await;
+            }
+
+            notifier.ended(test_stage, stage_start_time.elapsed());
+            if notifier.is_cancelled() {
+                break;
             }
         }
 
+        let mut failures = 0;
+        for handle in handles {
+            if handle.await.is_err() {
+                failures += 1;
+            }
+        }
+
+        failures
+    }
+
+    /// Runs the load steps of a concurrent group at the same time. Every step records its hits
+    /// into its own context of the test session, so that its metrics and its ended event only
+    /// account for its own hits, while the context of the test accounts for all of them.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_concurrent_loads(
+        &self,
+        steps: Vec<ConcurrentLoad<U>>,
+        data: U,
+        ctx: &Arc<Mutex<T>>,
+        tx_action: &Sender<T>,
+        tx_step: &Sender<T>,
+        tx_event: &Sender<LifecycleEvent>,
+        load_start_time: Instant,
+    ) {
+        let mut handles: Vec<JoinHandle<()>> = Vec::new();
+
+        for (name, action, stages, step) in steps {
+            let step_ctx = ctx.lock().await.new_step(name);
+            _ = tx_event
+                .send(LifecycleEvent::StepStarted(step.to_owned()))
+                .await;
+
+            let contexts = LoadContexts {
+                step: Arc::new(Mutex::new(step_ctx)),
+                test: Some(Arc::clone(ctx)),
+            };
+            let data = data.to_owned();
+            let tx_action = Sender::clone(tx_action);
+            let tx_step = Sender::clone(tx_step);
+            let tx_event = Sender::clone(tx_event);
+            let cancellation = self.cancellation.to_owned();
+
+            handles.push(tokio::spawn(async move {
+                let notifier = StageNotifier::new(&tx_event, &step, &cancellation);
+                Self::execute_load(
+                    action,
+                    data,
+                    &stages,
+                    &contexts,
+                    &tx_action,
+                    &notifier,
+                    load_start_time,
+                )
+                .await;
+                let step_ctx = contexts.step.lock().await;
+                _ = tx_step.send(step_ctx.to_owned()).await;
+            }));
+        }
+
         for handle in handles {
             _ = handle.await;
         }
     }
 
     async fn execute_load(
-        callback: LoadFunction<'static, U>,
+        action: LoadAction<'static, U>,
         data: U,
         stages: &Vec<TestStepStage>,
-        ctx: &Arc<Mutex<T>>,
+        ctx: &LoadContexts<T>,
         tx_action: &Sender<T>,
+        notifier: &StageNotifier<'_>,
         load_start_time: Instant,
     ) {
         let data = Arc::new(data);
-        let callback = Arc::new(callback);
+        let in_flight = Arc::new(AtomicU64::new(0));
         let mut handles: Vec<JoinHandle<()>> = Vec::new();
+        // Set once the feeder runs out of records or the run is cancelled.
+        let mut is_stopped = false;
+        let mut health_checked_at = Instant::now();
 
         for test_stage in stages {
-            ctx.lock().await.set_current_stage(test_stage.stage_name);
+            ctx.step
+                .lock()
+                .await
+                .set_current_stage(test_stage.stage_name);
+            notifier.started(test_stage);
 
             let stage_start_time = Instant::now();
-            let mut next_period = stage_start_time;
-
-            while stage_start_time.elapsed() < test_stage.during {
-                for _ in 0..test_stage.rate {
-                    let action_transmitter = Sender::clone(tx_action);
-                    let ctx = Arc::clone(ctx);
-                    let data = Arc::clone(&data);
-                    let callback = Arc::clone(&callback);
+            let mut schedule = test_stage
+                .arrivals
+                .schedule(test_stage.rate, test_stage.interval);
+
+            while !is_stopped && stage_start_time.elapsed() < test_stage.during {
+                let scheduled_at = match schedule.next_arrival() {
+                    Some(arrival) if arrival < test_stage.during => stage_start_time + arrival,
+                    _ => {
+                        notifier
+                            .wait_until(stage_start_time + test_stage.during)
+                            .await;
+                        break;
+                    }
+                };
+                if notifier.wait_until(scheduled_at).await {
+                    break;
+                }
 
-                    let handle = tokio::spawn(async move {
-                        let action_start_time = Instant::now();
-                        let action_result = callback(data).await;
-                        let mut ctx = ctx.lock().await;
-                        ctx.add_hit(action_result, action_start_time.elapsed());
-                        ctx.set_current_load_duration(load_start_time.elapsed());
-                        _ = action_transmitter.send(ctx.to_owned()).await;
-                    });
+                let transactions = Transactions::default();
+                let Some((action_name, hit)) = action.invoke(&data, &transactions) else {
+                    is_stopped = true;
+                    break;
+                };
+
+                let action_transmitter = Sender::clone(tx_action);
+                let contexts = LoadContexts::clone(ctx);
+                let in_flight = Arc::clone(&in_flight);
+                let started = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                let spawn_lag = Instant::now().saturating_duration_since(scheduled_at);
+
+                let handle = tokio::spawn(async move {
+                    let hit = match hit {
+                        Hit::Ready(hit) => Some(hit),
+                        Hit::User(checkout) => checkout.await,
+                    };
+                    let action_start_time = Instant::now();
+                    let action_result = match hit {
+                        Some(hit) => Some(hit.await),
+                        None => None,
+                    };
+                    let elapsed = action_start_time.elapsed();
+                    let schedule_lag = action_start_time.saturating_duration_since(scheduled_at);
+                    let records = transactions.take();
+
+                    if let Some(test_ctx) = &contexts.test {
+                        let mut test_ctx = test_ctx.lock().await;
+                        if let Some(action_result) = action_result {
+                            test_ctx.add_hit(action_result, elapsed);
+                            test_ctx.add_schedule_timing(elapsed + schedule_lag, schedule_lag);
+                        }
+                        test_ctx.add_spawn_lag(spawn_lag);
+                        test_ctx.set_current_max_in_flight(started as u128);
+                        for record in &records {
+                            test_ctx.add_transaction_hit(
+                                record.name,
+                                record.result,
+                                record.duration,
+                            );
+                        }
+                        test_ctx.set_current_load_duration(load_start_time.elapsed());
+                    }
+
+                    let mut ctx = contexts.step.lock().await;
+                    let pending = in_flight.fetch_sub(1, Ordering::SeqCst) - 1;
+                    if let Some(action_result) = action_result {
+                        match action_name {
+                            Some(action_name) => {
+                                ctx.add_action_hit(action_name, action_result, elapsed)
+                            }
+                            None => ctx.add_hit(action_result, elapsed),
+                        }
+                        ctx.add_schedule_timing(elapsed + schedule_lag, schedule_lag);
+                    }
+                    ctx.add_spawn_lag(spawn_lag);
+                    ctx.set_current_max_in_flight(started as u128);
+                    for record in records {
+                        ctx.add_transaction_hit(record.name, record.result, record.duration);
+                    }
+                    ctx.set_current_in_flight(pending as u128);
+                    ctx.set_current_load_duration(load_start_time.elapsed());
+                    let snapshot = ctx.to_owned();
+                    drop(ctx);
+                    // Snapshots are cumulative, so one that doesn't fit is superseded by the next.
+                    _ = action_transmitter.try_send(snapshot);
+                });
+
+                handles.push(handle);
+
+                if health_checked_at.elapsed() >= ProcessMonitor::SAMPLE_INTERVAL {
+                    notifier.check_health(ctx).await;
+                    health_checked_at = Instant::now();
+                }
+            }
 
-                    handles.push(handle);
+            is_stopped |= notifier.is_cancelled();
+            let dropped_hits = match is_stopped {
+                true => 0,
+                false => schedule.count_until(test_stage.during),
+            };
+            if dropped_hits > 0 {
+                if let Some(test_ctx) = &ctx.test {
+                    test_ctx.lock().await.add_dropped_hits(dropped_hits);
                 }
+                ctx.step.lock().await.add_dropped_hits(dropped_hits);
+            }
+            notifier.check_health(ctx).await;
 
-                next_period =
-                    Self::sleep_for(&stage_start_time, &next_period, &test_stage.interval).await;
+            notifier.ended(test_stage, stage_start_time.elapsed());
+            if is_stopped {
+                break;
             }
         }
 
         for handle in handles {
             _ = handle.await;
         }
+        notifier.check_health(ctx).await;
     }
 
     fn has_load_steps(&self) -> bool {
@@ -240,6 +767,24 @@ where
         })
     }
 
+    /// Returns the number of consecutive load steps sharing the group of the step at the given
+    /// position, which run concurrently.
+    fn get_concurrent_group_size(&self, index: usize) -> usize {
+        let TestStep::Load {
+            group: Some(group), ..
+        } = &self.test_steps[index]
+        else {
+            return 1;
+        };
+
+        self.test_steps[index..]
+            .iter()
+            .take_while(
+                |step| matches!(step, TestStep::Load { group: Some(other), .. } if other == group),
+            )
+            .count()
+    }
+
     fn has_init_step(&self) -> bool {
         self.test_steps
             .iter()
@@ -257,21 +802,6 @@ where
             .iter()
             .any(|step| matches!(step, TestStep::CleanUp { .. }))
     }
-
-    async fn sleep_for(
-        stage_start_time: &Instant,
-        next_period: &Instant,
-        interval: &Duration,
-    ) -> Instant {
-        let next_period = next_period
-            .checked_add(*interval)
-            .unwrap_or(*stage_start_time);
-        if let Some(time) = next_period.checked_duration_since(Instant::now()) {
-            sleep(time).await;
-        }
-
-        next_period
-    }
 }
 
 impl<'a, T> PartialEq for TestStep<'a, T> {
@@ -309,8 +839,103 @@ impl<'a, T> TestStep<'a, T> {
     ) -> Self {
         Self::Load {
             name,
-            action: Some(action),
+            action: Some(LoadAction::Plain(action)),
             stages,
+            group: None,
+        }
+    }
+
+    /// Creates a load step where every hit receives the next record of the feeder. A sequential
+    /// or unique feeder ends the step once its records are exhausted.
+    pub fn as_feed_load(
+        name: &'static str,
+        feeder: Feeder,
+        action: FeedLoadFunction<'a, T>,
+        stages: Vec<TestStepStage>,
+    ) -> Self {
+        Self::Load {
+            name,
+            action: Some(LoadAction::Fed(feeder, action)),
+            stages,
+            group: None,
+        }
+    }
+
+    /// Creates a load step whose action records named sub-transactions through the given
+    /// handle, such as the requests of a user journey. Every sub-transaction is reported as a
+    /// separate metric series.
+    pub fn as_transaction_load(
+        name: &'static str,
+        action: TransactionLoadFunction<'a, T>,
+        stages: Vec<TestStepStage>,
+    ) -> Self {
+        Self::Load {
+            name,
+            action: Some(LoadAction::Tracked(action)),
+            stages,
+            group: None,
+        }
+    }
+
+    /// Creates a load step where every hit runs one of the actions, picked in proportion to its
+    /// weight. The metrics of every action are reported under the step.
+    pub fn as_mixed_load(
+        name: &'static str,
+        actions: Vec<WeightedAction<'a, T>>,
+        stages: Vec<TestStepStage>,
+    ) -> Self {
+        if actions.is_empty() {
+            panic!("A mixed load step needs at least one action");
+        }
+
+        Self::Load {
+            name,
+            action: Some(LoadAction::Mixed(actions)),
+            stages,
+            group: None,
+        }
+    }
+
+    /// Creates a load step where every virtual user keeps its own mutable state, created by
+    /// `init` with the user index and handed to each `action` along with its iteration number.
+    /// Users are initialised before their hit is timed, and every initialisation is recorded as
+    /// a sub-transaction of the step.
+    pub fn as_user_load<V>(
+        name: &'static str,
+        init: UserInitFunction<'a, T, V>,
+        action: UserLoadFunction<'a, T, V>,
+        stages: Vec<TestStepStage>,
+    ) -> Self
+    where
+        T: Send + Sync + 'a,
+        V: Send + Sync + 'a,
+    {
+        Self::Load {
+            name,
+            action: Some(LoadAction::Users(users::into_user_hit_function(
+                init, action,
+            ))),
+            stages,
+            group: None,
+        }
+    }
+
+    /// Adds a load step to a concurrent group. Consecutive load steps of the same group run at
+    /// the same time, each one with its own stages, metrics and ended event.
+    pub fn in_group(self, group: &'static str) -> Self {
+        match self {
+            Self::Load {
+                name,
+                stages,
+                action,
+                ..
+            } => Self::Load {
+                name,
+                stages,
+                action,
+                group: Some(group),
+            },
+            _ => panic!("Only 'Load' step types can run concurrently"),
         }
     }
 
@@ -320,6 +945,36 @@ impl<'a, T> TestStep<'a, T> {
         }
     }
 
+    fn get_kind(&self) -> StepKind {
+        match self {
+            TestStep::Init { .. } => StepKind::Init,
+            TestStep::WarmUp { .. } => StepKind::WarmUp,
+            TestStep::Load { .. } => StepKind::Load,
+            TestStep::CleanUp { .. } => StepKind::CleanUp,
+        }
+    }
+
+    fn to_plan(&self) -> StepPlan {
+        let stages = match self {
+            TestStep::WarmUp { stages, .. } | TestStep::Load { stages, .. } => {
+                stages.iter().map(TestStepStage::to_plan).collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let group = match self {
+            TestStep::Load { group, .. } => group.map(str::to_owned),
+            _ => None,
+        };
+
+        StepPlan {
+            step_name: self.get_name().to_owned(),
+            kind: self.get_kind(),
+            stages,
+            group,
+        }
+    }
+
     fn get_order(&self) -> usize {
         match self {
             TestStep::Init { .. } => 0,
@@ -346,6 +1001,24 @@ impl TestStepStage {
             during,
             interval,
             rate,
+            arrivals: Arrivals::default(),
+        }
+    }
+
+    /// Spreads the hits of the stage within every interval following the given arrivals,
+    /// instead of releasing them in a burst. Only load steps honour the arrivals.
+    pub fn with_arrivals(mut self, arrivals: Arrivals) -> Self {
+        self.arrivals = arrivals;
+        self
+    }
+
+    fn to_plan(&self) -> StagePlan {
+        StagePlan {
+            stage_name: self.stage_name.to_owned(),
+            during: self.during.as_millis(),
+            interval: self.interval.as_millis(),
+            rate: self.rate,
+            arrivals: self.arrivals.get_name().to_owned(),
         }
     }
 }
@@ -355,7 +1028,11 @@ mod tests {
 
     use super::*;
     use crate::core::context::TestCaseContext;
+    use crate::core::feeders::FeederStrategy;
+    use crate::core::stats::{GeneratorHealth, TestStatus};
+    use crate::core::users::VirtualUser;
     use std::matches;
+    use tokio::time::sleep;
 
     const TEST_NAME: &str = "test name";
     const TEST_SUITE: &str = "test_suite";
@@ -508,4 +1185,645 @@ mod tests {
         let step = TestStep::<'static, EmptyData>::as_clean_up(Box::new(cleanup));
         assert!(matches!(step, TestStep::CleanUp { .. }));
     }
+
+    #[tokio::test]
+    async fn given_test_case_with_all_steps_when_running_then_notifies_lifecycle_events() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+
+        let stages = || {
+            vec![
+                TestStepStage::new(
+                    "first",
+                    Duration::from_millis(20),
+                    Duration::from_millis(10),
+                    1,
+                ),
+                TestStepStage::new(
+                    "second",
+                    Duration::from_millis(20),
+                    Duration::from_millis(10),
+                    1,
+                ),
+            ]
+        };
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_init(Box::new(init)));
+        test_case.with_step(TestStep::as_warm_up(Box::new(warmup), stages()));
+        test_case.with_step(TestStep::as_load("load", Box::new(load), stages()));
+        test_case.with_step(TestStep::as_clean_up(Box::new(cleanup)));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        drop(tx_event);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx_event.recv().await {
+            events.push(event);
+        }
+
+        // Generator warnings depend on how loaded the machine running the tests is.
+        let names = events
+            .iter()
+            .filter_map(|event| match event {
+                LifecycleEvent::TestStarted(_) => Some(String::from("test started")),
+                LifecycleEvent::StepStarted(status) => {
+                    Some(format!("{} started", status.step.step_name))
+                }
+                LifecycleEvent::StageStarted(status) => {
+                    Some(format!("{} started", status.stage.stage_name))
+                }
+                LifecycleEvent::StageEnded(status) => {
+                    Some(format!("{} ended", status.stage.stage_name))
+                }
+                LifecycleEvent::InternalStepEnded(status) => {
+                    Some(format!("{} ended", status.step_name))
+                }
+                LifecycleEvent::GeneratorWarning(_) => None,
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            names,
+            vec![
+                "test started",
+                "Init started",
+                "Init ended",
+                "Warm Up started",
+                "first started",
+                "first ended",
+                "second started",
+                "second ended",
+                "Warm Up ended",
+                "load started",
+                "first started",
+                "first ended",
+                "second started",
+                "second ended",
+                "Clean Up started",
+                "Clean Up ended",
+            ]
+        );
+
+        match &events[0] {
+            LifecycleEvent::TestStarted(plan) => {
+                assert_eq!(plan.test_suite, TEST_SUITE);
+                assert_eq!(plan.steps.len(), 4);
+                assert_eq!(plan.steps[2].kind, StepKind::Load);
+                assert_eq!(plan.steps[2].stages[1].during, 20);
+            }
+            _ => panic!("The first event must be the test plan"),
+        }
+
+        match &events[2] {
+            LifecycleEvent::InternalStepEnded(status) => {
+                assert_eq!(status.kind, StepKind::Init);
+                assert_eq!(status.outcome, StepOutcome::Succeeded);
+            }
+            _ => panic!("The init step must end with a structured status"),
+        }
+    }
+
+    fn fed_load(_ctx: Arc<EmptyData>, record: serde_json::Value) -> LoadResult<'static> {
+        Box::pin(async move {
+            match record.as_i64() {
+                Some(2) => Err(500),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn given_fed_load_step_when_feeder_is_exhausted_then_ends_step() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+
+        let stages = vec![
+            TestStepStage::new(
+                "first",
+                Duration::from_secs(10),
+                Duration::from_millis(10),
+                2,
+            ),
+            TestStepStage::new("second", Duration::from_secs(10), Duration::from_secs(1), 1),
+        ];
+        let feeder = Feeder::from_sequence(0..5, FeederStrategy::Sequential);
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_feed_load(
+            "load",
+            feeder,
+            Box::new(fed_load),
+            stages,
+        ));
+
+        let start_time = Instant::now();
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        drop(tx_event);
+
+        let mut stages_started = Vec::new();
+        while let Some(event) = rx_event.recv().await {
+            if let LifecycleEvent::StageStarted(status) = event {
+                stages_started.push(status.stage.stage_name);
+            }
+        }
+
+        let ctx = test_case.test_context.unwrap();
+        assert!(start_time.elapsed() < Duration::from_secs(2));
+        assert_eq!(ctx.get_successful_hits(), 4);
+        assert_eq!(ctx.get_unsuccessful_hits(), 1);
+        assert_eq!(stages_started, vec!["first"]);
+    }
+
+    #[test]
+    fn given_weighted_actions_when_picking_then_uses_cumulative_weights() {
+        let actions = vec![
+            WeightedAction::<EmptyData>::new("browse", 3, Box::new(load)),
+            WeightedAction::<EmptyData>::new("buy", 1, Box::new(load)),
+        ];
+
+        let picked = (0..LoadAction::total_weight(&actions))
+            .map(|point| LoadAction::pick(&actions, point).name)
+            .collect::<Vec<&str>>();
+
+        assert_eq!(picked, vec!["browse", "browse", "browse", "buy"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn given_zero_weight_when_creating_weighted_action_then_panics() {
+        WeightedAction::<EmptyData>::new("browse", 0, Box::new(load));
+    }
+
+    fn journey(_ctx: Arc<EmptyData>, transactions: Transactions) -> LoadResult<'static> {
+        Box::pin(async move {
+            transactions.measure("login", async { Ok(()) }).await?;
+            transactions.measure("detail", async { Err(404) }).await
+        })
+    }
+
+    #[tokio::test]
+    async fn given_transaction_load_step_when_running_then_records_sub_transactions() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = vec![TestStepStage::new(
+            "first",
+            Duration::from_millis(20),
+            Duration::from_millis(100),
+            3,
+        )];
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_transaction_load(
+            "journey",
+            Box::new(journey),
+            stages,
+        ));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+
+        let ctx = test_case.test_context.unwrap();
+        let transactions = ctx.get_current_transactions();
+        assert_eq!(ctx.get_unsuccessful_hits(), 3);
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].0, "detail");
+        assert_eq!(transactions[0].1.get_unsuccessful_hits(), 3);
+        assert_eq!(transactions[1].0, "login");
+        assert_eq!(transactions[1].1.get_successful_hits(), 3);
+    }
+
+    #[tokio::test]
+    async fn given_transaction_load_steps_and_clean_up_when_running_then_test_combines_transactions(
+    ) {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = || {
+            vec![TestStepStage::new(
+                "first",
+                Duration::from_millis(20),
+                Duration::from_millis(100),
+                3,
+            )]
+        };
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_transaction_load(
+            "browse",
+            Box::new(journey),
+            stages(),
+        ));
+        test_case.with_step(TestStep::as_transaction_load(
+            "buy",
+            Box::new(journey),
+            stages(),
+        ));
+        test_case.with_step(TestStep::as_clean_up(Box::new(cleanup)));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        drop(tx_step);
+
+        while let Some(step_ctx) = rx_step.recv().await {
+            let transactions = step_ctx.get_current_transactions();
+            assert_eq!(transactions.len(), 2);
+            assert_eq!(transactions[1].1.get_successful_hits(), 3);
+        }
+        let ctx = test_case.test_context.unwrap();
+        let status = TestStatus::new(String::from(TEST_NAME), ctx);
+        let transactions = status.metrics.transactions;
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].transaction_name, "detail");
+        assert_eq!(transactions[0].metrics.negative_hits, 6);
+        assert_eq!(transactions[1].transaction_name, "login");
+        assert_eq!(transactions[1].metrics.positive_hits, 6);
+    }
+
+    #[tokio::test]
+    async fn given_cancelled_run_when_running_then_ends_load_and_still_cleans_up() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+
+        let stages = || {
+            vec![TestStepStage::new(
+                "long",
+                Duration::from_secs(5),
+                Duration::from_millis(10),
+                1,
+            )]
+        };
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_load("first", Box::new(load), stages()));
+        test_case.with_step(TestStep::as_load("second", Box::new(load), stages()));
+        test_case.with_step(TestStep::as_clean_up(Box::new(cleanup)));
+        let cancellation = test_case.cancellation.to_owned();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            cancellation.cancel();
+        });
+
+        let start_time = Instant::now();
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        let elapsed = start_time.elapsed();
+        drop(tx_event);
+
+        let mut names = Vec::new();
+        while let Some(event) = rx_event.recv().await {
+            names.push(match event {
+                LifecycleEvent::StepStarted(status) => format!("{} started", status.step.step_name),
+                LifecycleEvent::InternalStepEnded(status) => format!("{} ended", status.step_name),
+                LifecycleEvent::StageStarted(status) => {
+                    format!("{} started", status.stage.stage_name)
+                }
+                LifecycleEvent::StageEnded(status) => format!("{} ended", status.stage.stage_name),
+                _ => continue,
+            });
+        }
+
+        assert!(elapsed < Duration::from_secs(1));
+        assert!(test_case.test_context.unwrap().get_hits() > 0);
+        assert_eq!(
+            names,
+            vec![
+                "first started",
+                "long started",
+                "long ended",
+                "Clean Up started",
+                "Clean Up ended",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn given_user_load_step_when_running_then_times_hits_apart_from_user_init() {
+        fn init_user(_data: Arc<EmptyData>, index: usize) -> UserInitResult<'static, usize> {
+            Box::pin(async move {
+                sleep(Duration::from_millis(100)).await;
+                match index {
+                    0 => Err(503),
+                    _ => Ok(index),
+                }
+            })
+        }
+
+        fn user_load(_data: Arc<EmptyData>, _user: &mut VirtualUser<usize>) -> LoadResult<'_> {
+            Box::pin(async move { Ok(()) })
+        }
+
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = vec![TestStepStage::new(
+            "first",
+            Duration::from_millis(300),
+            Duration::from_millis(150),
+            1,
+        )];
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_user_load(
+            "users",
+            Box::new(init_user),
+            Box::new(user_load),
+            stages,
+        ));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+
+        let ctx = test_case.test_context.unwrap();
+        let transactions = ctx.get_current_transactions();
+        assert!(ctx.get_hits() >= 1);
+        assert!(ctx.get_current_max_time() < 100);
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].0, users::USER_INIT);
+        assert_eq!(transactions[0].1.get_successful_hits(), 1);
+        assert_eq!(transactions[0].1.get_unsuccessful_hits(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_metrics_registry_when_running_then_context_reports_custom_metrics() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let registry = MetricsRegistry::default();
+        let recorder = registry.clone();
+        let stages = vec![TestStepStage::new(
+            "first",
+            Duration::from_millis(20),
+            Duration::from_millis(100),
+            3,
+        )];
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_metrics_registry(registry);
+        test_case.with_step(TestStep::as_load(
+            "load",
+            Box::new(move |_| {
+                let recorder = recorder.clone();
+                Box::pin(async move {
+                    recorder.increment_counter("items_returned", 2);
+                    Ok(())
+                })
+            }),
+            stages,
+        ));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+
+        let custom_metrics = test_case.test_context.unwrap().get_current_custom_metrics();
+        assert_eq!(custom_metrics.counters.get("items_returned"), Some(&6));
+    }
+
+    #[tokio::test]
+    async fn given_load_steps_in_concurrent_group_when_running_then_runs_them_at_the_same_time() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = |rate| {
+            vec![TestStepStage::new(
+                "first",
+                Duration::from_millis(300),
+                Duration::from_millis(100),
+                rate,
+            )]
+        };
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(
+            TestStep::as_load("background", Box::new(load), stages(1)).in_group("traffic"),
+        );
+        test_case
+            .with_step(TestStep::as_load("spike", Box::new(load), stages(4)).in_group("traffic"));
+        test_case.with_step(TestStep::as_load("after", Box::new(load), stages(1)));
+
+        let start_time = Instant::now();
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        let elapsed = start_time.elapsed();
+        drop(tx_step);
+
+        let mut steps = Vec::new();
+        while let Some(ctx) = rx_step.recv().await {
+            steps.push((ctx.get_current_step_name(), ctx.get_hits()));
+        }
+        steps.sort();
+
+        let ctx = test_case.test_context.unwrap();
+        assert!(elapsed < Duration::from_millis(850));
+        assert_eq!(
+            steps,
+            vec![
+                (String::from("after"), 3),
+                (String::from("background"), 3),
+                (String::from("spike"), 12),
+            ]
+        );
+        assert_eq!(ctx.get_hits(), 18);
+    }
+
+    #[tokio::test]
+    async fn given_load_step_before_concurrent_group_when_running_then_group_steps_count_own_hits()
+    {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = |rate| {
+            vec![TestStepStage::new(
+                "first",
+                Duration::from_millis(300),
+                Duration::from_millis(100),
+                rate,
+            )]
+        };
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_load("before", Box::new(load), stages(2)));
+        test_case.with_step(
+            TestStep::as_load("background", Box::new(load), stages(1)).in_group("traffic"),
+        );
+        test_case
+            .with_step(TestStep::as_load("spike", Box::new(load), stages(4)).in_group("traffic"));
+
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        drop(tx_step);
+
+        let ctx = test_case.test_context.unwrap();
+        let mut steps = Vec::new();
+        while let Some(step_ctx) = rx_step.recv().await {
+            assert_eq!(step_ctx.get_session_id(), ctx.get_session_id());
+            steps.push((step_ctx.get_current_step_name(), step_ctx.get_hits()));
+        }
+        steps.sort();
+
+        assert_eq!(
+            steps,
+            vec![
+                (String::from("background"), 3),
+                (String::from("before"), 6),
+                (String::from("spike"), 12),
+            ]
+        );
+        assert_eq!(ctx.get_hits(), 21);
+    }
+
+    #[tokio::test]
+    async fn given_uniform_arrivals_when_running_then_spreads_hits_within_interval() {
+        let (tx_action, mut rx_action) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_step, mut rx_step) = tokio::sync::mpsc::channel::<TestCaseContext>(10);
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(100);
+        tokio::spawn(async move { while rx_action.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_step.recv().await.is_some() {} });
+        tokio::spawn(async move { while rx_event.recv().await.is_some() {} });
+
+        let stages = vec![TestStepStage::new(
+            "paced",
+            Duration::from_millis(400),
+            Duration::from_millis(400),
+            4,
+        )
+        .with_arrivals(Arrivals::uniform())];
+        let mut test_case =
+            TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, EmptyData);
+        test_case.with_step(TestStep::as_load("paced", Box::new(load), stages));
+
+        let start_time = Instant::now();
+        test_case
+            .run(&tx_action, &tx_step, &tx_event)
+            .await
+            .unwrap();
+        let elapsed = start_time.elapsed();
+
+        let plan = test_case.get_plan(String::new());
+        let ctx = test_case.test_context.unwrap();
+        assert_eq!(ctx.get_hits(), 4);
+        assert!(elapsed >= Duration::from_millis(400));
+        assert_eq!(plan.steps[0].stages[0].arrivals, "uniform");
+    }
+
+    #[tokio::test]
+    async fn given_dropped_hits_when_checking_health_then_warns_only_once() {
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(10);
+        let step = StepStartedStatus {
+            session_id: String::from("session"),
+            test_name: TEST_NAME.to_owned(),
+            test_suite: TEST_SUITE.to_owned(),
+            step: TestStep::<'static, EmptyData>::as_load("load", Box::new(load), Vec::new())
+                .to_plan(),
+        };
+        let cancellation = Cancellation::default();
+        let notifier = StageNotifier::new(&tx_event, &step, &cancellation);
+        let mut ctx = TestCaseContext::default();
+        ctx.add_dropped_hits(3);
+        let ctx = LoadContexts {
+            step: Arc::new(Mutex::new(ctx)),
+            test: None,
+        };
+
+        notifier.check_health(&ctx).await;
+        ctx.step.lock().await.add_dropped_hits(2);
+        notifier.check_health(&ctx).await;
+        drop(notifier);
+        drop(tx_event);
+
+        let mut warnings = Vec::new();
+        while let Some(LifecycleEvent::GeneratorWarning(status)) = rx_event.recv().await {
+            warnings.push(status);
+        }
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].step_name, "load");
+        assert_eq!(warnings[0].threshold, GeneratorHealth::DROPPED_HITS);
+        assert!(warnings[0].warning.starts_with("3 hits were dropped"));
+    }
+
+    #[tokio::test]
+    async fn given_full_event_channel_when_notifying_then_counts_dropped_events() {
+        let (tx_event, mut rx_event) = tokio::sync::mpsc::channel::<LifecycleEvent>(1);
+        let step = StepStartedStatus {
+            session_id: String::from("session"),
+            test_name: TEST_NAME.to_owned(),
+            test_suite: TEST_SUITE.to_owned(),
+            step: TestStep::<'static, EmptyData>::as_load("load", Box::new(load), Vec::new())
+                .to_plan(),
+        };
+        let stage = TestStepStage::new("first", Duration::from_millis(10), Duration::ZERO, 1);
+        let cancellation = Cancellation::default();
+        let notifier = StageNotifier::new(&tx_event, &step, &cancellation);
+        let test_ctx = Arc::new(Mutex::new(TestCaseContext::default()));
+        let ctx = LoadContexts {
+            step: Arc::new(Mutex::new(TestCaseContext::default())),
+            test: Some(Arc::clone(&test_ctx)),
+        };
+
+        notifier.started(&stage);
+        notifier.ended(&stage, Duration::from_millis(10));
+        notifier.check_health(&ctx).await;
+
+        let step_health = ctx.step.lock().await.get_current_generator_health();
+        let test_health = test_ctx.lock().await.get_current_generator_health();
+        assert_eq!(step_health.dropped_events, 1);
+        assert_eq!(test_health.dropped_events, 1);
+        assert!(matches!(
+            rx_event.recv().await,
+            Some(LifecycleEvent::StageStarted(_))
+        ));
+        assert!(rx_event.try_recv().is_err());
+    }
 }
@@ -1,15 +1,43 @@
-use std::collections::{BTreeSet, HashMap};
+use crate::core::health::ProcessMonitor;
+use crate::core::registry::MetricsRegistry;
+use crate::core::stats::{
+    CustomMetrics, GeneratorHealth, LatencyHistogram, TimingRecorder, TimingSummary,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 
 pub trait TestContext: Default + Clone + Send {
     fn new(test_name: &'static str, test_suite: &'static str) -> Self;
+    /// Returns a context of the same test session that holds none of its hits, with the same
+    /// metrics registry and process monitor, to record the hits of the given step on their own.
+    fn new_step(&self, step_name: &'static str) -> Self;
     fn add_hit(&mut self, result: Result<(), i32>, duration: Duration);
+    fn add_action_hit(
+        &mut self,
+        action_name: &'static str,
+        result: Result<(), i32>,
+        duration: Duration,
+    );
+    fn add_transaction_hit(
+        &mut self,
+        transaction_name: &'static str,
+        result: Result<(), i32>,
+        duration: Duration,
+    );
+    /// Records when a hit ended relative to its scheduled start, and how late it started.
+    fn add_schedule_timing(&mut self, response_time: Duration, schedule_lag: Duration);
+    /// Records how late the generator spawned a hit.
+    fn add_spawn_lag(&mut self, spawn_lag: Duration);
+    fn add_dropped_hits(&mut self, dropped_hits: u128);
+    /// Records lifecycle events that were discarded instead of holding back the generator.
+    fn add_dropped_events(&mut self, dropped_events: u128);
     fn get_hits(&self) -> u128;
     fn get_successful_hits(&self) -> u128;
     fn get_unsuccessful_hits(&self) -> u128;
     fn get_session_id(&self) -> String;
     fn get_test_name(&self) -> String;
+    fn get_test_suite(&self) -> String;
     fn get_current_duration(&self) -> Duration;
     fn get_current_load_duration(&self) -> Duration;
     fn get_current_step_name(&self) -> String;
@@ -20,9 +48,28 @@ pub trait TestContext: Default + Clone + Send {
     fn get_current_percentile_time(&self, percentile: f64) -> u128;
     fn get_current_std_dev(&self) -> u128;
     fn get_current_errors(&self) -> HashMap<i32, u128>;
+    fn get_current_latency_histogram(&self) -> LatencyHistogram;
+    fn get_current_in_flight(&self) -> u128;
+    fn get_current_response_time(&self) -> TimingSummary;
+    fn get_current_schedule_lag(&self) -> TimingSummary;
+    fn get_current_generator_health(&self) -> GeneratorHealth;
+    /// Returns a context per named action of the current step, holding only the hits of that action.
+    fn get_current_actions(&self) -> Vec<(String, Self)>;
+    /// Returns a context per recorded sub-transaction of the current step, holding only the
+    /// timings of that sub-transaction. The context of the whole test has no current step, so
+    /// it combines the sub-transactions of every step.
+    fn get_current_transactions(&self) -> Vec<(String, Self)>;
+    /// Returns the custom metrics recorded since the test started, across all of its steps.
+    fn get_current_custom_metrics(&self) -> CustomMetrics;
     fn set_current_step(&mut self, step_name: &'static str);
     fn set_current_stage(&mut self, stage_name: &'static str);
     fn set_current_load_duration(&mut self, duration: Duration);
+    fn set_current_in_flight(&mut self, in_flight: u128);
+    /// Keeps the highest number of hits in flight at the same time.
+    fn set_current_max_in_flight(&mut self, max_in_flight: u128);
+    fn set_metrics_registry(&mut self, registry: MetricsRegistry);
+    /// Reports the process usage sampled by the monitor along with the generator health.
+    fn set_process_monitor(&mut self, monitor: ProcessMonitor);
 }
 
 #[derive(Default, Clone, Debug)]
@@ -34,6 +81,10 @@ pub struct TestCaseContext<'a> {
     pub test_stage_name: Option<&'a str>,
     test_started_at: Option<Instant>,
     test_metrics: TestContextMetrics,
+    action_metrics: BTreeMap<(&'a str, &'a str), TestContextMetrics>,
+    transaction_metrics: BTreeMap<(&'a str, &'a str), TestContextMetrics>,
+    metrics_registry: MetricsRegistry,
+    process_monitor: ProcessMonitor,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -41,8 +92,17 @@ struct TestContextMetrics {
     successful_hits: u128,
     unsuccessful_hits: u128,
     load_duration: Duration,
+    in_flight: u128,
     elapsed_times: BTreeSet<u128>,
+    latency_histogram: LatencyHistogram,
     errors: HashMap<i32, u128>,
+    response_time: TimingRecorder,
+    schedule_lag: TimingRecorder,
+    spawn_lag: TimingRecorder,
+    delayed_hits: u128,
+    dropped_hits: u128,
+    dropped_events: u128,
+    max_in_flight: u128,
 }
 
 impl<'a> TestContext for TestCaseContext<'a> {
@@ -55,6 +115,26 @@ impl<'a> TestContext for TestCaseContext<'a> {
             test_stage_name: None,
             test_started_at: Some(Instant::now()),
             test_metrics: TestContextMetrics::default(),
+            action_metrics: BTreeMap::default(),
+            transaction_metrics: BTreeMap::default(),
+            metrics_registry: MetricsRegistry::default(),
+            process_monitor: ProcessMonitor::default(),
+        }
+    }
+
+    fn new_step(&self, step_name: &'static str) -> Self {
+        TestCaseContext {
+            session_id: self.session_id,
+            test_name: self.test_name,
+            test_suite: self.test_suite,
+            test_step_name: Some(step_name),
+            test_stage_name: None,
+            test_started_at: self.test_started_at,
+            test_metrics: TestContextMetrics::default(),
+            action_metrics: BTreeMap::default(),
+            transaction_metrics: BTreeMap::default(),
+            metrics_registry: self.metrics_registry.to_owned(),
+            process_monitor: self.process_monitor.to_owned(),
         }
     }
 
@@ -63,14 +143,56 @@ impl<'a> TestContext for TestCaseContext<'a> {
     }
 
     fn add_hit(&mut self, result: Result<(), i32>, duration: Duration) {
-        if let Err(code) = result {
-            self.test_metrics.unsuccessful_hits += 1;
-            *self.test_metrics.errors.entry(code).or_insert(0) += 1;
-        } else {
-            self.test_metrics.successful_hits += 1;
+        self.test_metrics.add_hit(result, duration);
+    }
+
+    fn add_action_hit(
+        &mut self,
+        action_name: &'static str,
+        result: Result<(), i32>,
+        duration: Duration,
+    ) {
+        let step_name = self.test_step_name.unwrap_or("");
+        self.action_metrics
+            .entry((step_name, action_name))
+            .or_default()
+            .add_hit(result, duration);
+        self.test_metrics.add_hit(result, duration);
+    }
+
+    fn add_transaction_hit(
+        &mut self,
+        transaction_name: &'static str,
+        result: Result<(), i32>,
+        duration: Duration,
+    ) {
+        let step_name = self.test_step_name.unwrap_or("");
+        self.transaction_metrics
+            .entry((step_name, transaction_name))
+            .or_default()
+            .add_hit(result, duration);
+    }
+
+    fn add_schedule_timing(&mut self, response_time: Duration, schedule_lag: Duration) {
+        self.test_metrics
+            .response_time
+            .add(response_time.as_millis());
+        self.test_metrics.schedule_lag.add(schedule_lag.as_millis());
+    }
+
+    fn add_spawn_lag(&mut self, spawn_lag: Duration) {
+        self.test_metrics.spawn_lag.add(spawn_lag.as_millis());
+        if spawn_lag > GeneratorHealth::DELAYED_HIT_THRESHOLD {
+            self.test_metrics.delayed_hits += 1;
         }
+    }
+
+    fn add_dropped_hits(&mut self, dropped_hits: u128) {
+        self.test_metrics.dropped_hits += dropped_hits;
+    }
 
-        self.test_metrics.elapsed_times.insert(duration.as_millis());
+    fn add_dropped_events(&mut self, dropped_events: u128) {
+        self.test_metrics.dropped_events += dropped_events;
     }
 
     fn get_session_id(&self) -> String {
@@ -81,6 +203,10 @@ impl<'a> TestContext for TestCaseContext<'a> {
         self.test_name.to_owned()
     }
 
+    fn get_test_suite(&self) -> String {
+        self.test_suite.to_owned()
+    }
+
     fn set_current_step(&mut self, step_name: &'static str) {
         self.test_step_name = Some(step_name);
         self.test_stage_name = None;
@@ -94,6 +220,22 @@ impl<'a> TestContext for TestCaseContext<'a> {
         self.test_metrics.load_duration = duration;
     }
 
+    fn set_current_in_flight(&mut self, in_flight: u128) {
+        self.test_metrics.in_flight = in_flight;
+    }
+
+    fn set_current_max_in_flight(&mut self, max_in_flight: u128) {
+        self.test_metrics.max_in_flight = self.test_metrics.max_in_flight.max(max_in_flight);
+    }
+
+    fn set_metrics_registry(&mut self, registry: MetricsRegistry) {
+        self.metrics_registry = registry;
+    }
+
+    fn set_process_monitor(&mut self, monitor: ProcessMonitor) {
+        self.process_monitor = monitor;
+    }
+
     fn get_successful_hits(&self) -> u128 {
         self.test_metrics.successful_hits
     }
@@ -183,6 +325,90 @@ impl<'a> TestContext for TestCaseContext<'a> {
     fn get_current_errors(&self) -> HashMap<i32, u128> {
         self.test_metrics.errors.clone()
     }
+
+    fn get_current_latency_histogram(&self) -> LatencyHistogram {
+        self.test_metrics.latency_histogram.clone()
+    }
+
+    fn get_current_in_flight(&self) -> u128 {
+        self.test_metrics.in_flight
+    }
+
+    fn get_current_response_time(&self) -> TimingSummary {
+        self.test_metrics.response_time.summary()
+    }
+
+    fn get_current_schedule_lag(&self) -> TimingSummary {
+        self.test_metrics.schedule_lag.summary()
+    }
+
+    fn get_current_generator_health(&self) -> GeneratorHealth {
+        GeneratorHealth::new(
+            self.test_metrics.spawn_lag.summary(),
+            self.test_metrics.delayed_hits,
+            self.test_metrics.dropped_hits,
+            self.test_metrics.dropped_events,
+            self.test_metrics.max_in_flight,
+            self.process_monitor.get_usage(),
+        )
+    }
+
+    fn get_current_actions(&self) -> Vec<(String, Self)> {
+        let step_name = self.test_step_name.unwrap_or("");
+
+        self.action_metrics
+            .iter()
+            .filter(|((step, _), _)| *step == step_name)
+            .map(|((_, action_name), metrics)| {
+                (action_name.to_string(), self.with_metrics(metrics))
+            })
+            .collect()
+    }
+
+    fn get_current_transactions(&self) -> Vec<(String, Self)> {
+        let step_name = self.test_step_name.unwrap_or("");
+
+        self.transaction_metrics
+            .iter()
+            .filter(|((step, _), _)| *step == step_name)
+            .map(|((_, transaction_name), metrics)| {
+                (transaction_name.to_string(), self.with_metrics(metrics))
+            })
+            .collect()
+    }
+
+    fn get_current_custom_metrics(&self) -> CustomMetrics {
+        self.metrics_registry.snapshot()
+    }
+}
+
+impl<'a> TestCaseContext<'a> {
+    /// Returns a copy of the context that only holds the given metrics.
+    fn with_metrics(&self, metrics: &TestContextMetrics) -> Self {
+        TestCaseContext {
+            test_metrics: TestContextMetrics {
+                load_duration: self.test_metrics.load_duration,
+                ..metrics.to_owned()
+            },
+            action_metrics: BTreeMap::default(),
+            transaction_metrics: BTreeMap::default(),
+            ..self.to_owned()
+        }
+    }
+}
+
+impl TestContextMetrics {
+    fn add_hit(&mut self, result: Result<(), i32>, duration: Duration) {
+        if let Err(code) = result {
+            self.unsuccessful_hits += 1;
+            *self.errors.entry(code).or_insert(0) += 1;
+        } else {
+            self.successful_hits += 1;
+        }
+
+        self.elapsed_times.insert(duration.as_millis());
+        self.latency_histogram.add(duration.as_millis());
+    }
 }
 
 #[cfg(test)]
@@ -345,4 +571,130 @@ mod tests {
 
         assert_eq!(actual, duration);
     }
+
+    #[test]
+    fn given_set_of_results_when_getting_latency_histogram_then_returns_cumulative_buckets() {
+        let mut ctx = TestCaseContext::default();
+        seed_with_hits(&mut ctx);
+
+        let actual = ctx.get_current_latency_histogram();
+        let count_for = |le: u128| {
+            actual
+                .buckets
+                .iter()
+                .find(|bucket| bucket.le == le)
+                .map(|bucket| bucket.count)
+        };
+
+        assert_eq!(actual.count, 6);
+        assert_eq!(actual.sum, 960);
+        assert_eq!(count_for(50), Some(0));
+        assert_eq!(count_for(100), Some(2));
+        assert_eq!(count_for(250), Some(5));
+        assert_eq!(count_for(500), Some(6));
+    }
+
+    #[test]
+    fn given_action_hits_when_getting_current_actions_then_returns_metrics_per_action() {
+        let mut ctx = TestCaseContext::default();
+        ctx.set_current_step(STEP_NAME);
+        ctx.add_action_hit("browse", Ok(()), Duration::from_millis(100));
+        ctx.add_action_hit("browse", Err(500), Duration::from_millis(300));
+        ctx.add_action_hit("buy", Ok(()), Duration::from_millis(50));
+        ctx.set_current_load_duration(Duration::from_secs(1));
+        ctx.set_current_step("OTHER");
+        ctx.add_action_hit("other", Ok(()), Duration::from_millis(10));
+        ctx.set_current_step(STEP_NAME);
+
+        let actual = ctx.get_current_actions();
+
+        assert_eq!(ctx.get_hits(), 4);
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].0, "browse");
+        assert_eq!(actual[0].1.get_hits(), 2);
+        assert_eq!(actual[0].1.get_current_errors().get(&500), Some(&1));
+        assert_eq!(actual[1].0, "buy");
+        assert_eq!(actual[1].1.get_current_max_time(), 50);
+    }
+
+    #[test]
+    fn given_transaction_hits_when_getting_current_transactions_then_returns_metrics_per_step() {
+        let mut ctx = TestCaseContext::default();
+        ctx.set_current_step(STEP_NAME);
+        ctx.add_transaction_hit("login", Ok(()), Duration::from_millis(100));
+        ctx.add_transaction_hit("login", Err(401), Duration::from_millis(300));
+        ctx.set_current_step("OTHER");
+        ctx.add_transaction_hit("login", Ok(()), Duration::from_millis(10));
+        ctx.set_current_step(STEP_NAME);
+
+        let actual = ctx.get_current_transactions();
+
+        assert_eq!(ctx.get_hits(), 0);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].0, "login");
+        assert_eq!(actual[0].1.get_hits(), 2);
+        assert_eq!(actual[0].1.get_current_errors().get(&401), Some(&1));
+    }
+
+    #[test]
+    fn given_schedule_timings_when_getting_response_time_and_lag_then_summarizes_them() {
+        let mut ctx = TestCaseContext::default();
+        (1..=100).for_each(|time| {
+            ctx.add_schedule_timing(
+                Duration::from_millis(time),
+                Duration::from_millis(time / 10),
+            );
+        });
+
+        let response_time = ctx.get_current_response_time();
+        let schedule_lag = ctx.get_current_schedule_lag();
+
+        assert_eq!(response_time.count, 100);
+        assert_eq!(response_time.mean, 50);
+        assert_eq!(response_time.p90, 90);
+        assert_eq!(response_time.p99, 99);
+        assert_eq!(response_time.max, 100);
+        assert_eq!(schedule_lag.mean, 4);
+        assert_eq!(schedule_lag.max, 10);
+    }
+
+    #[test]
+    fn given_timings_beyond_last_bucket_when_summarizing_then_caps_percentiles_by_max() {
+        let mut ctx = TestCaseContext::default();
+        ctx.add_schedule_timing(Duration::from_millis(20), Duration::ZERO);
+        ctx.add_schedule_timing(Duration::from_secs(30), Duration::ZERO);
+
+        let actual = ctx.get_current_response_time();
+
+        assert_eq!(actual.p90, 30_000);
+        assert_eq!(actual.max, 30_000);
+    }
+
+    #[test]
+    fn given_spawn_lags_when_getting_generator_health_then_counts_delayed_hits() {
+        let mut ctx = TestCaseContext::default();
+        [0, 1, 2, 40, 60].into_iter().for_each(|lag| {
+            ctx.add_spawn_lag(Duration::from_millis(lag));
+        });
+
+        let actual = ctx.get_current_generator_health();
+
+        assert_eq!(actual.spawn_lag.count, 5);
+        assert_eq!(actual.spawn_lag.max, 60);
+        assert_eq!(actual.delayed_hits, 2);
+    }
+
+    #[test]
+    fn given_context_with_hits_when_creating_step_context_then_keeps_session_without_hits() {
+        let mut ctx = TestCaseContext::new("test", "suite");
+        ctx.set_current_step(STEP_NAME);
+        seed_with_hits(&mut ctx);
+
+        let actual = ctx.new_step("OTHER");
+
+        assert_eq!(actual.get_session_id(), ctx.get_session_id());
+        assert_eq!(actual.get_current_step_name(), "OTHER");
+        assert_eq!(actual.get_hits(), 0);
+        assert_eq!(ctx.get_hits(), 6);
+    }
 }
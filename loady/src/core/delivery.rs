@@ -0,0 +1,517 @@
+use crate::core::reporting::{LifecycleEvent, ReportingSink, SinkError, SinkResult};
+use crate::core::stats::{SinkReport, StepStatus, SuiteStatus, TestStatus};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+
+pub type SharedSink = Arc<Box<dyn ReportingSink>>;
+
+/// What to do when the queue of a sink is full.
+///
+/// Only the periodic action snapshots can be dropped or coalesced. Lifecycle events and the
+/// step and test summaries always wait for room, so a sink never misses them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discards the oldest queued snapshot to make room for the new one.
+    DropOldest,
+    /// Waits until the sink catches up, slowing down the reporting pipeline.
+    Block,
+    /// Replaces the newest queued snapshot, since snapshots are cumulative.
+    Coalesce,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SinkOptions {
+    pub capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+}
+
+#[derive(Clone, Debug)]
+pub enum SinkEvent {
+    Lifecycle(LifecycleEvent),
+    LoadActionEnded(StepStatus),
+    LoadStepEnded(StepStatus),
+    SuiteEnded(SuiteStatus),
+    TestEnded(TestStatus),
+}
+
+struct QueueState {
+    events: VecDeque<SinkEvent>,
+    closed: bool,
+    dropped: u64,
+    coalesced: u64,
+}
+
+struct SinkQueue {
+    options: SinkOptions,
+    state: Mutex<QueueState>,
+    not_empty: Notify,
+    not_full: Notify,
+}
+
+struct SinkWorker {
+    sink_name: String,
+    queue: Arc<SinkQueue>,
+    handle: JoinHandle<SinkReport>,
+}
+
+/// Drives every sink on its own task, fed by a bounded queue.
+pub struct SinkDispatcher {
+    workers: Mutex<Vec<SinkWorker>>,
+}
+
+impl Default for SinkOptions {
+    fn default() -> Self {
+        Self {
+            capacity: Self::DEFAULT_CAPACITY,
+            overflow_policy: OverflowPolicy::Coalesce,
+        }
+    }
+}
+
+impl SinkOptions {
+    pub const DEFAULT_CAPACITY: usize = 128;
+
+    pub fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        if capacity == 0 {
+            panic!("The capacity of the sink queue must be greater than zero");
+        }
+
+        Self {
+            capacity,
+            overflow_policy,
+        }
+    }
+}
+
+impl SinkEvent {
+    fn is_snapshot(&self) -> bool {
+        matches!(self, SinkEvent::LoadActionEnded(_))
+    }
+
+    async fn deliver(self, sink: &dyn ReportingSink) -> SinkResult {
+        match self {
+            SinkEvent::Lifecycle(LifecycleEvent::TestStarted(plan)) => {
+                sink.on_test_started(plan).await
+            }
+            SinkEvent::Lifecycle(LifecycleEvent::StepStarted(status)) => {
+                sink.on_step_started(status).await
+            }
+            SinkEvent::Lifecycle(LifecycleEvent::StageStarted(status)) => {
+                sink.on_stage_started(status).await
+            }
+            SinkEvent::Lifecycle(LifecycleEvent::StageEnded(status)) => {
+                sink.on_stage_ended(status).await
+            }
+            SinkEvent::Lifecycle(LifecycleEvent::InternalStepEnded(status)) => {
+                sink.on_internal_step_ended(status).await
+            }
+            SinkEvent::Lifecycle(LifecycleEvent::GeneratorWarning(status)) => {
+                sink.on_generator_warning(status).await
+            }
+            SinkEvent::LoadActionEnded(status) => sink.on_load_action_ended(status).await,
+            SinkEvent::LoadStepEnded(status) => sink.on_load_step_ended(status).await,
+            SinkEvent::TestEnded(status) => sink.on_test_ended(status).await,
+            SinkEvent::SuiteEnded(status) => sink.on_suite_ended(status).await,
+        }
+    }
+}
+
+impl SinkQueue {
+    fn new(options: SinkOptions) -> Self {
+        Self {
+            options,
+            state: Mutex::new(QueueState {
+                events: VecDeque::with_capacity(options.capacity),
+                closed: false,
+                dropped: 0,
+                coalesced: 0,
+            }),
+            not_empty: Notify::new(),
+            not_full: Notify::new(),
+        }
+    }
+
+    /// Pushes the event unless the queue is closed, as its worker may be gone already.
+    async fn push(&self, event: SinkEvent) {
+        loop {
+            {
+                let mut state = self.state.lock().await;
+                if state.closed {
+                    // Wakes up the next producer waiting for room, so it sees the queue closed.
+                    self.not_full.notify_one();
+                    return;
+                }
+
+                if state.events.len() < self.options.capacity {
+                    state.events.push_back(event);
+                    self.not_empty.notify_one();
+                    return;
+                }
+
+                if event.is_snapshot() {
+                    match self.options.overflow_policy {
+                        OverflowPolicy::DropOldest => {
+                            if let Some(index) =
+                                state.events.iter().position(SinkEvent::is_snapshot)
+                            {
+                                state.events.remove(index);
+                                state.events.push_back(event);
+                                state.dropped += 1;
+                                self.not_empty.notify_one();
+                                return;
+                            }
+                        }
+                        OverflowPolicy::Coalesce => {
+                            if let Some(index) =
+                                state.events.iter().rposition(SinkEvent::is_snapshot)
+                            {
+                                state.events[index] = event;
+                                state.coalesced += 1;
+                                return;
+                            }
+                        }
+                        OverflowPolicy::Block => {}
+                    }
+                }
+            }
+
+            self.not_full.notified().await;
+        }
+    }
+
+    async fn pop(&self) -> Option<SinkEvent> {
+        loop {
+            {
+                let mut state = self.state.lock().await;
+                if let Some(event) = state.events.pop_front() {
+                    self.not_full.notify_one();
+                    return Some(event);
+                }
+
+                if state.closed {
+                    return None;
+                }
+            }
+
+            self.not_empty.notified().await;
+        }
+    }
+
+    async fn close(&self) {
+        self.state.lock().await.closed = true;
+        self.not_empty.notify_one();
+        self.not_full.notify_one();
+    }
+}
+
+impl SinkDispatcher {
+    const MAX_REPORTED_ERRORS: usize = 10;
+
+    pub fn start(sinks: Vec<(SharedSink, SinkOptions)>) -> Self {
+        let workers = sinks
+            .into_iter()
+            .map(|(sink, options)| {
+                let sink_name = sink.name();
+                let queue = Arc::new(SinkQueue::new(options));
+                let handle = tokio::spawn(Self::work(sink, Arc::clone(&queue)));
+                SinkWorker {
+                    sink_name,
+                    queue,
+                    handle,
+                }
+            })
+            .collect();
+
+        Self {
+            workers: Mutex::new(workers),
+        }
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.workers.lock().await.is_empty()
+    }
+
+    /// Enqueues the event for every sink, applying the overflow policy of each queue. The
+    /// workers aren't locked while pushing, as a full queue may block until its sink catches up.
+    pub async fn publish(&self, event: SinkEvent) {
+        let queues = self
+            .workers
+            .lock()
+            .await
+            .iter()
+            .map(|worker| Arc::clone(&worker.queue))
+            .collect::<Vec<Arc<SinkQueue>>>();
+
+        for queue in queues {
+            queue.push(event.to_owned()).await;
+        }
+    }
+
+    /// Waits for every sink to drain its queue and returns how the delivery went.
+    pub async fn shutdown(&self) -> Vec<SinkReport> {
+        let workers = std::mem::take(&mut *self.workers.lock().await);
+        let mut reports = Vec::with_capacity(workers.len());
+
+        for worker in workers {
+            worker.queue.close().await;
+            let mut report = worker.handle.await.unwrap_or_else(|error| SinkReport {
+                sink_name: worker.sink_name,
+                errors: vec![error.to_string()],
+                failed: 1,
+                ..Default::default()
+            });
+
+            let state = worker.queue.state.lock().await;
+            report.dropped = state.dropped;
+            report.coalesced = state.coalesced;
+            reports.push(report);
+        }
+
+        reports
+    }
+
+    async fn work(sink: SharedSink, queue: Arc<SinkQueue>) -> SinkReport {
+        let mut report = SinkReport {
+            sink_name: sink.name(),
+            ..Default::default()
+        };
+
+        while let Some(event) = queue.pop().await {
+            let sink = Arc::clone(&sink);
+            let delivery = tokio::spawn(async move { event.deliver(sink.as_ref().as_ref()).await });
+            let result = match delivery.await {
+                Ok(result) => result,
+                Err(error) => Err(SinkError::new(error.to_string())),
+            };
+
+            match result {
+                Ok(()) => report.delivered += 1,
+                Err(error) => {
+                    report.failed += 1;
+                    if report.errors.len() < Self::MAX_REPORTED_ERRORS {
+                        report.errors.push(error.to_string());
+                    }
+                }
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::context::TestCaseContext;
+    use crate::core::stats::InternalStepStatus;
+    use async_trait::async_trait;
+    use std::time::Duration;
+    use tokio::sync::Semaphore;
+
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct FailingSink;
+
+    /// Waits for a permit before delivering every snapshot.
+    #[derive(Clone)]
+    struct StalledSink {
+        permits: Arc<Semaphore>,
+    }
+
+    #[async_trait]
+    impl ReportingSink for RecordingSink {
+        async fn on_test_ended(&self, _: TestStatus) -> SinkResult {
+            self.events.lock().await.push(String::from("test"));
+            Ok(())
+        }
+
+        async fn on_load_step_ended(&self, status: StepStatus) -> SinkResult {
+            self.events.lock().await.push(status.stage_name);
+            Ok(())
+        }
+
+        async fn on_load_action_ended(&self, status: StepStatus) -> SinkResult {
+            self.events.lock().await.push(status.stage_name);
+            Ok(())
+        }
+
+        async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ReportingSink for FailingSink {
+        async fn on_test_ended(&self, _: TestStatus) -> SinkResult {
+            panic!("The sink has crashed")
+        }
+
+        async fn on_load_step_ended(&self, _: StepStatus) -> SinkResult {
+            Ok(())
+        }
+
+        async fn on_load_action_ended(&self, _: StepStatus) -> SinkResult {
+            Err(SinkError::new("The backend is unavailable"))
+        }
+
+        async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ReportingSink for StalledSink {
+        async fn on_test_ended(&self, _: TestStatus) -> SinkResult {
+            Ok(())
+        }
+
+        async fn on_load_step_ended(&self, _: StepStatus) -> SinkResult {
+            Ok(())
+        }
+
+        async fn on_load_action_ended(&self, _: StepStatus) -> SinkResult {
+            self.permits.acquire().await.unwrap().forget();
+            Ok(())
+        }
+
+        async fn on_internal_step_ended(&self, _: InternalStepStatus) -> SinkResult {
+            Ok(())
+        }
+    }
+
+    fn snapshot(stage_name: &str) -> SinkEvent {
+        let mut status = StepStatus::new(String::from("test"), TestCaseContext::default());
+        status.stage_name = stage_name.to_owned();
+        SinkEvent::LoadActionEnded(status)
+    }
+
+    fn test_ended() -> SinkEvent {
+        SinkEvent::TestEnded(TestStatus::new(
+            String::from("test"),
+            TestCaseContext::default(),
+        ))
+    }
+
+    async fn publish_snapshots(
+        policy: OverflowPolicy,
+        capacity: usize,
+    ) -> (Vec<String>, SinkReport) {
+        let sink = RecordingSink::default();
+        let events = Arc::clone(&sink.events);
+        let dispatcher = SinkDispatcher::start(vec![(
+            Arc::new(Box::new(sink) as Box<dyn ReportingSink>),
+            SinkOptions::new(capacity, policy),
+        )]);
+
+        for stage_name in ["1", "2", "3", "4", "5"] {
+            dispatcher.publish(snapshot(stage_name)).await;
+        }
+        dispatcher.publish(test_ended()).await;
+
+        let report = dispatcher.shutdown().await.remove(0);
+        let events = events.lock().await.to_owned();
+        (events, report)
+    }
+
+    #[tokio::test]
+    async fn given_coalesce_policy_when_queue_is_full_then_replaces_newest_snapshot() {
+        let (events, report) = publish_snapshots(OverflowPolicy::Coalesce, 2).await;
+
+        assert_eq!(events, vec!["1", "5", "test"]);
+        assert_eq!(report.delivered, 3);
+        assert_eq!(report.coalesced, 3);
+        assert_eq!(report.dropped, 0);
+    }
+
+    #[tokio::test]
+    async fn given_drop_oldest_policy_when_queue_is_full_then_discards_oldest_snapshots() {
+        let (events, report) = publish_snapshots(OverflowPolicy::DropOldest, 2).await;
+
+        assert_eq!(events, vec!["4", "5", "test"]);
+        assert_eq!(report.delivered, 3);
+        assert_eq!(report.dropped, 3);
+        assert_eq!(report.coalesced, 0);
+    }
+
+    #[tokio::test]
+    async fn given_block_policy_when_queue_is_full_then_delivers_every_event() {
+        let (events, report) = publish_snapshots(OverflowPolicy::Block, 1).await;
+
+        assert_eq!(events, vec!["1", "2", "3", "4", "5", "test"]);
+        assert_eq!(report.delivered, 6);
+        assert_eq!(report.dropped, 0);
+        assert_eq!(report.coalesced, 0);
+    }
+
+    #[tokio::test]
+    async fn given_failing_sink_when_delivering_then_reports_failures() {
+        let dispatcher = SinkDispatcher::start(vec![(
+            Arc::new(Box::new(FailingSink) as Box<dyn ReportingSink>),
+            SinkOptions::default(),
+        )]);
+
+        dispatcher.publish(snapshot("1")).await;
+        dispatcher.publish(test_ended()).await;
+        let report = dispatcher.shutdown().await.remove(0);
+
+        assert_eq!(report.sink_name, "FailingSink");
+        assert_eq!(report.delivered, 0);
+        assert_eq!(report.failed, 2);
+        assert_eq!(report.errors[0], "The backend is unavailable");
+    }
+
+    #[tokio::test]
+    async fn given_blocked_publish_when_sink_is_stalled_then_does_not_lock_the_dispatcher() {
+        let sink = StalledSink {
+            permits: Arc::new(Semaphore::new(0)),
+        };
+        let permits = Arc::clone(&sink.permits);
+        let dispatcher = Arc::new(SinkDispatcher::start(vec![(
+            Arc::new(Box::new(sink) as Box<dyn ReportingSink>),
+            SinkOptions::new(1, OverflowPolicy::Block),
+        )]));
+
+        dispatcher.publish(snapshot("1")).await;
+        dispatcher.publish(snapshot("2")).await;
+        let blocked = tokio::spawn({
+            let dispatcher = Arc::clone(&dispatcher);
+            async move { dispatcher.publish(snapshot("3")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let is_empty =
+            tokio::time::timeout(Duration::from_millis(200), dispatcher.is_empty()).await;
+        assert_eq!(is_empty, Ok(false));
+        assert!(!blocked.is_finished());
+
+        permits.add_permits(3);
+        blocked.await.unwrap();
+        let report = dispatcher.shutdown().await.remove(0);
+        assert_eq!(report.delivered, 3);
+    }
+
+    #[tokio::test]
+    async fn given_closed_queue_when_pushing_then_discards_event() {
+        let queue = SinkQueue::new(SinkOptions::new(1, OverflowPolicy::Block));
+        queue.push(snapshot("1")).await;
+        queue.close().await;
+
+        let pushed =
+            tokio::time::timeout(Duration::from_millis(200), queue.push(snapshot("2"))).await;
+
+        assert!(pushed.is_ok());
+        assert_eq!(queue.state.lock().await.events.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn given_zero_capacity_when_creating_options_then_panics() {
+        _ = SinkOptions::new(0, OverflowPolicy::Block);
+    }
+}
@@ -1,5 +1,10 @@
-use crate::core::stats::{Metrics, StepStatus, TestStatus};
-use num_format::{Locale, ToFormattedString};
+use crate::core::stats::{
+    ActionStatus, CaseFailure, CustomMetrics, GeneratorHealth, Metrics, StepStatus, SuiteStatus,
+    TestStatus, TimingSummary, TransactionStatus,
+};
+use chrono::{DateTime, Utc};
+pub use num_format::Locale;
+use num_format::ToFormattedString;
 use serde::Serialize;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
@@ -12,6 +17,15 @@ pub enum FileType {
     Json,
 }
 
+/// Unit the durations are formatted with. Durations are measured with millisecond resolution,
+/// so durations in microseconds are always whole thousands.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DurationUnit {
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+
 enum FileContent<'a> {
     Txt(TestReport<'a>),
     Csv(TestReport<'a>),
@@ -20,16 +34,65 @@ enum FileContent<'a> {
 
 #[derive(Default)]
 pub struct Exporter {
+    pub localization: Localization,
+    pub git_sha_variable: Option<String>,
     export_files: Vec<ExportFile>,
 }
 
-#[derive(Default)]
-pub struct Localization;
+#[derive(Clone, Debug)]
+pub struct Localization {
+    locale: Locale,
+    decimal_separator: Option<String>,
+    duration_unit: DurationUnit,
+    labels: Labels,
+}
+
+#[derive(Clone, Debug)]
+pub struct Labels {
+    pub session_id: String,
+    pub test_case: String,
+    pub test_suite: String,
+    pub test_step: String,
+    pub test_action: String,
+    pub test_transaction: String,
+    pub custom_metrics: String,
+    pub test_duration: String,
+    pub load_duration: String,
+    pub min_time: String,
+    pub mean_time: String,
+    pub max_time: String,
+    pub std_dev: String,
+    pub p90_time: String,
+    pub p95_time: String,
+    pub p99_time: String,
+    pub all_hits: String,
+    pub positive_hits: String,
+    pub negative_hits: String,
+    pub request_per_sec: String,
+    pub response_time: String,
+    pub schedule_lag: String,
+    pub generator_health: String,
+    pub spawn_lag: String,
+    pub delayed_hits: String,
+    pub dropped_hits: String,
+    pub dropped_events: String,
+    pub max_in_flight: String,
+    pub cpu_usage: String,
+    pub memory_usage: String,
+    pub warnings: String,
+    pub errors: String,
+    pub result: String,
+    pub passed: String,
+    pub failed: String,
+    pub failures: String,
+}
 
 #[derive(Serialize)]
 struct TestReport<'a> {
     test_status: &'a TestStatus,
     step_status: &'a [StepStatus],
+    #[serde(skip)]
+    locale: &'a Localization,
 }
 
 struct ExportFile {
@@ -38,27 +101,152 @@ struct ExportFile {
     file_name: String,
 }
 
+struct PathTemplate {
+    values: Vec<(&'static str, String)>,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new(Locale::en)
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            session_id: String::from("Session ID"),
+            test_case: String::from("Test Case"),
+            test_suite: String::from("Test Suite"),
+            test_step: String::from("Test Step"),
+            test_action: String::from("Test Action"),
+            test_transaction: String::from("Test Transaction"),
+            custom_metrics: String::from("Custom Metrics"),
+            test_duration: String::from("Test Duration"),
+            load_duration: String::from("Load Duration"),
+            min_time: String::from("Min Time"),
+            mean_time: String::from("Mean Time"),
+            max_time: String::from("Max Time"),
+            std_dev: String::from("Std Dev"),
+            p90_time: String::from("p90"),
+            p95_time: String::from("p95"),
+            p99_time: String::from("p99"),
+            all_hits: String::from("All Hits"),
+            positive_hits: String::from("Successful hits"),
+            negative_hits: String::from("Unsuccessful hits"),
+            request_per_sec: String::from("Requests/sec"),
+            response_time: String::from("Response Time"),
+            schedule_lag: String::from("Schedule Lag"),
+            generator_health: String::from("Generator Health"),
+            spawn_lag: String::from("Spawn Lag"),
+            delayed_hits: String::from("Delayed hits"),
+            dropped_hits: String::from("Dropped hits"),
+            dropped_events: String::from("Dropped events"),
+            max_in_flight: String::from("Max in flight"),
+            cpu_usage: String::from("CPU usage"),
+            memory_usage: String::from("Memory (RSS)"),
+            warnings: String::from("Warnings"),
+            errors: String::from("Errors count"),
+            result: String::from("Result"),
+            passed: String::from("Passed"),
+            failed: String::from("Failed"),
+            failures: String::from("Failures"),
+        }
+    }
+}
+
+impl DurationUnit {
+    pub fn get_symbol(&self) -> &'static str {
+        match self {
+            Self::Microseconds => "µs",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+        }
+    }
+}
+
 impl Localization {
-    fn format_number(&self, num: &u128) -> String {
-        num.to_formatted_string(&Locale::en)
+    pub fn new(locale: Locale) -> Self {
+        Self {
+            locale,
+            decimal_separator: None,
+            duration_unit: DurationUnit::Milliseconds,
+            labels: Labels::default(),
+        }
+    }
+
+    pub fn with_decimal_separator(mut self, separator: &str) -> Self {
+        self.decimal_separator = Some(separator.to_owned());
+        self
+    }
+
+    pub fn with_duration_unit(mut self, duration_unit: DurationUnit) -> Self {
+        self.duration_unit = duration_unit;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn labels(&self) -> &Labels {
+        &self.labels
     }
 
-    fn format_float(&self, num: &f64) -> String {
-        format!("{:.2}", num)
+    pub fn duration_symbol(&self) -> &'static str {
+        self.duration_unit.get_symbol()
     }
 
-    fn format_duration(&self, duration: &u128) -> String {
-        self.format_number(duration)
+    pub fn format_number(&self, num: &u128) -> String {
+        num.to_formatted_string(&self.locale)
+    }
+
+    pub fn format_float(&self, num: &f64) -> String {
+        let content = format!("{:.2}", num.abs());
+        let (integer, fraction) = content.split_once('.').unwrap_or((&content, "00"));
+        let integer = integer
+            .parse::<u128>()
+            .map(|value| self.format_number(&value))
+            .unwrap_or(integer.to_owned());
+        let sign = if num.is_sign_negative() && *num != 0f64 {
+            self.locale.minus_sign()
+        } else {
+            ""
+        };
+
+        format!(
+            "{}{}{}{}",
+            sign,
+            integer,
+            self.get_decimal_separator(),
+            fraction
+        )
+    }
+
+    pub fn format_duration(&self, duration: &u128) -> String {
+        match self.duration_unit {
+            DurationUnit::Microseconds => self.format_number(&duration.saturating_mul(1000)),
+            DurationUnit::Milliseconds => self.format_number(duration),
+            DurationUnit::Seconds => self.format_float(&(*duration as f64 / 1000f64)),
+        }
+    }
+
+    fn get_decimal_separator(&self) -> &str {
+        match &self.decimal_separator {
+            Some(separator) => separator,
+            _ => self.locale.decimal(),
+        }
     }
 }
 
 impl TestStatus {
     pub fn as_txt(&self, locale: &Localization) -> String {
+        let labels = locale.labels();
         format!(
             "{: <20}: {}\r\n{: <20}: {}\r\n\r\n{}",
-            "Session ID",
+            labels.session_id,
             self.session_id,
-            "Test Case",
+            labels.test_case,
             self.test_name,
             self.metrics.as_txt(locale)
         )
@@ -74,60 +262,261 @@ impl TestStatus {
     }
 }
 
+impl SuiteStatus {
+    pub fn as_txt(&self, locale: &Localization) -> String {
+        const CASE_SEPARATOR: &str = "\r\n\r\n----------------------------------------------------------------------\r\n\r\n";
+
+        let labels = locale.labels();
+        let mut content = format!(
+            "{: <20}: {}\r\n{: <20}: {}\r\n{: <20}: {}\r\n\r\n{: <20}: {} {}\r\n{: <20}: {}\r\n{: <20}: {}\r\n{: <20}: {}",
+            labels.session_id,
+            self.session_id,
+            labels.test_suite,
+            self.suite_name,
+            labels.result,
+            self.result_label(locale),
+            labels.test_duration,
+            locale.format_duration(&self.duration),
+            locale.duration_symbol(),
+            labels.all_hits,
+            locale.format_number(&self.all_hits),
+            labels.positive_hits,
+            locale.format_number(&self.positive_hits),
+            labels.negative_hits,
+            locale.format_number(&self.negative_hits),
+        );
+
+        content = self.test_status.iter().fold(content, |cur, nxt| {
+            cur + CASE_SEPARATOR + nxt.as_txt(locale).as_str()
+        });
+
+        if !self.failures.is_empty() {
+            content += CASE_SEPARATOR;
+            content += &format!("{}:", labels.failures);
+            content = self
+                .failures
+                .iter()
+                .fold(content, |cur, nxt| cur + "\r\n" + nxt.as_txt().as_str());
+        }
+
+        content
+    }
+
+    fn as_csv(&self, locale: &Localization) -> String {
+        const NEW_LINE: &str = "\r\n";
+        let labels = locale.labels();
+
+        let content = self.test_status.iter().fold(String::from(""), |cur, nxt| {
+            cur + format!(
+                "{};{};{}",
+                self.suite_name,
+                labels.passed,
+                nxt.as_csv(locale)
+            )
+            .as_str()
+                + NEW_LINE
+        });
+
+        self.failures.iter().fold(content, |cur, nxt| {
+            cur + format!(
+                "{};{};;{};{}",
+                self.suite_name, labels.failed, nxt.test_name, nxt.reason
+            )
+            .as_str()
+                + NEW_LINE
+        })
+    }
+
+    fn result_label<'a>(&self, locale: &'a Localization) -> &'a str {
+        match self.passed {
+            true => &locale.labels().passed,
+            false => &locale.labels().failed,
+        }
+    }
+}
+
+impl CaseFailure {
+    fn as_txt(&self) -> String {
+        format!("{: <20}: {}", self.test_name, self.reason)
+    }
+}
+
 impl StepStatus {
     pub fn as_txt(&self, locale: &Localization) -> String {
-        format!(
+        let mut content = format!(
             "{: <20}: {}\r\n\r\n{}",
-            "Test Step",
+            locale.labels().test_step,
             self.step_name,
             self.metrics.as_txt(locale)
+        );
+
+        if !self.custom_metrics.is_empty() {
+            content += &self.custom_metrics.as_txt(locale);
+        }
+
+        self.actions.iter().fold(content, |cur, nxt| {
+            cur + "\r\n\r\n" + nxt.as_txt(locale).as_str()
+        })
+    }
+
+    fn as_csv(&self, locale: &Localization) -> String {
+        format!(
+            "{};{}{}",
+            self.step_name,
+            self.metrics.as_csv(locale),
+            self.custom_metrics.as_csv(locale)
+        )
+    }
+
+    /// Returns the row of the step followed by a row per action and per sub-transaction, whose
+    /// step column is the step name qualified by the action or transaction name.
+    fn as_csv_rows(&self, locale: &Localization) -> Vec<String> {
+        let actions = self.actions.iter().map(|action| {
+            format!(
+                "{}.{};{}",
+                self.step_name,
+                action.action_name,
+                action.metrics.as_csv(locale)
+            )
+        });
+        let transactions = self.metrics.transactions.iter().map(|transaction| {
+            format!(
+                "{}.{};{}",
+                self.step_name,
+                transaction.transaction_name,
+                transaction.metrics.as_csv(locale)
+            )
+        });
+
+        std::iter::once(self.as_csv(locale))
+            .chain(actions)
+            .chain(transactions)
+            .collect()
+    }
+}
+
+impl CustomMetrics {
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty() && self.gauges.is_empty() && self.histograms.is_empty()
+    }
+
+    fn as_txt(&self, locale: &Localization) -> String {
+        self.entries(locale).iter().fold(
+            format!("\r\n\r\n{: <20}:\r\n\r\n", locale.labels().custom_metrics),
+            |curr, (key, val)| curr + &format!("{: <20}: {}\r\n", key, val),
         )
     }
 
     fn as_csv(&self, locale: &Localization) -> String {
-        format!("{};{}", self.step_name, self.metrics.as_csv(locale))
+        self.entries(locale)
+            .iter()
+            .fold(String::new(), |curr, (key, val)| {
+                curr + &format!(";{}={}", key, val)
+            })
+    }
+
+    /// Flattens the custom metrics into named values, where every histogram is summarized by
+    /// its count, min, mean, p95 and max.
+    fn entries(&self, locale: &Localization) -> Vec<(String, String)> {
+        let counters = self
+            .counters
+            .iter()
+            .map(|(name, value)| (name.to_owned(), locale.format_number(&(*value as u128))));
+        let gauges = self
+            .gauges
+            .iter()
+            .map(|(name, value)| (name.to_owned(), locale.format_float(value)));
+        let histograms = self.histograms.iter().flat_map(|(name, summary)| {
+            [
+                ("count", locale.format_number(&(summary.count as u128))),
+                ("min", locale.format_float(&summary.min)),
+                ("mean", locale.format_float(&summary.mean)),
+                ("p95", locale.format_float(&summary.p95)),
+                ("max", locale.format_float(&summary.max)),
+            ]
+            .map(|(suffix, value)| (format!("{}.{}", name, suffix), value))
+        });
+
+        counters.chain(gauges).chain(histograms).collect()
+    }
+}
+
+impl ActionStatus {
+    fn as_txt(&self, locale: &Localization) -> String {
+        format!(
+            "{: <20}: {}\r\n\r\n{}",
+            locale.labels().test_action,
+            self.action_name,
+            self.metrics.as_txt(locale)
+        )
+    }
+}
+
+impl TransactionStatus {
+    fn as_txt(&self, locale: &Localization) -> String {
+        format!(
+            "{: <20}: {}\r\n\r\n{}",
+            locale.labels().test_transaction,
+            self.transaction_name,
+            self.metrics.as_txt(locale)
+        )
     }
 }
 
 impl Metrics {
     fn as_txt(&self, locale: &Localization) -> String {
-        let mut content = format!("{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n{: <20}: {:} ms\r\n\r\n{: <20}: {:}\r\n{: <20}: {:}\r\n{: <20}: {:}\r\n{: <20}: {:}", 
-            "Test Duration",
+        let labels = locale.labels();
+        let unit = locale.duration_symbol();
+        let mut content = format!("{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n{: <20}: {:} {unit}\r\n\r\n{: <20}: {:}\r\n{: <20}: {:}\r\n{: <20}: {:}\r\n{: <20}: {:}", 
+            labels.test_duration,
             locale.format_duration(&self.test_duration),
-            "Load Duration",
+            labels.load_duration,
             locale.format_duration(&self.load_duration),
-            "Min Time",
+            labels.min_time,
             locale.format_duration(&self.min_time),
-            "Mean Time", 
+            labels.mean_time,
             locale.format_duration(&self.mean_time),
-            "Max Time",
+            labels.max_time,
             locale.format_duration(&self.max_time),
-            "Std Dev",
+            labels.std_dev,
             locale.format_duration(&self.std_dev),
-            "p90",
+            labels.p90_time,
             locale.format_duration(&self.p90_time),
-            "p95",
+            labels.p95_time,
             locale.format_duration(&self.p95_time),
-            "p99",
+            labels.p99_time,
             locale.format_duration(&self.p99_time),
-            "All Hits",
+            labels.all_hits,
             locale.format_number(&self.all_hits),
-            "Successful hits",
+            labels.positive_hits,
             locale.format_number(&self.positive_hits),
-            "Unsuccessul hits",
+            labels.negative_hits,
             locale.format_number(&self.negative_hits),
-            "Requests/sec",
+            labels.request_per_sec,
             locale.format_float(&self.request_per_sec),
         );
 
+        if self.response_time.count > 0 {
+            content += &self.response_time.as_txt(&labels.response_time, locale);
+            content += &self.schedule_lag.as_txt(&labels.schedule_lag, locale);
+        }
+
+        if self.generator.spawn_lag.count > 0 {
+            content += &self.generator.as_txt(locale);
+        }
+
         if !self.errors.is_empty() {
             content += &self.errors.iter().fold(
-                format!("\r\n\r\n{: <20}:\r\n\r\n", "Errors count"),
-                |curr, (key, val)| curr + &format!("{: <20}: {:}\r\n", key, val),
+                format!("\r\n\r\n{: <20}:\r\n\r\n", labels.errors),
+                |curr, (key, val)| {
+                    curr + &format!("{: <20}: {:}\r\n", key, locale.format_number(val))
+                },
             );
         }
 
-        content
+        self.transactions.iter().fold(content, |cur, nxt| {
+            cur + "\r\n\r\n" + nxt.as_txt(locale).as_str()
+        })
     }
 
     fn as_csv(&self, locale: &Localization) -> String {
@@ -147,10 +536,13 @@ impl Metrics {
             locale.format_number(&self.negative_hits),
             locale.format_float(&self.request_per_sec),
         );
+        content += &self.response_time.as_csv(locale);
+        content += &self.schedule_lag.as_csv(locale);
+        content += &self.generator.as_csv(locale);
 
         if !self.errors.is_empty() {
             content = self.errors.iter().fold(content, |curr, (key, val)| {
-                curr + &format!(";{};{}", key, val)
+                curr + &format!(";{};{}", key, locale.format_number(val))
             });
         }
 
@@ -158,32 +550,131 @@ impl Metrics {
     }
 }
 
+impl TimingSummary {
+    fn as_txt(&self, label: &str, locale: &Localization) -> String {
+        let labels = locale.labels();
+        let unit = locale.duration_symbol();
+        [
+            (&labels.mean_time, self.mean),
+            (&labels.p90_time, self.p90),
+            (&labels.p95_time, self.p95),
+            (&labels.p99_time, self.p99),
+            (&labels.max_time, self.max),
+        ]
+        .iter()
+        .fold(
+            format!("\r\n\r\n{: <20}:\r\n", label),
+            |curr, (key, val)| {
+                curr + &format!("\r\n{: <20}: {} {unit}", key, locale.format_duration(val))
+            },
+        )
+    }
+
+    fn as_csv(&self, locale: &Localization) -> String {
+        [self.mean, self.p90, self.p95, self.p99, self.max]
+            .iter()
+            .fold(String::new(), |curr, val| {
+                curr + ";" + &locale.format_duration(val)
+            })
+    }
+}
+
+impl GeneratorHealth {
+    const BYTES_PER_MEGABYTE: f64 = 1_048_576f64;
+
+    fn as_txt(&self, locale: &Localization) -> String {
+        let labels = locale.labels();
+        let mut content = format!(
+            "\r\n\r\n{: <20}:\r\n\r\n{: <20}: {}\r\n{: <20}: {}\r\n{: <20}: {}\r\n{: <20}: {}",
+            labels.generator_health,
+            labels.delayed_hits,
+            locale.format_number(&self.delayed_hits),
+            labels.dropped_hits,
+            locale.format_number(&self.dropped_hits),
+            labels.dropped_events,
+            locale.format_number(&self.dropped_events),
+            labels.max_in_flight,
+            locale.format_number(&self.max_in_flight),
+        );
+
+        if let Some(cpu_usage) = &self.cpu_usage {
+            content += &format!(
+                "\r\n{: <20}: {} %",
+                labels.cpu_usage,
+                locale.format_float(cpu_usage)
+            );
+        }
+
+        if let Some(rss_bytes) = self.rss_bytes {
+            let megabytes = rss_bytes as f64 / Self::BYTES_PER_MEGABYTE;
+            content += &format!(
+                "\r\n{: <20}: {} MB",
+                labels.memory_usage,
+                locale.format_float(&megabytes)
+            );
+        }
+
+        content += &self.spawn_lag.as_txt(&labels.spawn_lag, locale);
+
+        if !self.warnings.is_empty() {
+            content += &self.warnings.iter().fold(
+                format!("\r\n\r\n{: <20}:\r\n", labels.warnings),
+                |curr, warning| curr + "\r\n" + warning,
+            );
+        }
+
+        content
+    }
+
+    /// Appends the health fields, leaving the CPU and memory usage empty where they are not
+    /// available so that the columns keep their position.
+    fn as_csv(&self, locale: &Localization) -> String {
+        let cpu_usage = self
+            .cpu_usage
+            .map(|cpu_usage| locale.format_float(&cpu_usage))
+            .unwrap_or_default();
+        let memory_usage = self
+            .rss_bytes
+            .map(|rss_bytes| locale.format_float(&(rss_bytes as f64 / Self::BYTES_PER_MEGABYTE)))
+            .unwrap_or_default();
+
+        format!(
+            ";{};{};{};{};{};{}{};{}",
+            locale.format_number(&self.delayed_hits),
+            locale.format_number(&self.dropped_hits),
+            locale.format_number(&self.dropped_events),
+            locale.format_number(&self.max_in_flight),
+            cpu_usage,
+            memory_usage,
+            self.spawn_lag.as_csv(locale),
+            self.warnings.join(" | ")
+        )
+    }
+}
+
 impl Display for FileContent<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         const STEP_SEPARATOR: &str = "\r\n\r\n----------------------------------------------------------------------\r\n\r\n";
         const NEW_LINE: &str = "\r\n";
 
-        let locale = Localization::default();
-
         let content = match self {
-            FileContent::Txt(report) => report
+            FileContent::Txt(report) => report.step_status.iter().fold(
+                report.test_status.as_txt(report.locale),
+                |cur, nxt| {
+                    cur + format!("{}{}", STEP_SEPARATOR, nxt.as_txt(report.locale)).as_str()
+                },
+            ),
+
+            FileContent::Csv(report) => report
                 .step_status
                 .iter()
-                .fold(report.test_status.as_txt(&locale), |cur, nxt| {
-                    cur + format!("{}{}", STEP_SEPARATOR, nxt.as_txt(&locale)).as_str()
+                .flat_map(|step_status| step_status.as_csv_rows(report.locale))
+                .fold(String::from(""), |cur, nxt| {
+                    cur + report.test_status.as_csv(report.locale).as_str()
+                        + nxt.as_str()
+                        + NEW_LINE
                 }),
 
-            FileContent::Csv(report) => {
-                report
-                    .step_status
-                    .iter()
-                    .fold(String::from(""), |cur, nxt| {
-                        cur + report.test_status.as_csv(&locale).as_str()
-                            + nxt.as_csv(&locale).as_str()
-                            + NEW_LINE
-                    })
-            }
-
             FileContent::Json(report) => serde_json::to_string(report).unwrap(),
         };
 
@@ -192,10 +683,16 @@ impl Display for FileContent<'_> {
 }
 
 impl FileType {
-    pub fn get_content(&self, test_status: TestStatus, step_status: Vec<StepStatus>) -> String {
+    pub fn get_content(
+        &self,
+        test_status: TestStatus,
+        step_status: Vec<StepStatus>,
+        locale: &Localization,
+    ) -> String {
         let report = TestReport {
             test_status: &test_status,
             step_status: step_status.as_slice(),
+            locale,
         };
 
         let content = match self {
@@ -207,6 +704,14 @@ impl FileType {
         format!("{}", content)
     }
 
+    pub fn get_suite_content(&self, suite_status: &SuiteStatus, locale: &Localization) -> String {
+        match self {
+            Self::Txt => suite_status.as_txt(locale),
+            Self::Csv => suite_status.as_csv(locale),
+            Self::Json => serde_json::to_string(suite_status).unwrap(),
+        }
+    }
+
     fn get_extension(&self) -> &'static str {
         match self {
             Self::Csv => "csv",
@@ -225,6 +730,8 @@ impl ExportFile {
         }
     }
 
+    const SUITE_SUFFIX: &str = "-suite";
+
     fn format_file_name(&self) -> String {
         let extension = self.file_type.get_extension();
 
@@ -234,17 +741,96 @@ impl ExportFile {
             format!("{}.{}", self.file_name, extension)
         }
     }
+
+    /// Suffixes the file name, so that the suite report never overwrites the report of a test
+    /// case written from a fixed file name.
+    fn format_suite_file_name(&self) -> String {
+        let extension = format!(".{}", self.file_type.get_extension());
+        let file_name = self
+            .file_name
+            .strip_suffix(&extension)
+            .unwrap_or(&self.file_name);
+
+        format!("{}{}{}", file_name, Self::SUITE_SUFFIX, extension)
+    }
 }
 
-impl Exporter {
+impl PathTemplate {
     const SESSION_ID_PATTERN: &str = "{session-id}";
+    const TEST_NAME_PATTERN: &str = "{test-name}";
+    const TEST_SUITE_PATTERN: &str = "{test-suite}";
+    const TIMESTAMP_PATTERN: &str = "{timestamp}";
+    const DATE_PATTERN: &str = "{date}";
+    const GIT_SHA_PATTERN: &str = "{git-sha}";
+    const UNKNOWN_VALUE: &str = "unknown";
+
+    fn new(
+        session_id: &str,
+        test_name: &str,
+        test_suite: &str,
+        created_at: DateTime<Utc>,
+        git_sha: Option<String>,
+    ) -> Self {
+        let values = vec![
+            (Self::SESSION_ID_PATTERN, session_id.to_owned()),
+            (Self::TEST_NAME_PATTERN, test_name.to_owned()),
+            (Self::TEST_SUITE_PATTERN, test_suite.to_owned()),
+            (
+                Self::TIMESTAMP_PATTERN,
+                created_at.format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+            (
+                Self::DATE_PATTERN,
+                created_at.format("%Y-%m-%d").to_string(),
+            ),
+            (
+                Self::GIT_SHA_PATTERN,
+                git_sha.unwrap_or(Self::UNKNOWN_VALUE.to_string()),
+            ),
+        ];
+
+        Self {
+            values: values
+                .into_iter()
+                .map(|(pattern, value)| (pattern, Self::sanitize(&value)))
+                .collect(),
+        }
+    }
+
+    fn render(&self, template: &str) -> String {
+        self.values
+            .iter()
+            .fold(template.to_owned(), |curr, (pattern, value)| {
+                curr.replace(pattern, value)
+            })
+    }
+
+    fn sanitize(value: &str) -> String {
+        let sanitized = value
+            .trim()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect::<String>();
+
+        match sanitized.trim_matches('.') {
+            "" => Self::UNKNOWN_VALUE.to_string(),
+            _ => sanitized,
+        }
+    }
+}
+
+impl Exporter {
+    const DEFAULT_GIT_SHA_VARIABLE: &str = "GIT_SHA";
 
     pub fn with_default_output_files(&mut self) {
         let mut add_default = |file_type: FileType| {
             self.with_output_file(
                 file_type,
                 String::from("output"),
-                Self::SESSION_ID_PATTERN.to_string(),
+                PathTemplate::SESSION_ID_PATTERN.to_string(),
             );
         };
 
@@ -263,31 +849,62 @@ impl Exporter {
         test_status: TestStatus,
         step_status: Vec<StepStatus>,
     ) -> std::io::Result<()> {
+        let template = PathTemplate::new(
+            &test_status.session_id,
+            &test_status.test_name,
+            &test_status.test_suite,
+            Utc::now(),
+            self.get_git_sha(),
+        );
+
+        for export_file in &self.export_files {
+            let content = export_file.file_type.get_content(
+                test_status.to_owned(),
+                step_status.to_owned(),
+                &self.localization,
+            );
+            let directory = template.render(&export_file.directory);
+            let file_name = template.render(&export_file.format_file_name());
+            Self::write_file(&directory, &file_name, &content)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the suite report into every output file, where the suite name fills both the
+    /// `{test-name}` and `{test-suite}` placeholders and the file name ends with `-suite`.
+    pub fn write_suite_files(&self, suite_status: &SuiteStatus) -> std::io::Result<()> {
+        let template = PathTemplate::new(
+            &suite_status.session_id,
+            &suite_status.suite_name,
+            &suite_status.suite_name,
+            Utc::now(),
+            self.get_git_sha(),
+        );
+
         for export_file in &self.export_files {
             let content = export_file
                 .file_type
-                .get_content(test_status.to_owned(), step_status.to_owned());
-            let file_name = export_file.format_file_name();
-            Self::write_file(
-                &export_file.directory,
-                &file_name,
-                &content,
-                &test_status.session_id,
-            )?;
+                .get_suite_content(suite_status, &self.localization);
+            let directory = template.render(&export_file.directory);
+            let file_name = template.render(&export_file.format_suite_file_name());
+            Self::write_file(&directory, &file_name, &content)?;
         }
 
         Ok(())
     }
 
-    fn write_file(
-        output_directory: &str,
-        output_file: &str,
-        content: &str,
-        session_id: &str,
-    ) -> std::io::Result<()> {
+    fn get_git_sha(&self) -> Option<String> {
+        let git_sha_variable = self
+            .git_sha_variable
+            .as_deref()
+            .unwrap_or(Self::DEFAULT_GIT_SHA_VARIABLE);
+        std::env::var(git_sha_variable).ok()
+    }
+
+    fn write_file(output_directory: &str, output_file: &str, content: &str) -> std::io::Result<()> {
         std::fs::create_dir_all(output_directory)?;
-        let mut file_name = output_file.replace(Self::SESSION_ID_PATTERN, session_id);
-        file_name = format!("{}/{}", output_directory, file_name);
+        let file_name = format!("{}/{}", output_directory, output_file);
         let mut file = File::create(file_name)?;
         file.write_all(content.as_bytes())?;
         Ok(())
@@ -298,6 +915,33 @@ impl Exporter {
 mod tests {
 
     use super::*;
+    use crate::core::stats::ValueSummary;
+    use std::collections::BTreeMap;
+
+    fn empty_metrics() -> Metrics {
+        Metrics {
+            test_duration: 0,
+            load_duration: 0,
+            mean_time: 0,
+            max_time: 0,
+            min_time: 0,
+            std_dev: 0,
+            p90_time: 0,
+            p95_time: 0,
+            p99_time: 0,
+            positive_hits: 0,
+            negative_hits: 0,
+            all_hits: 0,
+            request_per_sec: 0f64,
+            in_flight: 0,
+            errors: Default::default(),
+            latency_histogram: Default::default(),
+            transactions: Vec::new(),
+            response_time: Default::default(),
+            schedule_lag: Default::default(),
+            generator: Default::default(),
+        }
+    }
 
     #[test]
     fn given_filetype_when_getting_extension_then_returns_expected_value() {
@@ -317,8 +961,420 @@ mod tests {
         exporter.with_default_output_files();
 
         assert_eq!(exporter.export_files.len(), 3);
-        assert_file(exporter.export_files.get(0).unwrap(), FileType::Txt);
+        assert_file(exporter.export_files.first().unwrap(), FileType::Txt);
         assert_file(exporter.export_files.get(1).unwrap(), FileType::Csv);
         assert_file(exporter.export_files.get(2).unwrap(), FileType::Json);
     }
+
+    #[test]
+    fn given_templated_path_when_rendering_then_replaces_placeholders() {
+        let test_status = TestStatus {
+            session_id: String::from("abc"),
+            test_name: String::from("checkout flow"),
+            test_suite: String::from("nightly/shop"),
+            metrics: empty_metrics(),
+            sinks: Vec::new(),
+        };
+        let created_at = DateTime::parse_from_rfc3339("2023-10-21T08:30:15Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let template = PathTemplate::new(
+            &test_status.session_id,
+            &test_status.test_name,
+            &test_status.test_suite,
+            created_at,
+            Some(String::from("1a2b3c")),
+        );
+
+        let actual =
+            template.render("output/{test-suite}/{date}/{test-name}-{timestamp}-{git-sha}");
+
+        assert_eq!(
+            actual,
+            "output/nightly_shop/2023-10-21/checkout_flow-20231021T083015Z-1a2b3c"
+        );
+        assert_eq!(template.render("{session-id}.json"), "abc.json");
+    }
+
+    #[test]
+    fn given_fixed_file_name_when_writing_suite_files_then_keeps_case_report() {
+        let directory = std::env::temp_dir()
+            .join(format!("loady-{}", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string();
+        let mut exporter = Exporter::default();
+        exporter.with_output_file(
+            FileType::Csv,
+            directory.to_owned(),
+            String::from("report.csv"),
+        );
+        let test_status = TestStatus {
+            session_id: String::from("case-session"),
+            test_name: String::from("checkout"),
+            test_suite: String::from("nightly"),
+            metrics: empty_metrics(),
+            sinks: Vec::new(),
+        };
+        let suite_status = SuiteStatus::new(
+            String::from("nightly"),
+            1500,
+            vec![test_status.to_owned()],
+            vec![],
+        );
+
+        exporter
+            .write_output_files(test_status, Vec::new())
+            .unwrap();
+        exporter.write_suite_files(&suite_status).unwrap();
+
+        let case_report = std::fs::read_to_string(format!("{}/report.csv", directory));
+        let suite_report = std::fs::read_to_string(format!("{}/report-suite.csv", directory));
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(case_report.unwrap(), "");
+        assert!(suite_report
+            .unwrap()
+            .starts_with("nightly;Passed;case-session;"));
+    }
+
+    #[test]
+    fn given_export_file_when_formatting_suite_file_name_then_appends_suffix() {
+        let with_extension = ExportFile::new(
+            FileType::Json,
+            String::from("output"),
+            String::from("report.json"),
+        );
+        let without_extension = ExportFile::new(
+            FileType::Txt,
+            String::from("output"),
+            String::from("{session-id}"),
+        );
+
+        assert_eq!(with_extension.format_suite_file_name(), "report-suite.json");
+        assert_eq!(
+            without_extension.format_suite_file_name(),
+            "{session-id}-suite.txt"
+        );
+    }
+
+    #[test]
+    fn given_missing_git_sha_when_rendering_then_uses_unknown() {
+        let test_status = TestStatus {
+            session_id: String::from("abc"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            metrics: empty_metrics(),
+            sinks: Vec::new(),
+        };
+        let template = PathTemplate::new(
+            &test_status.session_id,
+            &test_status.test_name,
+            &test_status.test_suite,
+            Utc::now(),
+            None,
+        );
+
+        assert_eq!(template.render("{git-sha}"), "unknown");
+    }
+
+    #[test]
+    fn given_unsafe_values_when_sanitizing_then_returns_safe_file_names() {
+        assert_eq!(PathTemplate::sanitize("../etc"), ".._etc");
+        assert_eq!(PathTemplate::sanitize(".."), "unknown");
+        assert_eq!(PathTemplate::sanitize("a:b*c?d"), "a_b_c_d");
+        assert_eq!(PathTemplate::sanitize(" name "), "name");
+    }
+
+    #[test]
+    fn given_default_localization_when_formatting_numbers_then_uses_english_format() {
+        let locale = Localization::default();
+
+        assert_eq!(locale.format_number(&1234567), "1,234,567");
+        assert_eq!(locale.format_float(&1234.567), "1,234.57");
+        assert_eq!(locale.format_duration(&1500), "1,500");
+        assert_eq!(locale.duration_symbol(), "ms");
+    }
+
+    #[test]
+    fn given_locale_when_formatting_numbers_then_uses_locale_separators() {
+        let locale = Localization::new(Locale::de);
+
+        assert_eq!(locale.format_number(&1234567), "1.234.567");
+        assert_eq!(locale.format_float(&1234.567), "1.234,57");
+    }
+
+    #[test]
+    fn given_decimal_separator_when_formatting_float_then_overrides_locale_separator() {
+        let locale = Localization::new(Locale::en).with_decimal_separator(",");
+
+        assert_eq!(locale.format_float(&12.5), "12,50");
+    }
+
+    #[test]
+    fn given_duration_unit_when_formatting_duration_then_converts_milliseconds() {
+        let micros = Localization::default().with_duration_unit(DurationUnit::Microseconds);
+        let millis = Localization::default().with_duration_unit(DurationUnit::Milliseconds);
+        let seconds = Localization::default().with_duration_unit(DurationUnit::Seconds);
+
+        assert_eq!(micros.format_duration(&15), "15,000");
+        assert_eq!(micros.duration_symbol(), "µs");
+        assert_eq!(millis.format_duration(&15000), "15,000");
+        assert_eq!(millis.duration_symbol(), "ms");
+        assert_eq!(seconds.format_duration(&1500), "1.50");
+        assert_eq!(seconds.duration_symbol(), "s");
+    }
+
+    #[test]
+    fn given_translated_labels_when_formatting_txt_then_uses_labels() {
+        let labels = Labels {
+            session_id: String::from("Sitzungs-ID"),
+            test_case: String::from("Testfall"),
+            ..Labels::default()
+        };
+        let locale = Localization::new(Locale::de)
+            .with_duration_unit(DurationUnit::Seconds)
+            .with_labels(labels);
+        let test_status = TestStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            metrics: Metrics {
+                test_duration: 2500,
+                load_duration: 2000,
+                ..empty_metrics()
+            },
+            sinks: Vec::new(),
+        };
+
+        let actual = test_status.as_txt(&locale);
+
+        assert!(actual.contains("Sitzungs-ID"));
+        assert!(actual.contains("Testfall"));
+        assert!(actual.contains("2,50 s"));
+        assert!(!actual.contains("Session ID"));
+    }
+
+    #[test]
+    fn given_step_with_actions_when_formatting_txt_then_appends_action_metrics() {
+        let step_status = StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("checkout"),
+            stage_name: String::from("load"),
+            metrics: empty_metrics(),
+            actions: vec![ActionStatus {
+                action_name: String::from("browse"),
+                metrics: empty_metrics(),
+            }],
+            custom_metrics: CustomMetrics::default(),
+        };
+
+        let actual = step_status.as_txt(&Localization::default());
+
+        assert!(actual.starts_with("Test Step"));
+        assert!(actual.contains("Test Action         : browse"));
+    }
+
+    #[test]
+    fn given_metrics_with_transactions_when_formatting_txt_then_appends_transaction_metrics() {
+        let metrics = Metrics {
+            transactions: vec![TransactionStatus {
+                transaction_name: String::from("login"),
+                metrics: Metrics {
+                    positive_hits: 7,
+                    ..empty_metrics()
+                },
+            }],
+            ..empty_metrics()
+        };
+
+        let actual = metrics.as_txt(&Localization::default());
+
+        assert!(actual.contains("Test Transaction    : login"));
+        assert!(actual.contains("Successful hits     : 7"));
+    }
+
+    #[test]
+    fn given_step_with_actions_and_transactions_when_formatting_csv_then_appends_their_rows() {
+        let test_status = TestStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            metrics: empty_metrics(),
+            sinks: Vec::new(),
+        };
+        let step_status = StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("checkout"),
+            stage_name: String::from("load"),
+            metrics: Metrics {
+                transactions: vec![TransactionStatus {
+                    transaction_name: String::from("login"),
+                    metrics: Metrics {
+                        positive_hits: 7,
+                        ..empty_metrics()
+                    },
+                }],
+                ..empty_metrics()
+            },
+            actions: vec![ActionStatus {
+                action_name: String::from("browse"),
+                metrics: Metrics {
+                    negative_hits: 3,
+                    ..empty_metrics()
+                },
+            }],
+            custom_metrics: CustomMetrics::default(),
+        };
+
+        let actual =
+            FileType::Csv.get_content(test_status, vec![step_status], &Localization::default());
+        let rows = actual.split_terminator("\r\n").collect::<Vec<&str>>();
+
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("session;test;"));
+        assert!(rows[0].contains(";checkout;"));
+        assert!(rows[1].contains(";checkout.browse;"));
+        assert!(rows[1].contains(";0;0;3;"));
+        assert!(rows[2].contains(";checkout.login;"));
+        assert!(rows[2].contains(";0;7;0;"));
+    }
+
+    #[test]
+    fn given_step_with_custom_metrics_when_formatting_then_appends_custom_values() {
+        let step_status = StepStatus {
+            session_id: String::from("session"),
+            test_name: String::from("test"),
+            test_suite: String::from("suite"),
+            step_name: String::from("checkout"),
+            stage_name: String::from("load"),
+            metrics: empty_metrics(),
+            actions: Vec::new(),
+            custom_metrics: CustomMetrics {
+                counters: BTreeMap::from([(String::from("cache_hits"), 1200)]),
+                gauges: BTreeMap::from([(String::from("queue_depth"), 3.5)]),
+                histograms: BTreeMap::from([(
+                    String::from("payload_bytes"),
+                    ValueSummary {
+                        count: 2,
+                        max: 512.0,
+                        ..ValueSummary::default()
+                    },
+                )]),
+            },
+        };
+        let locale = Localization::default();
+
+        let txt = step_status.as_txt(&locale);
+        let csv = step_status.as_csv(&locale);
+
+        assert!(txt.contains("Custom Metrics"));
+        assert!(txt.contains("cache_hits          : 1,200"));
+        assert!(txt.contains("payload_bytes.max   : 512.00"));
+        assert!(csv.ends_with(
+            ";cache_hits=1,200;queue_depth=3.50;payload_bytes.count=2;payload_bytes.min=0.00;payload_bytes.mean=0.00;payload_bytes.p95=0.00;payload_bytes.max=512.00"
+        ));
+    }
+
+    #[test]
+    fn given_suite_status_when_formatting_then_reports_result_and_failures() {
+        let test_status = TestStatus {
+            session_id: String::from("case-session"),
+            test_name: String::from("checkout"),
+            test_suite: String::from("nightly"),
+            metrics: Metrics {
+                all_hits: 10,
+                positive_hits: 10,
+                ..empty_metrics()
+            },
+            sinks: Vec::new(),
+        };
+        let suite_status = SuiteStatus::new(
+            String::from("nightly"),
+            1500,
+            vec![test_status],
+            vec![CaseFailure {
+                test_name: String::from("login"),
+                reason: String::from("No load steps have found!"),
+            }],
+        );
+        let locale = Localization::default();
+
+        let txt = FileType::Txt.get_suite_content(&suite_status, &locale);
+        let csv = FileType::Csv.get_suite_content(&suite_status, &locale);
+        let json = FileType::Json.get_suite_content(&suite_status, &locale);
+
+        assert!(txt.contains("Test Suite          : nightly"));
+        assert!(txt.contains("Result              : Failed"));
+        assert!(txt.contains("Test Case           : checkout"));
+        assert!(txt.contains("login               : No load steps have found!"));
+        assert!(csv.starts_with("nightly;Passed;case-session;checkout;"));
+        assert!(csv.ends_with("nightly;Failed;;login;No load steps have found!\r\n"));
+        assert!(json.contains("\"passed\":false"));
+    }
+
+    #[test]
+    fn given_metrics_with_response_time_when_formatting_then_appends_response_time_and_lag() {
+        let metrics = Metrics {
+            response_time: TimingSummary {
+                count: 4,
+                mean: 120,
+                p90: 180,
+                p95: 190,
+                p99: 199,
+                max: 200,
+            },
+            schedule_lag: TimingSummary {
+                count: 4,
+                max: 80,
+                ..TimingSummary::default()
+            },
+            ..empty_metrics()
+        };
+        let locale = Localization::default();
+
+        let txt = metrics.as_txt(&locale);
+        let csv = metrics.as_csv(&locale);
+
+        assert!(txt.contains("Response Time       :\r\n\r\nMean Time           : 120 ms"));
+        assert!(txt.contains("Schedule Lag        :"));
+        assert!(txt.contains("Max Time            : 80 ms"));
+        assert!(csv.contains(";120;180;190;199;200;0;0;0;0;80;"));
+    }
+
+    #[test]
+    fn given_metrics_with_generator_health_when_formatting_then_appends_health_and_warnings() {
+        let metrics = Metrics {
+            generator: GeneratorHealth {
+                spawn_lag: TimingSummary {
+                    count: 100,
+                    max: 45,
+                    ..TimingSummary::default()
+                },
+                delayed_hits: 12,
+                dropped_hits: 3,
+                dropped_events: 2,
+                max_in_flight: 250,
+                cpu_usage: Some(97.5),
+                rss_bytes: Some(52_428_800),
+                warnings: vec![String::from("3 hits were dropped")],
+            },
+            ..empty_metrics()
+        };
+
+        let actual = metrics.as_txt(&Localization::default());
+        let csv = metrics.as_csv(&Localization::default());
+
+        assert!(csv.ends_with(";12;3;2;250;97.50;50.00;0;0;0;0;45;3 hits were dropped"));
+        assert!(actual.contains("Generator Health    :"));
+        assert!(actual.contains("Dropped hits        : 3"));
+        assert!(actual.contains("Dropped events      : 2"));
+        assert!(actual.contains("Max in flight       : 250"));
+        assert!(actual.contains("CPU usage           : 97.50 %"));
+        assert!(actual.contains("Memory (RSS)        : 50.00 MB"));
+        assert!(actual.contains("Spawn Lag           :"));
+        assert!(actual.ends_with("Warnings            :\r\n\r\n3 hits were dropped"));
+    }
 }
@@ -0,0 +1,291 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+use std::io::{Error, ErrorKind};
+use std::ops::Range;
+use std::sync::{Mutex, PoisonError};
+
+/// How a feeder picks the record handed to the next hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeederStrategy {
+    /// Hands the records in order and ends the step once they are exhausted.
+    Sequential,
+    /// Picks a random record for every hit, so it never runs out of records.
+    Random,
+    /// Hands the records in order, starting over once the last one is reached.
+    Circular,
+    /// Hands every record once in a random order and ends the step once they are exhausted.
+    Unique,
+}
+
+/// Source of test data for a load step, where every hit receives the next record.
+pub struct Feeder {
+    strategy: FeederStrategy,
+    records: Vec<Value>,
+    state: Mutex<FeederState>,
+}
+
+struct FeederState {
+    position: usize,
+    order: Vec<usize>,
+}
+
+impl Feeder {
+    pub fn new(records: Vec<Value>, strategy: FeederStrategy) -> Self {
+        if records.is_empty() {
+            panic!("A feeder needs at least one record");
+        }
+
+        let mut order = (0..records.len()).collect::<Vec<usize>>();
+        if strategy == FeederStrategy::Unique {
+            order.shuffle(&mut rand::thread_rng());
+        }
+
+        Self {
+            strategy,
+            records,
+            state: Mutex::new(FeederState { position: 0, order }),
+        }
+    }
+
+    /// Reads a CSV file whose first row holds the column names. Every row becomes a JSON object
+    /// with its values as strings.
+    pub fn from_csv(path: &str, strategy: FeederStrategy) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_csv_content(&content, strategy)
+    }
+
+    pub fn from_csv_content(content: &str, strategy: FeederStrategy) -> std::io::Result<Self> {
+        let mut rows = Self::parse_csv(content)?.into_iter();
+        let headers = rows
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "The CSV content is empty"))?;
+
+        let records = rows
+            .enumerate()
+            .map(|(index, row)| match row.len() == headers.len() {
+                true => Ok(Value::Object(
+                    headers
+                        .iter()
+                        .cloned()
+                        .zip(row.into_iter().map(Value::String))
+                        .collect::<Map<String, Value>>(),
+                )),
+                false => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "The CSV row {} has {} values but {} columns were expected",
+                        index + 2,
+                        row.len(),
+                        headers.len()
+                    ),
+                )),
+            })
+            .collect::<std::io::Result<Vec<Value>>>()?;
+
+        Self::from_records(records, strategy)
+    }
+
+    /// Reads a JSON file holding an array, where every item becomes a record.
+    pub fn from_json(path: &str, strategy: FeederStrategy) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_json_content(&content, strategy)
+    }
+
+    pub fn from_json_content(content: &str, strategy: FeederStrategy) -> std::io::Result<Self> {
+        match serde_json::from_str::<Value>(content)? {
+            Value::Array(records) => Self::from_records(records, strategy),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "The JSON content must be an array",
+            )),
+        }
+    }
+
+    pub fn from_sequence(range: Range<i64>, strategy: FeederStrategy) -> Self {
+        Self::new(range.map(Value::from).collect(), strategy)
+    }
+
+    pub fn from_generator<F>(count: usize, strategy: FeederStrategy, generator: F) -> Self
+    where
+        F: Fn(usize) -> Value,
+    {
+        Self::new((0..count).map(generator).collect(), strategy)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the next record, or `None` once a sequential or unique feeder is exhausted.
+    pub fn next_record(&self) -> Option<Value> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let index = match self.strategy {
+            FeederStrategy::Random => rand::thread_rng().gen_range(0..self.records.len()),
+            FeederStrategy::Circular => state.order[state.position % self.records.len()],
+            FeederStrategy::Sequential | FeederStrategy::Unique => {
+                *state.order.get(state.position)?
+            }
+        };
+
+        state.position += 1;
+        Some(self.records[index].to_owned())
+    }
+
+    fn from_records(records: Vec<Value>, strategy: FeederStrategy) -> std::io::Result<Self> {
+        match records.is_empty() {
+            true => Err(Error::new(
+                ErrorKind::InvalidData,
+                "The feeder content has no records",
+            )),
+            false => Ok(Self::new(records, strategy)),
+        }
+    }
+
+    fn parse_csv(content: &str) -> std::io::Result<Vec<Vec<String>>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut is_quoted = false;
+        let mut chars = content.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, is_quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', true) => is_quoted = false,
+                ('"', false) if field.is_empty() => is_quoted = true,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\r', false) => {}
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    let row = std::mem::take(&mut row);
+                    if row.iter().any(|value| !value.is_empty()) {
+                        rows.push(row);
+                    }
+                }
+                _ => field.push(c),
+            }
+        }
+
+        if is_quoted {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "The CSV content has an unterminated quoted value",
+            ));
+        }
+
+        row.push(field);
+        if row.iter().any(|value| !value.is_empty()) {
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::HashSet;
+
+    fn take(feeder: &Feeder, count: usize) -> Vec<Option<Value>> {
+        (0..count).map(|_| feeder.next_record()).collect()
+    }
+
+    #[test]
+    fn given_sequential_feeder_when_exhausted_then_returns_none() {
+        let feeder = Feeder::from_sequence(1..3, FeederStrategy::Sequential);
+
+        assert_eq!(
+            take(&feeder, 3),
+            vec![Some(Value::from(1)), Some(Value::from(2)), None]
+        );
+    }
+
+    #[test]
+    fn given_circular_feeder_when_exhausted_then_starts_over() {
+        let feeder = Feeder::from_sequence(1..3, FeederStrategy::Circular);
+
+        assert_eq!(
+            take(&feeder, 3),
+            vec![
+                Some(Value::from(1)),
+                Some(Value::from(2)),
+                Some(Value::from(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn given_unique_feeder_when_exhausted_then_returned_every_record_once() {
+        let feeder = Feeder::from_sequence(0..50, FeederStrategy::Unique);
+
+        let records = take(&feeder, 51);
+
+        assert_eq!(records[50], None);
+        let unique = records
+            .iter()
+            .flatten()
+            .map(|value| value.as_i64().unwrap())
+            .collect::<HashSet<i64>>();
+        assert_eq!(unique.len(), 50);
+    }
+
+    #[test]
+    fn given_random_feeder_when_taking_more_than_its_records_then_never_runs_out() {
+        let feeder = Feeder::from_sequence(0..2, FeederStrategy::Random);
+
+        assert!(take(&feeder, 10).iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn given_csv_content_when_creating_feeder_then_maps_rows_by_header() {
+        let content = "user,password\r\nalice,\"p,a\"\"ss\"\n\nbob,secret\n";
+
+        let feeder = Feeder::from_csv_content(content, FeederStrategy::Sequential).unwrap();
+
+        assert_eq!(feeder.len(), 2);
+        assert_eq!(
+            feeder.next_record().unwrap(),
+            serde_json::json!({"user": "alice", "password": "p,a\"ss"})
+        );
+        assert_eq!(feeder.next_record().unwrap()["user"], "bob");
+    }
+
+    #[test]
+    fn given_csv_row_with_missing_values_when_creating_feeder_then_returns_error() {
+        let result = Feeder::from_csv_content("user,password\nalice\n", FeederStrategy::Random);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_json_content_when_creating_feeder_then_uses_array_items() {
+        let feeder =
+            Feeder::from_json_content(r#"[{"id": 7}, {"id": 9}]"#, FeederStrategy::Sequential)
+                .unwrap();
+
+        assert_eq!(feeder.next_record().unwrap()["id"], 7);
+        assert!(Feeder::from_json_content(r#"{"id": 7}"#, FeederStrategy::Random).is_err());
+        assert!(Feeder::from_json_content("[]", FeederStrategy::Random).is_err());
+    }
+
+    #[test]
+    fn given_generator_when_creating_feeder_then_creates_records() {
+        let feeder = Feeder::from_generator(3, FeederStrategy::Sequential, |index| {
+            Value::from(format!("product-{}", index))
+        });
+
+        assert_eq!(feeder.len(), 3);
+        assert_eq!(feeder.next_record().unwrap(), "product-0");
+    }
+}
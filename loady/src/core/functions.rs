@@ -1,3 +1,6 @@
+use crate::core::transactions::Transactions;
+use crate::core::users::VirtualUser;
+use serde_json::Value;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -8,5 +11,17 @@ pub type WarmUpResult<'a> = Pin<Box<dyn Future<Output = ()> + Send + Sync + 'a>>
 pub type WarmUpFunction<'a, T> = Box<dyn Fn(Arc<T>) -> WarmUpResult<'a> + Send + Sync + 'a>;
 pub type LoadResult<'a> = Pin<Box<dyn Future<Output = Result<(), i32>> + Send + Sync + 'a>>;
 pub type LoadFunction<'a, T> = Box<dyn Fn(Arc<T>) -> LoadResult<'a> + Send + Sync + 'a>;
+pub type FeedLoadFunction<'a, T> = Box<dyn Fn(Arc<T>, Value) -> LoadResult<'a> + Send + Sync + 'a>;
+pub type TransactionLoadFunction<'a, T> =
+    Box<dyn Fn(Arc<T>, Transactions) -> LoadResult<'a> + Send + Sync + 'a>;
 pub type CleanUpResult<'a> = Pin<Box<dyn Future<Output = ()> + Send + Sync + 'a>>;
 pub type CleanUpFunction<'a, T> = Box<dyn Fn(T) -> CleanUpResult<'a> + Send + Sync + 'a>;
+pub type UserInitResult<'a, V> = Pin<Box<dyn Future<Output = Result<V, i32>> + Send + Sync + 'a>>;
+pub type UserInitFunction<'a, T, V> =
+    Box<dyn Fn(Arc<T>, usize) -> UserInitResult<'a, V> + Send + Sync + 'a>;
+pub type UserLoadFunction<'a, T, V> =
+    Box<dyn for<'u> Fn(Arc<T>, &'u mut VirtualUser<V>) -> LoadResult<'u> + Send + Sync + 'a>;
+pub type UserHitResult<'a> =
+    Pin<Box<dyn Future<Output = Option<LoadResult<'a>>> + Send + Sync + 'a>>;
+pub type UserHitFunction<'a, T> =
+    Box<dyn Fn(Arc<T>, Transactions) -> UserHitResult<'a> + Send + Sync + 'a>;
@@ -0,0 +1,309 @@
+use crate::core::stats::{GeneratorHealth, TimingSummary};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+
+/// CPU time and resident memory of the current process, read from `/proc`. It is only
+/// available on Linux.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ProcessUsage {
+    pub cpu_time: Duration,
+    pub rss_bytes: u64,
+}
+
+/// Samples the CPU usage and resident memory of the process on a timer, so that reading the
+/// generator health does not touch `/proc`. Clones share the same samples.
+#[derive(Clone, Debug, Default)]
+pub struct ProcessMonitor {
+    state: Arc<Mutex<MonitorState>>,
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    started: Option<(Instant, ProcessUsage)>,
+    usage: Option<(f64, u64)>,
+    sampler: Option<JoinHandle<()>>,
+}
+
+impl ProcessMonitor {
+    pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Reads the usage at start and keeps sampling it until the monitor is stopped.
+    pub(crate) fn start() -> Self {
+        let monitor = ProcessMonitor::default();
+        monitor.lock().started = ProcessUsage::read().map(|usage| (Instant::now(), usage));
+
+        let state = Arc::downgrade(&monitor.state);
+        monitor.lock().sampler = Some(tokio::spawn(async move {
+            loop {
+                sleep(Self::SAMPLE_INTERVAL).await;
+                match Weak::upgrade(&state) {
+                    Some(state) => ProcessMonitor { state }.sample(),
+                    None => break,
+                }
+            }
+        }));
+        monitor
+    }
+
+    /// Takes a last sample and stops sampling.
+    pub(crate) fn stop(&self) {
+        self.sample();
+        if let Some(sampler) = self.lock().sampler.take() {
+            sampler.abort();
+        }
+    }
+
+    /// Returns the CPU usage since start, in percent of a core, and the resident memory of the
+    /// last sample.
+    pub(crate) fn get_usage(&self) -> Option<(f64, u64)> {
+        self.lock().usage
+    }
+
+    fn sample(&self) {
+        let Some((started_at, start)) = self.lock().started else {
+            return;
+        };
+        let Some(now) = ProcessUsage::read() else {
+            return;
+        };
+
+        let cpu_time = now.cpu_time.saturating_sub(start.cpu_time);
+        let cpu_usage = match started_at.elapsed().as_secs_f64() {
+            elapsed if elapsed > 0f64 => cpu_time.as_secs_f64() / elapsed * 100f64,
+            _ => 0f64,
+        };
+        self.lock().usage = Some((cpu_usage, now.rss_bytes));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MonitorState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ProcessUsage {
+    /// Clock ticks per second used by `/proc/self/stat`, read once with `sysconf(_SC_CLK_TCK)`.
+    /// It falls back to the `USER_HZ` of 100 that Linux exposes on most architectures.
+    #[cfg(target_os = "linux")]
+    fn clock_ticks() -> u64 {
+        static CLOCK_TICKS: OnceLock<u64> = OnceLock::new();
+        *CLOCK_TICKS.get_or_init(|| {
+            // SAFETY: sysconf only reads a system setting and has no preconditions.
+            match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+                ticks if ticks > 0 => ticks as u64,
+                _ => 100,
+            }
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read() -> Option<Self> {
+        None
+    }
+
+    #[cfg(target_os = "linux")]
+    fn read() -> Option<Self> {
+        let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+        Some(Self {
+            cpu_time: Self::parse_cpu_time(&stat, Self::clock_ticks())?,
+            rss_bytes: Self::parse_rss_bytes(&status)?,
+        })
+    }
+
+    /// Returns the user and system time of the process, skipping its name as it may hold
+    /// spaces.
+    fn parse_cpu_time(stat: &str, clock_ticks: u64) -> Option<Duration> {
+        let fields = stat[stat.rfind(')')? + 1..]
+            .split_whitespace()
+            .collect::<Vec<&str>>();
+        let user_time = fields.get(11)?.parse::<u64>().ok()?;
+        let system_time = fields.get(12)?.parse::<u64>().ok()?;
+
+        Some(Duration::from_millis(
+            (user_time + system_time) * 1000 / clock_ticks,
+        ))
+    }
+
+    fn parse_rss_bytes(status: &str) -> Option<u64> {
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kilobytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+        Some(kilobytes * 1024)
+    }
+}
+
+impl GeneratorHealth {
+    /// Hits spawned later than this after their scheduled start count as delayed.
+    pub const DELAYED_HIT_THRESHOLD: Duration = Duration::from_millis(10);
+    /// Share of delayed hits above which the generator is reported as falling behind.
+    pub const DELAYED_HITS_RATIO: f64 = 0.01;
+    /// CPU usage, as a share of the available cores, above which the generator is reported as
+    /// saturated.
+    pub const CPU_USAGE_RATIO: f64 = 0.9;
+
+    /// Names of the thresholds, as reported by the generator warning events.
+    pub const DELAYED_HITS: &str = "delayed_hits";
+    pub const DROPPED_HITS: &str = "dropped_hits";
+    pub const DROPPED_EVENTS: &str = "dropped_events";
+    pub const CPU_USAGE: &str = "cpu_usage";
+
+    pub(crate) fn new(
+        spawn_lag: TimingSummary,
+        delayed_hits: u128,
+        dropped_hits: u128,
+        dropped_events: u128,
+        max_in_flight: u128,
+        usage: Option<(f64, u64)>,
+    ) -> Self {
+        let mut health = GeneratorHealth {
+            spawn_lag,
+            delayed_hits,
+            dropped_hits,
+            dropped_events,
+            max_in_flight,
+            cpu_usage: usage.map(|(cpu_usage, _)| cpu_usage),
+            rss_bytes: usage.map(|(_, rss_bytes)| rss_bytes),
+            warnings: Vec::new(),
+        };
+        health.warnings = health
+            .get_crossed_thresholds()
+            .into_iter()
+            .map(|(_, warning)| warning)
+            .collect();
+        health
+    }
+
+    /// Number of cores available to the process, read once as it doesn't change during a run.
+    fn cores() -> usize {
+        static CORES: OnceLock<usize> = OnceLock::new();
+        *CORES.get_or_init(|| std::thread::available_parallelism().map_or(1, |cores| cores.get()))
+    }
+
+    /// Returns the name of every threshold the generator crossed along with its warning.
+    pub(crate) fn get_crossed_thresholds(&self) -> Vec<(&'static str, String)> {
+        let mut warnings = Vec::new();
+
+        if self.delayed_hits as f64 > self.spawn_lag.count as f64 * Self::DELAYED_HITS_RATIO {
+            warnings.push((
+                Self::DELAYED_HITS,
+                format!(
+                    "{} of {} hits were spawned over {} ms behind schedule",
+                    self.delayed_hits,
+                    self.spawn_lag.count,
+                    Self::DELAYED_HIT_THRESHOLD.as_millis()
+                ),
+            ));
+        }
+
+        if self.dropped_hits > 0 {
+            warnings.push((
+                Self::DROPPED_HITS,
+                format!(
+                    "{} hits were dropped as the generator fell behind schedule",
+                    self.dropped_hits
+                ),
+            ));
+        }
+
+        if self.dropped_events > 0 {
+            warnings.push((
+                Self::DROPPED_EVENTS,
+                format!(
+                    "{} lifecycle events were dropped as the reporting pipeline was full",
+                    self.dropped_events
+                ),
+            ));
+        }
+
+        let cores = Self::cores();
+        if let Some(cpu_usage) = self.cpu_usage {
+            if cpu_usage > cores as f64 * 100f64 * Self::CPU_USAGE_RATIO {
+                warnings.push((
+                    Self::CPU_USAGE,
+                    format!(
+                        "The generator used {:.0}% of CPU out of {} cores",
+                        cpu_usage, cores
+                    ),
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn given_proc_files_when_parsing_then_returns_cpu_time_and_rss() {
+        let stat = "4321 (loady (worker)) S 1 4321 4321 0 -1 4194560 2310 0 0 0 250 50 0 0 20 0 9 0 1234 123456789 3000";
+        let status = "Name:\tloady\nVmPeak:\t  204800 kB\nVmRSS:\t   51200 kB\nThreads:\t9\n";
+
+        assert_eq!(
+            ProcessUsage::parse_cpu_time(stat, 100),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            ProcessUsage::parse_cpu_time(stat, 250),
+            Some(Duration::from_millis(1200))
+        );
+        assert_eq!(ProcessUsage::parse_rss_bytes(status), Some(52_428_800));
+        assert_eq!(ProcessUsage::parse_rss_bytes("Name:\tloady\n"), None);
+    }
+
+    #[test]
+    fn given_late_and_dropped_hits_when_checking_health_then_warns() {
+        let spawn_lag = TimingSummary {
+            count: 5,
+            max: 60,
+            ..TimingSummary::default()
+        };
+
+        let actual = GeneratorHealth::new(spawn_lag, 2, 3, 0, 12, None);
+
+        assert_eq!(actual.delayed_hits, 2);
+        assert_eq!(actual.max_in_flight, 12);
+        assert_eq!(actual.warnings.len(), 2);
+        assert!(actual.warnings[1].starts_with("3 hits were dropped"));
+    }
+
+    #[test]
+    fn given_dropped_events_when_checking_health_then_warns() {
+        let actual = GeneratorHealth::new(TimingSummary::default(), 0, 0, 4, 1, None);
+
+        assert_eq!(actual.dropped_events, 4);
+        assert_eq!(actual.warnings.len(), 1);
+        assert!(actual.warnings[0].starts_with("4 lifecycle events were dropped"));
+    }
+
+    #[test]
+    fn given_hits_on_schedule_when_checking_health_then_does_not_warn() {
+        let spawn_lag = TimingSummary {
+            count: 3,
+            max: 2,
+            ..TimingSummary::default()
+        };
+
+        let actual = GeneratorHealth::new(spawn_lag, 0, 0, 0, 3, Some((12.5, 1024)));
+
+        assert_eq!(actual.delayed_hits, 0);
+        assert_eq!(actual.cpu_usage, Some(12.5));
+        assert!(actual.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn given_stopped_monitor_when_getting_usage_then_returns_last_sample() {
+        let monitor = ProcessMonitor::start();
+        let before_stop = monitor.get_usage();
+
+        monitor.stop();
+
+        assert!(before_stop.is_none());
+        assert_eq!(monitor.get_usage().is_some(), cfg!(target_os = "linux"));
+        assert!(monitor.lock().sampler.is_none());
+    }
+}
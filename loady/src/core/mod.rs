@@ -1,7 +1,16 @@
+pub mod arrivals;
+pub mod cancellation;
 pub mod composition;
 pub mod context;
+pub mod delivery;
 pub mod exporting;
+pub mod feeders;
 pub mod functions;
+pub mod health;
+pub mod registry;
 pub mod reporting;
 pub mod runner;
 pub mod stats;
+pub mod suite;
+pub mod transactions;
+pub mod users;
@@ -0,0 +1,234 @@
+use crate::core::stats::{CustomMetrics, ValueSummary};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Registry of user defined counters, gauges and value histograms. Clones share the same
+/// registry, so it can be kept in the test data and used from inside the actions.
+///
+/// A registry is shared by the whole test, so its snapshot holds every value recorded since the
+/// test started, whatever the step that recorded it.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsRegistry {
+    state: Arc<Mutex<RegistryState>>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, f64>,
+    histograms: BTreeMap<String, ValueRecorder>,
+}
+
+/// Log-scaled buckets of the values recorded into a histogram, which summarizes them in bounded
+/// memory. Every bucket spans values within [`ValueRecorder::GROWTH`] of each other, so the
+/// percentiles are off by less than one percent of the recorded values.
+#[derive(Debug, Default)]
+struct ValueRecorder {
+    /// Buckets of the negative values, keyed by the bucket of their absolute value.
+    negative: BTreeMap<i32, u64>,
+    zero: u64,
+    positive: BTreeMap<i32, u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl MetricsRegistry {
+    pub fn increment_counter(&self, name: &str, value: u64) {
+        *self.lock().counters.entry(name.to_owned()).or_insert(0) += value;
+    }
+
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        self.lock().gauges.insert(name.to_owned(), value);
+    }
+
+    /// Records a value into the histogram of the given name. Values that aren't finite are
+    /// ignored.
+    pub fn record_value(&self, name: &str, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        self.lock()
+            .histograms
+            .entry(name.to_owned())
+            .or_default()
+            .add(value);
+    }
+
+    pub fn snapshot(&self) -> CustomMetrics {
+        let state = self.lock();
+
+        CustomMetrics {
+            counters: state.counters.clone(),
+            gauges: state.gauges.clone(),
+            histograms: state
+                .histograms
+                .iter()
+                .map(|(name, recorder)| (name.to_owned(), recorder.summary()))
+                .collect(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RegistryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ValueRecorder {
+    /// Ratio between the upper bounds of two consecutive buckets.
+    const GROWTH: f64 = 1.02;
+    const P50: f64 = 0.5;
+    const P90: f64 = 0.9;
+    const P95: f64 = 0.95;
+    const P99: f64 = 0.99;
+
+    fn add(&mut self, value: f64) {
+        match value {
+            _ if value > 0f64 => *self.positive.entry(Self::index(value)).or_default() += 1,
+            _ if value < 0f64 => *self.negative.entry(Self::index(-value)).or_default() += 1,
+            _ => self.zero += 1,
+        }
+
+        match self.count {
+            0 => (self.min, self.max) = (value, value),
+            _ => (self.min, self.max) = (self.min.min(value), self.max.max(value)),
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    fn summary(&self) -> ValueSummary {
+        ValueSummary {
+            count: self.count,
+            sum: self.sum,
+            min: self.min,
+            max: self.max,
+            mean: self.sum / self.count as f64,
+            p50: self.percentile(Self::P50),
+            p90: self.percentile(Self::P90),
+            p95: self.percentile(Self::P95),
+            p99: self.percentile(Self::P99),
+        }
+    }
+
+    /// Returns the index of the bucket of a positive value, whose upper bound is
+    /// `GROWTH ^ index`.
+    fn index(value: f64) -> i32 {
+        (value.ln() / Self::GROWTH.ln()).ceil() as i32
+    }
+
+    /// Returns the value halfway, in relative terms, between the bounds of the bucket.
+    fn value(index: i32) -> f64 {
+        2f64 * Self::GROWTH.powi(index) / (Self::GROWTH + 1f64)
+    }
+
+    /// Walks the buckets from the lowest value up to the one holding the rank of the
+    /// percentile, and returns its value capped by the lowest and the highest values.
+    fn percentile(&self, percentile: f64) -> f64 {
+        let rank = ((percentile * self.count as f64).ceil() as u64).max(1);
+        let buckets = self
+            .negative
+            .iter()
+            .rev()
+            .map(|(index, count)| (-Self::value(*index), *count))
+            .chain(std::iter::once((0f64, self.zero)))
+            .chain(
+                self.positive
+                    .iter()
+                    .map(|(index, count)| (Self::value(*index), *count)),
+            );
+
+        let mut seen = 0;
+        for (value, count) in buckets {
+            seen += count;
+            if seen >= rank {
+                return value.clamp(self.min, self.max);
+            }
+        }
+
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn given_counters_and_gauges_when_taking_snapshot_then_returns_latest_values() {
+        let registry = MetricsRegistry::default();
+        let shared = registry.clone();
+
+        registry.increment_counter("cache_hits", 2);
+        shared.increment_counter("cache_hits", 3);
+        registry.set_gauge("queue_depth", 4.0);
+        shared.set_gauge("queue_depth", 1.5);
+
+        let actual = registry.snapshot();
+
+        assert_eq!(actual.counters.get("cache_hits"), Some(&5));
+        assert_eq!(actual.gauges.get("queue_depth"), Some(&1.5));
+        assert!(actual.histograms.is_empty());
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 0.01,
+            "{} isn't within 1% of {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn given_recorded_values_when_taking_snapshot_then_summarizes_histogram() {
+        let registry = MetricsRegistry::default();
+        (1..=100).for_each(|value| registry.record_value("payload_bytes", value as f64));
+
+        let actual = registry.snapshot();
+        let summary = actual.histograms.get("payload_bytes").unwrap();
+
+        assert_eq!(summary.count, 100);
+        assert_eq!(summary.sum, 5050.0);
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.max, 100.0);
+        assert_eq!(summary.mean, 50.5);
+        assert_close(summary.p50, 50.0);
+        assert_close(summary.p95, 95.0);
+        assert_close(summary.p99, 99.0);
+    }
+
+    #[test]
+    fn given_many_values_when_recording_then_keeps_bounded_buckets() {
+        let registry = MetricsRegistry::default();
+        (0..100_000).for_each(|value| registry.record_value("latency", (value % 1000) as f64));
+
+        let actual = registry.snapshot();
+        let summary = actual.histograms.get("latency").unwrap();
+        let buckets = registry.lock().histograms["latency"].positive.len();
+
+        assert_eq!(summary.count, 100_000);
+        assert_close(summary.p90, 899.0);
+        assert!(buckets < 400);
+    }
+
+    #[test]
+    fn given_negative_zero_and_invalid_values_when_recording_then_ignores_invalid_ones() {
+        let registry = MetricsRegistry::default();
+        [-10.0, 0.0, 10.0, f64::NAN, f64::INFINITY]
+            .iter()
+            .for_each(|value| registry.record_value("delta", *value));
+
+        let actual = registry.snapshot();
+        let summary = actual.histograms.get("delta").unwrap();
+
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.min, -10.0);
+        assert_eq!(summary.max, 10.0);
+        assert_eq!(summary.p50, 0.0);
+        assert_eq!(summary.p99, 10.0);
+    }
+}
@@ -1,8 +1,15 @@
+use crate::core::cancellation::Cancellation;
 use crate::core::context::TestContext;
+use crate::core::delivery::{SinkDispatcher, SinkEvent};
 use crate::core::exporting::{Exporter, FileType, Localization};
-use crate::core::stats::{StepStatus, TestStatus};
+use crate::core::stats::{
+    GeneratorWarningStatus, InternalStepStatus, Metrics, StageStatus, StepOutcome,
+    StepStartedStatus, StepStatus, SuiteStatus, TestPlan, TestStatus,
+};
 use async_trait::async_trait;
-use std::fmt::Debug;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::fmt::{Debug, Display, Formatter};
 use std::io::Error;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
@@ -11,42 +18,324 @@ use tokio::time::{Duration, Instant};
 pub struct Reporter {
     pub exporter: Exporter,
     pub use_summary: bool,
+    pub use_plain_output: bool,
     pub reporting_frequency: Duration,
     stats_by_steps: Arc<Mutex<Vec<StepStatus>>>,
 }
 
 #[derive(Default, Clone)]
-pub struct DefaultReportingSink;
+pub struct DefaultReportingSink {
+    localization: Localization,
+}
+
+/// How the console sink writes every event.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConsoleFormat {
+    /// One `key=value` line per event.
+    #[default]
+    Line,
+    /// One JSON object per line, for log shippers.
+    JsonLines,
+}
+
+/// Prints one compact line per event and never emits control codes, which suits CI logs.
+#[derive(Default, Clone)]
+pub struct ConsoleReportingSink {
+    format: ConsoleFormat,
+}
+
+#[derive(Clone, Debug)]
+pub enum LifecycleEvent {
+    TestStarted(TestPlan),
+    StepStarted(StepStartedStatus),
+    StageStarted(StageStatus),
+    StageEnded(StageStatus),
+    InternalStepEnded(InternalStepStatus),
+    GeneratorWarning(GeneratorWarningStatus),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SinkError {
+    pub message: String,
+}
+
+pub type SinkResult = Result<(), SinkError>;
 
 #[async_trait]
 pub trait ReportingSink: Sync + Send {
-    async fn on_test_ended(&self, status: TestStatus);
-    async fn on_load_step_ended(&self, status: StepStatus);
-    async fn on_load_action_ended(&self, step_status: StepStatus);
-    async fn on_internal_step_ended(&self, step_name: &str);
+    /// Name used to identify the sink in the delivery reports of the test status.
+    fn name(&self) -> String {
+        let type_name = std::any::type_name::<Self>();
+        type_name
+            .rsplit("::")
+            .next()
+            .unwrap_or(type_name)
+            .to_owned()
+    }
+
+    /// Called once the runner starts driving the sink, with the cancellation of the run, which
+    /// the sink may use to stop the test early.
+    fn on_attached(&self, _cancellation: Cancellation) {}
+
+    async fn on_test_started(&self, _plan: TestPlan) -> SinkResult {
+        Ok(())
+    }
+
+    async fn on_step_started(&self, _status: StepStartedStatus) -> SinkResult {
+        Ok(())
+    }
+
+    async fn on_stage_started(&self, _status: StageStatus) -> SinkResult {
+        Ok(())
+    }
+
+    async fn on_stage_ended(&self, _status: StageStatus) -> SinkResult {
+        Ok(())
+    }
+
+    async fn on_suite_ended(&self, _status: SuiteStatus) -> SinkResult {
+        Ok(())
+    }
+
+    /// Called the first time a step crosses one of the generator health thresholds.
+    async fn on_generator_warning(&self, _status: GeneratorWarningStatus) -> SinkResult {
+        Ok(())
+    }
+
+    async fn on_test_ended(&self, status: TestStatus) -> SinkResult;
+    async fn on_load_step_ended(&self, status: StepStatus) -> SinkResult;
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult;
+    async fn on_internal_step_ended(&self, status: InternalStepStatus) -> SinkResult;
+}
+
+impl SinkError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for SinkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+impl DefaultReportingSink {
+    pub fn new(localization: Localization) -> Self {
+        Self { localization }
+    }
 }
 
 #[async_trait]
 impl ReportingSink for DefaultReportingSink {
-    async fn on_test_ended(&self, test_status: TestStatus) {
-        let locale = Localization::default();
+    async fn on_generator_warning(&self, status: GeneratorWarningStatus) -> SinkResult {
+        eprintln!("Warning on {}: {}", status.step_name, status.warning);
+        Ok(())
+    }
+
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
         print!("\x1B[2J\x1B[1;1H");
-        println!("{}", test_status.as_txt(&locale));
+        println!("{}", test_status.as_txt(&self.localization));
+        Ok(())
     }
 
-    async fn on_load_step_ended(&self, step_status: StepStatus) {
-        let locale = Localization::default();
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
         print!("\x1B[2J\x1B[1;1H");
-        println!("{}", step_status.as_txt(&locale));
+        println!("{}", step_status.as_txt(&self.localization));
+        Ok(())
     }
 
-    async fn on_load_action_ended(&self, step_status: StepStatus) {
-        let locale = Localization::default();
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
         print!("\x1B[2J\x1B[1;1H");
-        println!("{}", step_status.as_txt(&locale));
+        println!("{}", step_status.as_txt(&self.localization));
+        Ok(())
+    }
+
+    async fn on_internal_step_ended(&self, _status: InternalStepStatus) -> SinkResult {
+        Ok(())
+    }
+}
+
+impl ConsoleReportingSink {
+    pub fn new(format: ConsoleFormat) -> Self {
+        Self { format }
+    }
+
+    fn print(&self, fields: Vec<(&str, Value)>) -> SinkResult {
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        println!("{}", self.format_record(&timestamp, fields));
+        Ok(())
+    }
+
+    fn format_record(&self, timestamp: &str, fields: Vec<(&str, Value)>) -> String {
+        match self.format {
+            ConsoleFormat::Line => fields
+                .iter()
+                .fold(timestamp.to_owned(), |line, (key, value)| {
+                    format!("{} {}={}", line, key, Self::format_value(value))
+                }),
+            ConsoleFormat::JsonLines => {
+                let mut record = Map::new();
+                record.insert(String::from("timestamp"), timestamp.into());
+                fields.into_iter().for_each(|(key, value)| {
+                    record.insert(key.to_owned(), value);
+                });
+                Value::Object(record).to_string()
+            }
+        }
+    }
+
+    fn format_value(value: &Value) -> String {
+        match value {
+            Value::String(text) if text.is_empty() || text.contains([' ', '=', '"']) => {
+                format!("{:?}", text)
+            }
+            Value::String(text) => text.to_owned(),
+            Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, value))
+                .collect::<Vec<String>>()
+                .join(","),
+            _ => value.to_string(),
+        }
+    }
+
+    fn metric_fields(metrics: &Metrics) -> Vec<(&'static str, Value)> {
+        let mut fields = vec![
+            ("rps", Self::round(metrics.request_per_sec)),
+            ("p95_ms", (metrics.p95_time as u64).into()),
+            ("in_flight", (metrics.in_flight as u64).into()),
+            ("ok", (metrics.positive_hits as u64).into()),
+            ("ko", (metrics.negative_hits as u64).into()),
+        ];
+
+        if !metrics.errors.is_empty() {
+            let errors = metrics
+                .errors
+                .iter()
+                .map(|(code, count)| (code.to_string(), Value::from(*count as u64)))
+                .collect::<Map<String, Value>>();
+            fields.push(("errors", Value::Object(errors)));
+        }
+
+        if !metrics.generator.warnings.is_empty() {
+            fields.push(("warnings", metrics.generator.warnings.join("; ").into()));
+        }
+
+        fields
+    }
+
+    fn step_fields(event: &str, step_status: &StepStatus) -> Vec<(&'static str, Value)> {
+        let mut fields = vec![
+            ("event", event.into()),
+            ("test", step_status.test_name.as_str().into()),
+            ("step", step_status.step_name.as_str().into()),
+            ("stage", step_status.stage_name.as_str().into()),
+        ];
+        fields.extend(Self::metric_fields(&step_status.metrics));
+        fields
+    }
+
+    fn round(value: f64) -> Value {
+        ((value * 100f64).round() / 100f64).into()
+    }
+}
+
+#[async_trait]
+impl ReportingSink for ConsoleReportingSink {
+    async fn on_test_started(&self, plan: TestPlan) -> SinkResult {
+        self.print(vec![
+            ("event", "test_started".into()),
+            ("test", plan.test_name.into()),
+            ("session", plan.session_id.into()),
+        ])
+    }
+
+    async fn on_stage_started(&self, status: StageStatus) -> SinkResult {
+        let mut fields = vec![
+            ("event", "stage_started".into()),
+            ("test", status.test_name.into()),
+            ("step", status.step_name.into()),
+            ("stage", status.stage.stage_name.into()),
+        ];
+
+        if status.stage.interval > 0 {
+            let target = status.stage.rate as f64 * 1000f64 / status.stage.interval as f64;
+            fields.push(("target_rps", Self::round(target)));
+        }
+
+        self.print(fields)
+    }
+
+    async fn on_test_ended(&self, test_status: TestStatus) -> SinkResult {
+        let mut fields = vec![
+            ("event", "test_ended".into()),
+            ("test", test_status.test_name.as_str().into()),
+            ("session", test_status.session_id.as_str().into()),
+            (
+                "duration_ms",
+                (test_status.metrics.test_duration as u64).into(),
+            ),
+        ];
+        fields.extend(Self::metric_fields(&test_status.metrics));
+        self.print(fields)
+    }
+
+    async fn on_generator_warning(&self, status: GeneratorWarningStatus) -> SinkResult {
+        self.print(vec![
+            ("event", "generator_warning".into()),
+            ("test", status.test_name.into()),
+            ("step", status.step_name.into()),
+            ("threshold", status.threshold.into()),
+            ("warning", status.warning.into()),
+        ])
+    }
+
+    async fn on_suite_ended(&self, status: SuiteStatus) -> SinkResult {
+        self.print(vec![
+            ("event", "suite_ended".into()),
+            ("suite", status.suite_name.into()),
+            ("session", status.session_id.into()),
+            ("passed", status.passed.into()),
+            ("duration_ms", (status.duration as u64).into()),
+            ("cases", (status.test_status.len() as u64).into()),
+            ("failures", (status.failures.len() as u64).into()),
+            ("ok", (status.positive_hits as u64).into()),
+            ("ko", (status.negative_hits as u64).into()),
+        ])
+    }
+
+    async fn on_load_step_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.print(Self::step_fields("step_ended", &step_status))
+    }
+
+    async fn on_load_action_ended(&self, step_status: StepStatus) -> SinkResult {
+        self.print(Self::step_fields("snapshot", &step_status))
     }
 
-    async fn on_internal_step_ended(&self, _step_name: &str) {}
+    async fn on_internal_step_ended(&self, status: InternalStepStatus) -> SinkResult {
+        let mut fields = vec![
+            ("event", "internal_step_ended".into()),
+            ("test", status.test_name.into()),
+            ("step", status.step_name.into()),
+            ("kind", format!("{:?}", status.kind).into()),
+            ("duration_ms", (status.duration as u64).into()),
+        ];
+
+        match status.outcome {
+            StepOutcome::Succeeded => fields.push(("outcome", "succeeded".into())),
+            StepOutcome::Failed(error) => {
+                fields.push(("outcome", "failed".into()));
+                fields.push(("error", error.into()));
+            }
+        }
+
+        self.print(fields)
+    }
 }
 
 impl Default for Reporter {
@@ -54,6 +343,7 @@ impl Default for Reporter {
         Self {
             exporter: Default::default(),
             use_summary: Default::default(),
+            use_plain_output: Default::default(),
             reporting_frequency: Self::DEFAULT_REPORTING_FREQUENCY,
             stats_by_steps: Arc::new(Mutex::new(Vec::default())),
         }
@@ -62,29 +352,30 @@ impl Default for Reporter {
 
 impl Reporter {
     pub const DEFAULT_REPORTING_FREQUENCY: Duration = Duration::from_secs(5);
+    /// Capacity of the channels feeding the reporter. The load loop never waits for room in
+    /// them: snapshots and lifecycle events that don't fit are dropped.
+    pub const CHANNEL_CAPACITY: usize = 128;
 
     pub fn handle_action_ended<T>(
         &self,
-        sinks: &Arc<Vec<Arc<Box<dyn ReportingSink>>>>,
+        dispatcher: &Arc<SinkDispatcher>,
     ) -> (tokio::task::JoinHandle<()>, mpsc::Sender<T>)
     where
         T: TestContext + 'static + Sync + Debug,
     {
-        let (sender, mut receiver) = mpsc::channel::<T>(10);
-        let sinks = Arc::clone(sinks);
+        let (sender, mut receiver) = mpsc::channel::<T>(Self::CHANNEL_CAPACITY);
+        let dispatcher = Arc::clone(dispatcher);
         let reporting_frequency = self.reporting_frequency;
 
         let t_action_join = tokio::spawn(async move {
+            let has_sinks = !dispatcher.is_empty().await;
             let mut frequency_instant = Instant::now();
             while let Some(inner_ctx) = receiver.recv().await {
-                let sinks = Arc::clone(&sinks);
-                if !sinks.is_empty() && frequency_instant.elapsed() > reporting_frequency {
+                if has_sinks && frequency_instant.elapsed() > reporting_frequency {
                     let step_status = StepStatus::new(inner_ctx.get_test_name(), inner_ctx);
-
-                    for sink in sinks.as_ref() {
-                        sink.on_load_action_ended(step_status.to_owned()).await;
-                    }
-
+                    dispatcher
+                        .publish(SinkEvent::LoadActionEnded(step_status))
+                        .await;
                     frequency_instant = Instant::now();
                 }
             }
@@ -95,26 +386,21 @@ impl Reporter {
 
     pub fn handle_load_step_ended<T>(
         &self,
-        sinks: &Arc<Vec<Arc<Box<dyn ReportingSink>>>>,
+        dispatcher: &Arc<SinkDispatcher>,
     ) -> (tokio::task::JoinHandle<()>, mpsc::Sender<T>)
     where
         T: TestContext + 'static + Sync + Debug,
     {
-        let (sender, mut receiver) = mpsc::channel::<T>(10);
-        let sinks = Arc::clone(sinks);
+        let (sender, mut receiver) = mpsc::channel::<T>(Self::CHANNEL_CAPACITY);
+        let dispatcher = Arc::clone(dispatcher);
         let stats_by_step = Arc::clone(&self.stats_by_steps);
 
         let t_step_join = tokio::spawn(async move {
             while let Some(inner_ctx) = receiver.recv().await {
-                let sinks = Arc::clone(&sinks);
                 let step_status = StepStatus::new(inner_ctx.get_test_name(), inner_ctx);
-
-                if !sinks.is_empty() {
-                    for sink in sinks.as_ref() {
-                        sink.on_load_step_ended(step_status.to_owned()).await;
-                    }
-                }
-
+                dispatcher
+                    .publish(SinkEvent::LoadStepEnded(step_status.to_owned()))
+                    .await;
                 stats_by_step.lock().await.push(step_status);
             }
         });
@@ -122,71 +408,174 @@ impl Reporter {
         (t_step_join, sender)
     }
 
-    pub fn handle_internal_events<T>(
+    pub fn handle_lifecycle_events(
         &self,
-        sinks: &Arc<Vec<Arc<Box<dyn ReportingSink>>>>,
-    ) -> (tokio::task::JoinHandle<()>, mpsc::Sender<T>)
-    where
-        T: TestContext + 'static + Sync + Debug,
-    {
-        let (sender, mut receiver) = mpsc::channel::<T>(10);
-        let sinks = Arc::clone(sinks);
+        dispatcher: &Arc<SinkDispatcher>,
+    ) -> (tokio::task::JoinHandle<()>, mpsc::Sender<LifecycleEvent>) {
+        let (sender, mut receiver) = mpsc::channel::<LifecycleEvent>(Self::CHANNEL_CAPACITY);
+        let dispatcher = Arc::clone(dispatcher);
 
-        let t_internal_step_join = tokio::spawn(async move {
-            while let Some(inner_ctx) = receiver.recv().await {
-                let step_name = &inner_ctx.get_current_step_name();
-                if !sinks.is_empty() {
-                    for sink in sinks.as_ref() {
-                        let sink = Arc::clone(sink);
-                        sink.on_internal_step_ended(step_name).await;
-                    }
-                }
+        let t_lifecycle_join = tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                dispatcher.publish(SinkEvent::Lifecycle(event)).await;
             }
         });
 
-        (t_internal_step_join, sender)
+        (t_lifecycle_join, sender)
     }
 
-    pub async fn report_test_status<T>(
+    pub async fn report_test_status(
         &self,
-        sinks: Arc<Vec<Arc<Box<dyn ReportingSink>>>>,
-        ctx: T,
-    ) -> Result<TestStatus, Error>
+        dispatcher: Arc<SinkDispatcher>,
+        mut test_status: TestStatus,
+    ) -> Result<TestStatus, Error> {
+        test_status.sinks = dispatcher.shutdown().await;
+
+        let stats_by_step = self.export_test_status(&test_status).await?;
+
+        if self.use_summary {
+            let content = FileType::Txt.get_content(
+                test_status.to_owned(),
+                stats_by_step,
+                &self.exporter.localization,
+            );
+            self.print_summary(&content);
+        }
+
+        Ok(test_status)
+    }
+
+    /// Publishes the status of a test case that has ended into the sinks.
+    pub async fn publish_test_status<T>(dispatcher: &Arc<SinkDispatcher>, ctx: T) -> TestStatus
     where
         T: TestContext + 'static + Sync + Debug,
     {
         let test_status = TestStatus::new(ctx.get_test_name(), ctx);
-        let stats_by_step = self.stats_by_steps.lock().await.to_vec();
+        dispatcher
+            .publish(SinkEvent::TestEnded(test_status.to_owned()))
+            .await;
+        test_status
+    }
+
+    /// Writes the output files of a test case along with the status of its steps, which are
+    /// returned.
+    pub async fn export_test_status(
+        &self,
+        test_status: &TestStatus,
+    ) -> Result<Vec<StepStatus>, Error> {
+        let stats_by_step = self
+            .stats_by_steps
+            .lock()
+            .await
+            .iter()
+            .filter(|step_status| step_status.session_id == test_status.session_id)
+            .cloned()
+            .collect::<Vec<StepStatus>>();
 
-        Self::write_to_sinks(sinks, test_status.to_owned()).await?;
         self.exporter
             .write_output_files(test_status.to_owned(), stats_by_step.to_owned())?;
 
+        Ok(stats_by_step)
+    }
+
+    pub async fn report_suite_status(
+        &self,
+        dispatcher: Arc<SinkDispatcher>,
+        mut suite_status: SuiteStatus,
+    ) -> Result<SuiteStatus, Error> {
+        dispatcher
+            .publish(SinkEvent::SuiteEnded(suite_status.to_owned()))
+            .await;
+        suite_status.sinks = dispatcher.shutdown().await;
+
+        for test_status in &suite_status.test_status {
+            self.export_test_status(test_status).await?;
+        }
+        self.exporter.write_suite_files(&suite_status)?;
+
         if self.use_summary {
             let content =
-                FileType::Txt.get_content(test_status.to_owned(), stats_by_step.to_owned());
-
-            print!("\x1B[2J\x1B[1;1H");
-            println!("\r\n{}\r\n", content);
+                FileType::Txt.get_suite_content(&suite_status, &self.exporter.localization);
+            self.print_summary(&content);
         }
 
-        Ok(test_status)
+        Ok(suite_status)
     }
 
-    async fn write_to_sinks(
-        sinks: Arc<Vec<Arc<Box<dyn ReportingSink>>>>,
-        test_status: TestStatus,
-    ) -> Result<(), Error> {
-        if !sinks.is_empty() {
-            let arc_test_status = Arc::new(Mutex::new(test_status));
-            for sink in sinks.as_ref() {
-                let status = Arc::clone(&arc_test_status);
-                let cloned_sink = Arc::clone(sink);
-                let t_status = status.lock().await.clone();
-                cloned_sink.on_test_ended(t_status).await;
+    fn print_summary(&self, content: &str) {
+        match self.use_plain_output {
+            true => println!("{}", content.replace("\r\n", "\n")),
+            false => {
+                print!("\x1B[2J\x1B[1;1H");
+                println!("\r\n{}\r\n", content);
             }
         }
+    }
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::context::TestCaseContext;
+    use std::collections::HashMap;
+
+    const TIMESTAMP: &str = "2024-01-01T00:00:00.000Z";
+
+    fn step_status() -> StepStatus {
+        let mut step_status = StepStatus::new(String::from("test"), TestCaseContext::default());
+        step_status.step_name = String::from("load");
+        step_status.stage_name = String::from("second wave");
+        step_status.metrics.request_per_sec = 6.2871;
+        step_status.metrics.p95_time = 191;
+        step_status.metrics.positive_hits = 44;
+        step_status.metrics.negative_hits = 3;
+        step_status.metrics.errors = HashMap::from([(500, 2), (401, 1)]);
+        step_status
+    }
+
+    #[test]
+    fn given_line_format_when_formatting_snapshot_then_returns_single_plain_line() {
+        let sink = ConsoleReportingSink::new(ConsoleFormat::Line);
+        let fields = ConsoleReportingSink::step_fields("snapshot", &step_status());
+
+        let line = sink.format_record(TIMESTAMP, fields);
+
+        assert_eq!(
+            line,
+            "2024-01-01T00:00:00.000Z event=snapshot test=test step=load stage=\"second wave\" rps=6.29 p95_ms=191 in_flight=0 ok=44 ko=3 errors=401:1,500:2"
+        );
+        assert!(!line.contains(['\x1B', '\r', '\n']));
+    }
+
+    #[test]
+    fn given_json_lines_format_when_formatting_snapshot_then_returns_json_object() {
+        let sink = ConsoleReportingSink::new(ConsoleFormat::JsonLines);
+        let fields = ConsoleReportingSink::step_fields("step_ended", &step_status());
+
+        let line = sink.format_record(TIMESTAMP, fields);
+        let record: Value = serde_json::from_str(&line).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(record["timestamp"], TIMESTAMP);
+        assert_eq!(record["event"], "step_ended");
+        assert_eq!(record["stage"], "second wave");
+        assert_eq!(record["rps"], 6.29);
+        assert_eq!(record["p95_ms"], 191);
+        assert_eq!(record["errors"]["500"], 2);
+    }
+
+    #[test]
+    fn given_snapshot_without_errors_when_formatting_line_then_omits_errors() {
+        let sink = ConsoleReportingSink::default();
+        let mut step_status = step_status();
+        step_status.metrics.errors.clear();
+
+        let line = sink.format_record(
+            TIMESTAMP,
+            ConsoleReportingSink::step_fields("snapshot", &step_status),
+        );
+
+        assert!(!line.contains("errors="));
     }
 }
@@ -1,62 +1,142 @@
+use crate::core::cancellation::Cancellation;
 use crate::core::composition::TestCase;
 use crate::core::context::TestContext;
-use crate::core::exporting::FileType;
-use crate::core::reporting::{DefaultReportingSink, Reporter, ReportingSink};
-use crate::core::stats::TestStatus;
+use crate::core::delivery::{SharedSink, SinkDispatcher, SinkOptions};
+use crate::core::exporting::{FileType, Localization};
+use crate::core::reporting::{
+    ConsoleFormat, ConsoleReportingSink, DefaultReportingSink, Reporter, ReportingSink,
+};
+use crate::core::stats::{CaseFailure, SuiteStatus, TestStatus};
+use crate::core::suite::{SuiteCase, SuiteMode, TestSuite};
 use std::fmt::Debug;
 use std::marker::Sync;
 use std::sync::Arc;
-use tokio::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
 
 #[derive(Default)]
 pub struct TestRunner {
     reporter: Reporter,
-    sinks: Vec<Arc<Box<dyn ReportingSink>>>,
+    sinks: Vec<(SharedSink, Option<SinkOptions>)>,
+    sink_options: SinkOptions,
+    use_default_reporting_sink: bool,
+    console_format: Option<ConsoleFormat>,
+    cancellation: Cancellation,
 }
 
-impl<'a> TestRunner {
-    pub async fn run<T, U>(
-        &self,
-        mut test_case: TestCase<'static, T, U>,
-    ) -> Result<TestStatus, &str>
+impl TestRunner {
+    pub async fn run<T, U>(&self, test_case: TestCase<'static, T, U>) -> Result<TestStatus, &str>
     where
         T: TestContext + 'static + Sync + Debug,
         U: 'static + Clone + Sync + Send,
     {
-        let sinks = Arc::new(self.sinks.to_owned());
-        let (action_handle, action_sender) = self.reporter.handle_action_ended(&sinks);
-        let (step_handle, load_sender) = self.reporter.handle_load_step_ended(&sinks);
-        let (internal_handle, internal_sender) = self.reporter.handle_internal_events(&sinks);
+        let dispatcher = self.start_dispatcher();
+        let handle = Box::new(test_case).start(&self.reporter, &dispatcher, &self.cancellation);
+        let result = Self::wait_test_case(handle).await;
 
-        test_case
-            .run(&action_sender, &load_sender, &internal_sender)
-            .await?;
+        let test_status = match result {
+            Ok(test_status) => test_status,
+            Err(error) => {
+                dispatcher.shutdown().await;
+                return Err(error);
+            }
+        };
 
-        drop(action_sender);
-        drop(load_sender);
-        drop(internal_sender);
+        match self
+            .reporter
+            .report_test_status(dispatcher, test_status)
+            .await
+        {
+            Ok(status) => Ok(status),
+            Err(_) => Err("An error occurred while exporting the test status"),
+        }
+    }
+
+    /// Runs every test case of the suite, one after another or all at once depending on the
+    /// suite mode, and reports the aggregated status. A failing test case fails the suite
+    /// without stopping the other test cases.
+    pub async fn run_suite(&self, suite: TestSuite) -> Result<SuiteStatus, &str> {
+        if suite.is_empty() {
+            return Err("No test cases have found!");
+        }
 
-        _ = action_handle.await;
-        _ = step_handle.await;
-        _ = internal_handle.await;
+        let dispatcher = self.start_dispatcher();
+        let suite_name = suite.suite_name.to_string();
+        let mode = suite.mode;
+        let start_time = Instant::now();
+        let mut test_status = Vec::new();
+        let mut failures = Vec::new();
+        let mut handles = Vec::new();
 
-        let ctx = test_case.test_context.clone().unwrap_or(T::default());
-        let test_status = self.reporter.report_test_status(sinks, ctx).await;
+        for test_case in suite.into_test_cases() {
+            let test_name = test_case.test_name();
+            let handle = test_case.start(&self.reporter, &dispatcher, &self.cancellation);
+
+            match mode {
+                SuiteMode::Sequential => {
+                    let result = Self::wait_test_case(handle).await;
+                    Self::collect_result(test_name, result, &mut test_status, &mut failures);
+                }
+                SuiteMode::Concurrent => handles.push((test_name, handle)),
+            }
+        }
+
+        for (test_name, handle) in handles {
+            let result = Self::wait_test_case(handle).await;
+            Self::collect_result(test_name, result, &mut test_status, &mut failures);
+        }
+
+        let suite_status = SuiteStatus::new(
+            suite_name,
+            start_time.elapsed().as_millis(),
+            test_status,
+            failures,
+        );
 
-        match test_status {
+        match self
+            .reporter
+            .report_suite_status(dispatcher, suite_status)
+            .await
+        {
             Ok(status) => Ok(status),
-            Err(_) => Err("An error occurred while exporting the test status"),
+            Err(_) => Err("An error occurred while exporting the suite status"),
         }
     }
 
+    /// Returns the handle that stops the running test cases early. Once cancelled, the runner
+    /// stops every later test case at once too.
+    pub fn cancellation(&self) -> Cancellation {
+        self.cancellation.to_owned()
+    }
+
     pub fn with_default_reporting_sink(mut self) -> Self {
-        self.sinks
-            .push(Arc::new(Box::<DefaultReportingSink>::default()));
+        self.use_default_reporting_sink = true;
+        self
+    }
+
+    /// Prints one line per event without control codes, instead of the default reporting sink.
+    pub fn with_console_reporting_sink(mut self, format: ConsoleFormat) -> Self {
+        self.console_format = Some(format);
+        self.reporter.use_plain_output = true;
         self
     }
 
     pub fn with_reporting_sink<T: ReportingSink + 'static>(mut self, sink: T) -> Self {
-        self.sinks.push(Arc::new(Box::new(sink)));
+        self.sinks.push((Arc::new(Box::new(sink)), None));
+        self
+    }
+
+    pub fn with_reporting_sink_options<T: ReportingSink + 'static>(
+        mut self,
+        sink: T,
+        options: SinkOptions,
+    ) -> Self {
+        self.sinks.push((Arc::new(Box::new(sink)), Some(options)));
+        self
+    }
+
+    pub fn with_default_sink_options(mut self, options: SinkOptions) -> Self {
+        self.sink_options = options;
         self
     }
 
@@ -79,6 +159,16 @@ impl<'a> TestRunner {
         self
     }
 
+    pub fn with_git_sha_variable(mut self, variable_name: &str) -> Self {
+        self.reporter.exporter.git_sha_variable = Some(variable_name.to_string());
+        self
+    }
+
+    pub fn with_localization(mut self, localization: Localization) -> Self {
+        self.reporter.exporter.localization = localization;
+        self
+    }
+
     pub fn with_test_summary_std_out(mut self) -> Self {
         self.reporter.use_summary = true;
         self
@@ -95,4 +185,56 @@ impl<'a> TestRunner {
         self.reporter.reporting_frequency = Duration::from_secs(seconds);
         self
     }
+
+    async fn wait_test_case(
+        handle: JoinHandle<Result<TestStatus, &'static str>>,
+    ) -> Result<TestStatus, &'static str> {
+        match handle.await {
+            Ok(result) => result,
+            Err(_) => Err("The test case has panicked"),
+        }
+    }
+
+    fn collect_result(
+        test_name: &str,
+        result: Result<TestStatus, &str>,
+        test_status: &mut Vec<TestStatus>,
+        failures: &mut Vec<CaseFailure>,
+    ) {
+        match result {
+            Ok(status) => test_status.push(status),
+            Err(reason) => failures.push(CaseFailure {
+                test_name: test_name.to_string(),
+                reason: reason.to_string(),
+            }),
+        }
+    }
+
+    fn start_dispatcher(&self) -> Arc<SinkDispatcher> {
+        let sinks = self.get_sinks();
+        for (sink, _) in &sinks {
+            sink.on_attached(self.cancellation.to_owned());
+        }
+
+        Arc::new(SinkDispatcher::start(sinks))
+    }
+
+    fn get_sinks(&self) -> Vec<(SharedSink, SinkOptions)> {
+        let mut sinks = self
+            .sinks
+            .iter()
+            .map(|(sink, options)| (Arc::clone(sink), options.unwrap_or(self.sink_options)))
+            .collect::<Vec<(SharedSink, SinkOptions)>>();
+
+        if let Some(format) = self.console_format {
+            let sink: SharedSink = Arc::new(Box::new(ConsoleReportingSink::new(format)));
+            sinks.insert(0, (sink, self.sink_options));
+        } else if self.use_default_reporting_sink {
+            let localization = self.reporter.exporter.localization.to_owned();
+            let sink: SharedSink = Arc::new(Box::new(DefaultReportingSink::new(localization)));
+            sinks.insert(0, (sink, self.sink_options));
+        }
+
+        sinks
+    }
 }
@@ -1,8 +1,9 @@
 use crate::core::context::TestContext;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Metrics {
     pub test_duration: u128,
     pub load_duration: u128,
@@ -17,40 +18,397 @@ pub struct Metrics {
     pub negative_hits: u128,
     pub all_hits: u128,
     pub request_per_sec: f64,
+    pub in_flight: u128,
     pub errors: HashMap<i32, u128>,
+    pub latency_histogram: LatencyHistogram,
+    #[serde(default)]
+    pub transactions: Vec<TransactionStatus>,
+    /// Time from the scheduled start of every hit until it ended, which accounts for the hits
+    /// that started late and so is corrected for coordinated omission. The other times only
+    /// measure the service time, from the actual start of every hit.
+    #[serde(default)]
+    pub response_time: TimingSummary,
+    /// How far behind the pacing schedule the hits started.
+    #[serde(default)]
+    pub schedule_lag: TimingSummary,
+    #[serde(default)]
+    pub generator: GeneratorHealth,
 }
 
-#[derive(Clone, Debug, Serialize)]
+/// Health of the load generator itself, which tells whether the results describe the target or
+/// a saturated generator.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GeneratorHealth {
+    /// Time between the scheduled and the actual spawn of the hits.
+    pub spawn_lag: TimingSummary,
+    pub delayed_hits: u128,
+    /// Hits that were scheduled but never spawned, as the stage ended first.
+    pub dropped_hits: u128,
+    /// Lifecycle events the generator discarded, as the reporting pipeline was full.
+    #[serde(default)]
+    pub dropped_events: u128,
+    pub max_in_flight: u128,
+    /// CPU usage of the process since the test started, in percent of a core.
+    pub cpu_usage: Option<f64>,
+    /// Resident memory of the process.
+    pub rss_bytes: Option<u64>,
+    pub warnings: Vec<String>,
+}
+
+/// Summary of a timing in milliseconds.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TimingSummary {
+    pub count: u128,
+    pub mean: u128,
+    pub p90: u128,
+    pub p95: u128,
+    pub p99: u128,
+    pub max: u128,
+}
+
+/// Fixed-bucket recording of a timing in milliseconds, which summarizes it without keeping
+/// every sample.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TimingRecorder {
+    histogram: LatencyHistogram,
+    max: u128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionStatus {
+    pub transaction_name: String,
+    pub metrics: Metrics,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<LatencyBucket>,
+    pub sum: u128,
+    pub count: u128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub le: u128,
+    pub count: u128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StepStatus {
     pub session_id: String,
     pub test_name: String,
+    pub test_suite: String,
     pub step_name: String,
+    pub stage_name: String,
     pub metrics: Metrics,
+    #[serde(default)]
+    pub actions: Vec<ActionStatus>,
+    #[serde(default)]
+    pub custom_metrics: CustomMetrics,
 }
 
-#[derive(Clone, Debug, Serialize)]
+/// Snapshot of the counters, gauges and value histograms recorded into the metrics registry
+/// since the test started.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CustomMetrics {
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, f64>,
+    pub histograms: BTreeMap<String, ValueSummary>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ValueSummary {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActionStatus {
+    pub action_name: String,
+    pub metrics: Metrics,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TestStatus {
     pub session_id: String,
     pub test_name: String,
+    pub test_suite: String,
     pub metrics: Metrics,
+    #[serde(default)]
+    pub sinks: Vec<SinkReport>,
+}
+
+/// Report of a suite run, aggregating the status of every test case.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SuiteStatus {
+    pub session_id: String,
+    pub suite_name: String,
+    pub passed: bool,
+    pub duration: u128,
+    pub all_hits: u128,
+    pub positive_hits: u128,
+    pub negative_hits: u128,
+    pub test_status: Vec<TestStatus>,
+    pub failures: Vec<CaseFailure>,
+    #[serde(default)]
+    pub sinks: Vec<SinkReport>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CaseFailure {
+    pub test_name: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SinkReport {
+    pub sink_name: String,
+    pub delivered: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    pub coalesced: u64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepKind {
+    Init,
+    WarmUp,
+    Load,
+    CleanUp,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepOutcome {
+    Succeeded,
+    Failed(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StagePlan {
+    pub stage_name: String,
+    pub during: u128,
+    pub interval: u128,
+    pub rate: u32,
+    /// Distribution of the arrival times of the hits within every interval.
+    #[serde(default)]
+    pub arrivals: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepPlan {
+    pub step_name: String,
+    pub kind: StepKind,
+    pub stages: Vec<StagePlan>,
+    /// Concurrent group of the step, whose steps run at the same time.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TestPlan {
+    pub session_id: String,
+    pub test_name: String,
+    pub test_suite: String,
+    pub started_at: String,
+    pub steps: Vec<StepPlan>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepStartedStatus {
+    pub session_id: String,
+    pub test_name: String,
+    pub test_suite: String,
+    pub step: StepPlan,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageStatus {
+    pub session_id: String,
+    pub test_name: String,
+    pub test_suite: String,
+    pub step_name: String,
+    pub stage: StagePlan,
+    pub elapsed: u128,
+}
+
+/// Raised the first time a step crosses one of the generator health thresholds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GeneratorWarningStatus {
+    pub session_id: String,
+    pub test_name: String,
+    pub test_suite: String,
+    pub step_name: String,
+    pub threshold: String,
+    pub warning: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InternalStepStatus {
+    pub session_id: String,
+    pub test_name: String,
+    pub test_suite: String,
+    pub step_name: String,
+    pub kind: StepKind,
+    pub duration: u128,
+    pub outcome: StepOutcome,
+}
+
+impl StageStatus {
+    pub fn new(step: &StepStartedStatus, stage: StagePlan, elapsed: u128) -> Self {
+        StageStatus {
+            session_id: step.session_id.to_owned(),
+            test_name: step.test_name.to_owned(),
+            test_suite: step.test_suite.to_owned(),
+            step_name: step.step.step_name.to_owned(),
+            stage,
+            elapsed,
+        }
+    }
+}
+
+impl GeneratorWarningStatus {
+    pub fn new(step: &StepStartedStatus, threshold: &str, warning: String) -> Self {
+        GeneratorWarningStatus {
+            session_id: step.session_id.to_owned(),
+            test_name: step.test_name.to_owned(),
+            test_suite: step.test_suite.to_owned(),
+            step_name: step.step.step_name.to_owned(),
+            threshold: threshold.to_owned(),
+            warning,
+        }
+    }
+}
+
+impl InternalStepStatus {
+    pub fn new(step: &StepStartedStatus, duration: u128, outcome: StepOutcome) -> Self {
+        InternalStepStatus {
+            session_id: step.session_id.to_owned(),
+            test_name: step.test_name.to_owned(),
+            test_suite: step.test_suite.to_owned(),
+            step_name: step.step.step_name.to_owned(),
+            kind: step.step.kind,
+            duration,
+            outcome,
+        }
+    }
 }
 
 impl TestStatus {
     pub fn new(test_name: String, test_context: impl TestContext) -> Self {
         TestStatus {
             test_name,
+            test_suite: test_context.get_test_suite(),
             session_id: test_context.get_session_id(),
             metrics: Metrics::new(test_context),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl SuiteStatus {
+    /// Aggregates the status of the test cases, where the suite passes when every test case has
+    /// run to completion.
+    pub fn new(
+        suite_name: String,
+        duration: u128,
+        test_status: Vec<TestStatus>,
+        failures: Vec<CaseFailure>,
+    ) -> Self {
+        SuiteStatus {
+            session_id: Uuid::new_v4().to_string(),
+            suite_name,
+            passed: failures.is_empty(),
+            duration,
+            all_hits: test_status
+                .iter()
+                .map(|status| status.metrics.all_hits)
+                .sum(),
+            positive_hits: test_status
+                .iter()
+                .map(|status| status.metrics.positive_hits)
+                .sum(),
+            negative_hits: test_status
+                .iter()
+                .map(|status| status.metrics.negative_hits)
+                .sum(),
+            test_status,
+            failures,
+            sinks: Vec::new(),
         }
     }
 }
 
+impl TimingRecorder {
+    const P90: f64 = 0.9;
+    const P95: f64 = 0.95;
+    const P99: f64 = 0.99;
+
+    pub(crate) fn add(&mut self, time: u128) {
+        self.histogram.add(time);
+        self.max = self.max.max(time);
+    }
+
+    /// Summarizes the recorded timings, where the percentiles are interpolated within their
+    /// bucket and capped by the highest timing.
+    pub(crate) fn summary(&self) -> TimingSummary {
+        let count = self.histogram.count;
+        if count == 0 {
+            return TimingSummary::default();
+        }
+
+        TimingSummary {
+            count,
+            mean: self.histogram.sum / count,
+            p90: self.percentile(Self::P90),
+            p95: self.percentile(Self::P95),
+            p99: self.percentile(Self::P99),
+            max: self.max,
+        }
+    }
+
+    fn percentile(&self, percentile: f64) -> u128 {
+        let rank = (percentile * self.histogram.count as f64).ceil() as u128;
+        let mut lower = (0u128, 0u128);
+
+        for bucket in &self.histogram.buckets {
+            if bucket.count >= rank {
+                let (lower_le, lower_count) = lower;
+                let share = (rank - lower_count) as f64 / (bucket.count - lower_count) as f64;
+                let value = lower_le as f64 + share * (bucket.le - lower_le) as f64;
+                return (value.round() as u128).min(self.max);
+            }
+            lower = (bucket.le, bucket.count);
+        }
+
+        self.max
+    }
+}
+
 impl StepStatus {
     pub fn new(test_name: String, test_context: impl TestContext) -> Self {
         StepStatus {
             test_name,
+            test_suite: test_context.get_test_suite(),
             session_id: test_context.get_session_id(),
             step_name: test_context.get_current_step_name(),
+            stage_name: test_context.get_current_stage_name(),
+            actions: test_context
+                .get_current_actions()
+                .into_iter()
+                .map(|(action_name, ctx)| ActionStatus {
+                    action_name,
+                    metrics: Metrics::new(ctx),
+                })
+                .collect(),
+            custom_metrics: test_context.get_current_custom_metrics(),
             metrics: Metrics::new(test_context),
         }
     }
@@ -76,7 +434,20 @@ impl Metrics {
             p90_time: test_context.get_current_percentile_time(Self::P90),
             p95_time: test_context.get_current_percentile_time(Self::P95),
             p99_time: test_context.get_current_percentile_time(Self::P99),
+            in_flight: test_context.get_current_in_flight(),
             errors: test_context.get_current_errors(),
+            latency_histogram: test_context.get_current_latency_histogram(),
+            transactions: test_context
+                .get_current_transactions()
+                .into_iter()
+                .map(|(transaction_name, ctx)| TransactionStatus {
+                    transaction_name,
+                    metrics: Metrics::new(ctx),
+                })
+                .collect(),
+            response_time: test_context.get_current_response_time(),
+            schedule_lag: test_context.get_current_schedule_lag(),
+            generator: test_context.get_current_generator_health(),
         };
 
         metrics.all_hits = metrics.positive_hits + metrics.negative_hits;
@@ -90,3 +461,30 @@ impl Metrics {
         metrics
     }
 }
+
+impl LatencyHistogram {
+    pub const DEFAULT_BOUNDS: [u128; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+    pub fn add(&mut self, time: u128) {
+        self.buckets
+            .iter_mut()
+            .filter(|bucket| time <= bucket.le)
+            .for_each(|bucket| bucket.count += 1);
+
+        self.sum += time;
+        self.count += 1;
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: Self::DEFAULT_BOUNDS
+                .iter()
+                .map(|le| LatencyBucket { le: *le, count: 0 })
+                .collect(),
+            sum: 0,
+            count: 0,
+        }
+    }
+}
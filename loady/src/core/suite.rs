@@ -0,0 +1,136 @@
+use crate::core::cancellation::Cancellation;
+use crate::core::composition::TestCase;
+use crate::core::context::TestContext;
+use crate::core::delivery::SinkDispatcher;
+use crate::core::reporting::Reporter;
+use crate::core::stats::TestStatus;
+use std::fmt::Debug;
+use std::marker::Sync;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SuiteMode {
+    Sequential,
+    Concurrent,
+}
+
+/// Group of test cases run by the same runner, sharing its sinks and reported together.
+pub struct TestSuite {
+    pub suite_name: &'static str,
+    pub mode: SuiteMode,
+    test_cases: Vec<Box<dyn SuiteCase>>,
+}
+
+pub(crate) trait SuiteCase: Send {
+    fn test_name(&self) -> &'static str;
+
+    fn start(
+        self: Box<Self>,
+        reporter: &Reporter,
+        dispatcher: &Arc<SinkDispatcher>,
+        cancellation: &Cancellation,
+    ) -> JoinHandle<Result<TestStatus, &'static str>>;
+}
+
+impl TestSuite {
+    pub fn new(suite_name: &'static str, mode: SuiteMode) -> Self {
+        TestSuite {
+            suite_name,
+            mode,
+            test_cases: Vec::new(),
+        }
+    }
+
+    pub fn with_test_case<T, U>(mut self, test_case: TestCase<'static, T, U>) -> Self
+    where
+        T: TestContext + 'static + Sync + Debug,
+        U: 'static + Clone + Sync + Send,
+    {
+        self.test_cases.push(Box::new(test_case));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.test_cases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.test_cases.is_empty()
+    }
+
+    pub(crate) fn into_test_cases(self) -> Vec<Box<dyn SuiteCase>> {
+        self.test_cases
+    }
+}
+
+impl<T, U> SuiteCase for TestCase<'static, T, U>
+where
+    T: TestContext + 'static + Sync + Debug,
+    U: 'static + Clone + Sync + Send,
+{
+    fn test_name(&self) -> &'static str {
+        self.test_name
+    }
+
+    fn start(
+        mut self: Box<Self>,
+        reporter: &Reporter,
+        dispatcher: &Arc<SinkDispatcher>,
+        cancellation: &Cancellation,
+    ) -> JoinHandle<Result<TestStatus, &'static str>> {
+        self.cancellation = cancellation.to_owned();
+        let dispatcher = Arc::clone(dispatcher);
+        let (action_handle, action_sender) = reporter.handle_action_ended(&dispatcher);
+        let (step_handle, load_sender) = reporter.handle_load_step_ended(&dispatcher);
+        let (lifecycle_handle, lifecycle_sender) = reporter.handle_lifecycle_events(&dispatcher);
+
+        tokio::spawn(async move {
+            let result = self
+                .run(&action_sender, &load_sender, &lifecycle_sender)
+                .await;
+
+            drop(action_sender);
+            drop(load_sender);
+            drop(lifecycle_sender);
+
+            _ = action_handle.await;
+            _ = step_handle.await;
+            _ = lifecycle_handle.await;
+
+            result?;
+
+            let ctx = self.test_context.clone().unwrap_or_default();
+            Ok(Reporter::publish_test_status(&dispatcher, ctx).await)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::context::TestCaseContext;
+
+    #[test]
+    fn given_test_cases_when_building_suite_then_keeps_them_in_order() {
+        let suite = TestSuite::new("nightly", SuiteMode::Sequential)
+            .with_test_case(TestCase::<TestCaseContext, ()>::new("login", "nightly", ()))
+            .with_test_case(TestCase::<TestCaseContext, ()>::new(
+                "checkout",
+                "nightly",
+                (),
+            ));
+
+        assert_eq!(suite.len(), 2);
+        assert_eq!(suite.mode, SuiteMode::Sequential);
+
+        let names = suite
+            .into_test_cases()
+            .iter()
+            .map(|test_case| test_case.test_name())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(names, vec!["login", "checkout"]);
+    }
+}
@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::time::{Duration, Instant};
+
+/// Handle passed into a load action to record named sub-transactions, each one with its own
+/// timing and outcome. The hit itself is still recorded with the total latency.
+#[derive(Clone, Default)]
+pub struct Transactions {
+    records: Arc<Mutex<Vec<TransactionRecord>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionRecord {
+    pub name: &'static str,
+    pub result: Result<(), i32>,
+    pub duration: Duration,
+}
+
+impl Transactions {
+    pub fn record(&self, name: &'static str, result: Result<(), i32>, duration: Duration) {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(TransactionRecord {
+                name,
+                result,
+                duration,
+            });
+    }
+
+    /// Awaits the future and records its elapsed time and outcome under the given name, handing
+    /// its output back to the action.
+    pub async fn measure<F, R>(&self, name: &'static str, future: F) -> Result<R, i32>
+    where
+        F: Future<Output = Result<R, i32>>,
+    {
+        let start_time = Instant::now();
+        let result = future.await;
+        let outcome = result.as_ref().map(|_| ()).map_err(|code| *code);
+        self.record(name, outcome, start_time.elapsed());
+        result
+    }
+
+    pub(crate) fn take(&self) -> Vec<TransactionRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[tokio::test]
+    async fn given_measured_futures_when_taking_records_then_returns_outcomes_in_order() {
+        let transactions = Transactions::default();
+
+        let token = transactions
+            .measure("login", async { Ok::<_, i32>("token") })
+            .await;
+        let list = transactions
+            .measure("list", async { Err::<(), i32>(503) })
+            .await;
+        let records = transactions.take();
+
+        assert_eq!(token, Ok("token"));
+        assert_eq!(list, Err(503));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].name, "login");
+        assert_eq!(records[0].result, Ok(()));
+        assert_eq!(records[1].result, Err(503));
+        assert!(transactions.take().is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::core::functions::{LoadResult, UserHitFunction, UserInitFunction, UserLoadFunction};
+use crate::core::transactions::Transactions;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::time::Instant;
+
+/// Name of the sub-transaction every user initialisation is recorded as.
+pub const USER_INIT: &str = "user init";
+
+/// The state owned by a single virtual user, handed to every action it runs.
+#[derive(Debug)]
+pub struct VirtualUser<V> {
+    /// Index of the user within its load step, starting at zero.
+    pub index: usize,
+    /// Number of actions the user has already run, starting at zero.
+    pub iteration: u64,
+    pub state: V,
+}
+
+/// Keeps the idle virtual users of a load step, creating new ones when every user is busy.
+struct UserPool<V> {
+    idle: Vec<VirtualUser<V>>,
+    created: usize,
+}
+
+enum Checkout<V> {
+    Idle(VirtualUser<V>),
+    New(usize),
+}
+
+/// A user checked out of the pool, which is returned once dropped. This way the user goes back
+/// to the pool, as it is, even when the hit running it is cancelled.
+struct CheckedOutUser<V> {
+    user: Option<VirtualUser<V>>,
+    pool: Arc<Mutex<UserPool<V>>>,
+}
+
+impl<V> VirtualUser<V> {
+    pub fn new(index: usize, state: V) -> Self {
+        Self {
+            index,
+            iteration: 0,
+            state,
+        }
+    }
+}
+
+impl<V> UserPool<V> {
+    fn new() -> Self {
+        Self {
+            idle: Vec::new(),
+            created: 0,
+        }
+    }
+
+    fn checkout(&mut self) -> Checkout<V> {
+        match self.idle.pop() {
+            Some(user) => Checkout::Idle(user),
+            None => {
+                self.created += 1;
+                Checkout::New(self.created - 1)
+            }
+        }
+    }
+
+    fn release(&mut self, user: VirtualUser<V>) {
+        self.idle.push(user);
+    }
+}
+
+impl<V> Deref for CheckedOutUser<V> {
+    type Target = VirtualUser<V>;
+
+    fn deref(&self) -> &Self::Target {
+        self.user
+            .as_ref()
+            .expect("The user is only taken when dropped")
+    }
+}
+
+impl<V> DerefMut for CheckedOutUser<V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.user
+            .as_mut()
+            .expect("The user is only taken when dropped")
+    }
+}
+
+impl<V> Drop for CheckedOutUser<V> {
+    fn drop(&mut self) {
+        if let Some(user) = self.user.take() {
+            self.pool
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .release(user);
+        }
+    }
+}
+
+/// Wraps a per-user action into a hit function backed by a pool of virtual users, which checks
+/// out a user and returns the hit it runs.
+///
+/// A user is initialised the first time no idle user is available, so the pool grows up to the
+/// peak concurrency of the step. The initialisation is recorded as a sub-transaction apart from
+/// the hit. A failed initialisation returns no hit and the user index is not reused. A user is
+/// returned to the pool once its hit ends or is dropped.
+pub(crate) fn into_user_hit_function<'a, T, V>(
+    init: UserInitFunction<'a, T, V>,
+    action: UserLoadFunction<'a, T, V>,
+) -> UserHitFunction<'a, T>
+where
+    T: Send + Sync + 'a,
+    V: Send + Sync + 'a,
+{
+    let pool = Arc::new(Mutex::new(UserPool::<V>::new()));
+    let init = Arc::new(init);
+    let action = Arc::new(action);
+
+    Box::new(move |data: Arc<T>, transactions: Transactions| {
+        let pool = Arc::clone(&pool);
+        let init = Arc::clone(&init);
+        let action = Arc::clone(&action);
+
+        Box::pin(async move {
+            let checkout = pool
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .checkout();
+            let user = match checkout {
+                Checkout::Idle(user) => user,
+                Checkout::New(index) => {
+                    let start_time = Instant::now();
+                    let state = init(Arc::clone(&data), index).await;
+                    let outcome = state.as_ref().map(|_| ()).map_err(|code| *code);
+                    transactions.record(USER_INIT, outcome, start_time.elapsed());
+                    VirtualUser::new(index, state.ok()?)
+                }
+            };
+            let mut user = CheckedOutUser {
+                user: Some(user),
+                pool,
+            };
+
+            let hit: LoadResult<'a> = Box::pin(async move {
+                let result = action(data, &mut user).await;
+                user.iteration += 1;
+                result
+            });
+            Some(hit)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::core::functions::UserInitResult;
+    use tokio::time::{sleep, Duration};
+
+    #[derive(Default)]
+    struct Data {
+        calls: Mutex<Vec<(usize, u64, u32)>>,
+    }
+
+    fn init(_data: Arc<Data>, index: usize) -> UserInitResult<'static, u32> {
+        Box::pin(async move { Ok(index as u32 * 100) })
+    }
+
+    fn failing_init(_data: Arc<Data>, index: usize) -> UserInitResult<'static, u32> {
+        Box::pin(async move {
+            match index {
+                0 => Err(503),
+                _ => Ok(0),
+            }
+        })
+    }
+
+    fn load(data: Arc<Data>, user: &mut VirtualUser<u32>) -> LoadResult<'_> {
+        Box::pin(async move {
+            user.state += 1;
+            sleep(Duration::from_millis(10)).await;
+            data.calls
+                .lock()
+                .unwrap()
+                .push((user.index, user.iteration, user.state));
+            Ok(())
+        })
+    }
+
+    async fn run(
+        action: &UserHitFunction<'static, Data>,
+        data: &Arc<Data>,
+        transactions: &Transactions,
+    ) -> Option<Result<(), i32>> {
+        let hit = action(Arc::clone(data), Transactions::clone(transactions)).await?;
+        Some(hit.await)
+    }
+
+    #[tokio::test]
+    async fn given_sequential_actions_when_running_then_reuses_user_state() {
+        let data = Arc::new(Data::default());
+        let transactions = Transactions::default();
+        let action = into_user_hit_function(Box::new(init), Box::new(load));
+
+        for _ in 0..3 {
+            run(&action, &data, &transactions).await.unwrap().unwrap();
+        }
+
+        assert_eq!(
+            *data.calls.lock().unwrap(),
+            vec![(0, 0, 1), (0, 1, 2), (0, 2, 3)]
+        );
+        assert_eq!(transactions.take().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_concurrent_actions_when_running_then_creates_one_user_each() {
+        let data = Arc::new(Data::default());
+        let transactions = Transactions::default();
+        let action = into_user_hit_function(Box::new(init), Box::new(load));
+
+        _ = tokio::join!(
+            run(&action, &data, &transactions),
+            run(&action, &data, &transactions),
+            run(&action, &data, &transactions)
+        );
+
+        let mut calls = data.calls.lock().unwrap().to_owned();
+        calls.sort();
+        assert_eq!(calls, vec![(0, 0, 1), (1, 0, 101), (2, 0, 201)]);
+    }
+
+    #[tokio::test]
+    async fn given_cancelled_action_when_running_next_then_reuses_its_user() {
+        let data = Arc::new(Data::default());
+        let transactions = Transactions::default();
+        let action = into_user_hit_function(Box::new(init), Box::new(load));
+
+        let cancelled =
+            tokio::time::timeout(Duration::from_millis(2), run(&action, &data, &transactions))
+                .await;
+        run(&action, &data, &transactions).await.unwrap().unwrap();
+
+        assert!(cancelled.is_err());
+        assert_eq!(*data.calls.lock().unwrap(), vec![(0, 0, 2)]);
+        assert_eq!(transactions.take().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn given_failing_user_init_when_running_then_reports_init_and_creates_next_user() {
+        let data = Arc::new(Data::default());
+        let transactions = Transactions::default();
+        let action = into_user_hit_function(Box::new(failing_init), Box::new(load));
+
+        let first = run(&action, &data, &transactions).await;
+        let second = run(&action, &data, &transactions).await;
+        let records = transactions.take();
+
+        assert_eq!(first, None);
+        assert_eq!(second, Some(Ok(())));
+        assert_eq!(*data.calls.lock().unwrap(), vec![(1, 0, 1)]);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].name, USER_INIT);
+        assert_eq!(records[0].result, Err(503));
+        assert_eq!(records[1].result, Ok(()));
+    }
+}
@@ -1,6 +1,9 @@
-use crate::core::composition::{TestCase, TestStep, TestStepStage};
+use crate::core::arrivals::Arrivals;
+use crate::core::composition::{TestCase, TestStep, TestStepStage, WeightedAction};
 use crate::core::context::TestCaseContext;
+use crate::core::feeders::Feeder;
 use crate::core::functions::*;
+use crate::core::registry::MetricsRegistry;
 use std::fmt::Debug;
 use std::marker::Sync;
 use tokio::time::Duration;
@@ -40,12 +43,72 @@ where
         self
     }
 
+    pub fn with_feed_load_step(
+        mut self,
+        name: &'static str,
+        feeder: Feeder,
+        action: FeedLoadFunction<'static, T>,
+    ) -> Self {
+        let step = TestStep::as_feed_load(name, feeder, action, Vec::default());
+        self.test_case.with_step(step);
+        self
+    }
+
+    pub fn with_transaction_load_step(
+        mut self,
+        name: &'static str,
+        action: TransactionLoadFunction<'static, T>,
+    ) -> Self {
+        let step = TestStep::as_transaction_load(name, action, Vec::default());
+        self.test_case.with_step(step);
+        self
+    }
+
+    pub fn with_mixed_load_step(
+        mut self,
+        name: &'static str,
+        actions: Vec<WeightedAction<'static, T>>,
+    ) -> Self {
+        let step = TestStep::as_mixed_load(name, actions, Vec::default());
+        self.test_case.with_step(step);
+        self
+    }
+
+    pub fn with_user_load_step<V>(
+        mut self,
+        name: &'static str,
+        init: UserInitFunction<'static, T, V>,
+        action: UserLoadFunction<'static, T, V>,
+    ) -> Self
+    where
+        V: 'static + Send + Sync,
+    {
+        let step = TestStep::as_user_load(name, init, action, Vec::default());
+        self.test_case.with_step(step);
+        self
+    }
+
     pub fn with_clean_up_step(mut self, action: CleanUpFunction<'static, T>) -> Self {
         let step = TestStep::as_clean_up(action);
         self.test_case.with_step(step);
         self
     }
 
+    pub fn with_metrics_registry(mut self, registry: &MetricsRegistry) -> Self {
+        self.test_case.with_metrics_registry(registry.to_owned());
+        self
+    }
+
+    /// Adds the last load step to a concurrent group. Consecutive load steps of the same group
+    /// run at the same time.
+    pub fn with_concurrent_group(mut self, group: &'static str) -> Self {
+        match self.test_case.test_steps.last_mut() {
+            Some(TestStep::Load { group: current, .. }) => *current = Some(group),
+            _ => panic!("Only 'Load' step types can run concurrently"),
+        };
+        self
+    }
+
     pub fn with_stage(
         mut self,
         stage_name: &'static str,
@@ -65,6 +128,20 @@ where
         self
     }
 
+    /// Spreads the hits of the last stage following the given arrivals.
+    pub fn with_arrivals(mut self, arrivals: Arrivals) -> Self {
+        match self.test_case.test_steps.last_mut() {
+            Some(TestStep::Load { stages, .. }) => {
+                let stage = stages
+                    .last_mut()
+                    .expect("A stage must be added before its arrivals");
+                *stage = stage.to_owned().with_arrivals(arrivals);
+            }
+            _ => panic!("Only 'Load' step types can use arrivals"),
+        };
+        self
+    }
+
     pub fn build(self) -> TestCase<'static, TestCaseContext<'static>, T> {
         self.test_case
     }
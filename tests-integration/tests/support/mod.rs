@@ -1,4 +1,6 @@
 use loady::core::functions::*;
+use loady::core::transactions::Transactions;
+use loady::core::users::VirtualUser;
 use rand::prelude::SliceRandom;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -53,6 +55,27 @@ pub fn load(_ctx: Arc<EmptyData>) -> LoadResult<'static> {
     })
 }
 
+#[allow(dead_code)]
+pub fn init_user(_ctx: Arc<EmptyData>, index: usize) -> UserInitResult<'static, usize> {
+    Box::pin(async move { Ok(index) })
+}
+
+#[allow(dead_code)]
+pub fn user_load(_ctx: Arc<EmptyData>, user: &mut VirtualUser<usize>) -> LoadResult<'_> {
+    Box::pin(async move {
+        user.state += 1;
+        Ok(())
+    })
+}
+
+#[allow(dead_code)]
+pub fn journey(_ctx: Arc<EmptyData>, transactions: Transactions) -> LoadResult<'static> {
+    Box::pin(async move {
+        transactions.measure("login", async { Ok(()) }).await?;
+        transactions.measure("list", async { Ok(()) }).await
+    })
+}
+
 #[allow(dead_code)]
 pub fn cleanup(_ctx: EmptyData) -> CleanUpResult<'static> {
     Box::pin(async move {})
@@ -1,4 +1,7 @@
 use crate::support::*;
+use loady::core::arrivals::Arrivals;
+use loady::core::composition::WeightedAction;
+use loady::core::registry::MetricsRegistry;
 use loady::utils::TestCaseBuilder;
 use std::time::Duration;
 
@@ -35,3 +38,122 @@ fn given_test_info_without_steps_when_using_builder_then_build_test_case() {
 
     assert!(test_case.test_steps.is_empty());
 }
+
+#[test]
+fn given_user_load_step_when_using_builder_then_build_test_case() {
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_user_load_step(TEST_STEP_1, Box::new(init_user), Box::new(user_load))
+        .with_stage(
+            TEST_STAGE_1,
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+            1,
+        )
+        .build();
+
+    assert_eq!(test_case.test_steps.len(), 1);
+}
+
+#[test]
+fn given_mixed_load_step_when_using_builder_then_build_test_case() {
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_mixed_load_step(
+            TEST_STEP_1,
+            vec![
+                WeightedAction::new("browse", 80, Box::new(load)),
+                WeightedAction::new("buy", 20, Box::new(load)),
+            ],
+        )
+        .with_stage(
+            TEST_STAGE_1,
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+            1,
+        )
+        .build();
+
+    assert_eq!(test_case.test_steps.len(), 1);
+}
+
+#[test]
+fn given_transaction_load_step_when_using_builder_then_build_test_case() {
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_transaction_load_step(TEST_STEP_1, Box::new(journey))
+        .with_stage(
+            TEST_STAGE_1,
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+            1,
+        )
+        .build();
+
+    assert_eq!(test_case.test_steps.len(), 1);
+}
+
+#[test]
+fn given_metrics_registry_when_using_builder_then_build_test_case() {
+    let registry = MetricsRegistry::default();
+
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_metrics_registry(&registry)
+        .with_load_step(TEST_STEP_1, Box::new(load))
+        .build();
+
+    assert!(test_case.metrics_registry.is_some());
+}
+
+#[test]
+fn given_load_steps_in_concurrent_group_when_using_builder_then_build_test_case() {
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_load_step(TEST_STEP_1, Box::new(load))
+        .with_concurrent_group("traffic")
+        .with_stage(
+            TEST_STAGE_1,
+            Duration::from_secs(20),
+            Duration::from_secs(1),
+            1,
+        )
+        .with_load_step(TEST_STEP_2, Box::new(load))
+        .with_concurrent_group("traffic")
+        .with_stage(
+            TEST_STAGE_2,
+            Duration::from_secs(5),
+            Duration::from_secs(1),
+            10,
+        )
+        .build();
+
+    assert_eq!(test_case.test_steps.len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn given_clean_up_step_when_adding_it_to_concurrent_group_then_panics() {
+    TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_clean_up_step(Box::new(cleanup))
+        .with_concurrent_group("traffic");
+}
+
+#[test]
+fn given_stage_with_arrivals_when_using_builder_then_build_test_case() {
+    let test_case = TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_load_step(TEST_STEP_1, Box::new(load))
+        .with_stage(
+            TEST_STAGE_1,
+            Duration::from_secs(20),
+            Duration::from_secs(1),
+            10,
+        )
+        .with_arrivals(Arrivals::poisson().with_seed(42))
+        .build();
+
+    assert_eq!(test_case.test_steps.len(), 1);
+}
+
+#[test]
+#[should_panic]
+fn given_load_step_without_stages_when_adding_arrivals_then_panics() {
+    TestCaseBuilder::<EmptyData>::new(TEST_NAME, TEST_SUITE, &EmptyData)
+        .with_load_step(TEST_STEP_1, Box::new(load))
+        .with_arrivals(Arrivals::uniform());
+}
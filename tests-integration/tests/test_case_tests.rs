@@ -1,6 +1,7 @@
 use crate::support::*;
 use loady::core::composition::{TestCase, TestStep, TestStepStage};
 use loady::core::context::TestCaseContext;
+use loady::core::reporting::LifecycleEvent;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tokio::time::Duration;
@@ -22,12 +23,12 @@ async fn given_test_case_without_steps_when_running_then_do_nothing() {
     let data = EmptyData::default();
     let (tx_load_action, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
     let (tx_load_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
-    let (tx_internal_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
+    let (tx_event, _) = mpsc::channel::<LifecycleEvent>(CHANNEL_BUFFER_SIZE);
     let mut test_case =
         TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, data);
 
     _ = test_case
-        .run(&tx_load_action, &tx_load_step, &tx_internal_step)
+        .run(&tx_load_action, &tx_load_step, &tx_event)
         .await;
 
     assert!(test_case.test_context.is_none());
@@ -38,7 +39,7 @@ async fn given_test_case_without_load_steps_when_running_then_do_nothing() {
     let data = EmptyData::default();
     let (tx_load_action, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
     let (tx_load_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
-    let (tx_internal_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
+    let (tx_event, _) = mpsc::channel::<LifecycleEvent>(CHANNEL_BUFFER_SIZE);
     let mut test_case =
         TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, data);
     test_case.with_step(TestStep::<'static, EmptyData>::as_clean_up(Box::new(
@@ -51,7 +52,7 @@ async fn given_test_case_without_load_steps_when_running_then_do_nothing() {
     test_case.with_step(TestStep::<'static, EmptyData>::as_init(Box::new(init)));
 
     _ = test_case
-        .run(&tx_load_action, &tx_load_step, &tx_internal_step)
+        .run(&tx_load_action, &tx_load_step, &tx_event)
         .await;
 
     assert!(test_case.test_context.is_none());
@@ -62,7 +63,7 @@ async fn given_test_case_with_load_step_and_empty_stages_when_running_then_do_no
     let data = EmptyData::default();
     let (tx_load_action, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
     let (tx_load_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
-    let (tx_internal_step, _) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
+    let (tx_event, _) = mpsc::channel::<LifecycleEvent>(CHANNEL_BUFFER_SIZE);
     let mut test_case =
         TestCase::<'static, TestCaseContext, EmptyData>::new(TEST_NAME, TEST_SUITE, data);
     test_case.with_step(TestStep::<'static, EmptyData>::as_load(
@@ -72,7 +73,7 @@ async fn given_test_case_with_load_step_and_empty_stages_when_running_then_do_no
     ));
 
     _ = test_case
-        .run(&tx_load_action, &tx_load_step, &tx_internal_step)
+        .run(&tx_load_action, &tx_load_step, &tx_event)
         .await;
 
     assert!(test_case.test_context.is_none());
@@ -84,8 +85,7 @@ async fn given_test_case_with_load_step_and_stages_when_running_then_do_somethin
     let (tx_load_action, mut rx_load_action) =
         mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
     let (tx_load_step, mut rx_load_step) = mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
-    let (tx_internal_step, mut rx_internal_step) =
-        mpsc::channel::<TestCaseContext>(CHANNEL_BUFFER_SIZE);
+    let (tx_event, mut rx_event) = mpsc::channel::<LifecycleEvent>(CHANNEL_BUFFER_SIZE);
 
     _ = tokio::spawn(async move {
         while let Some(_) = rx_load_action.recv().await {
@@ -100,7 +100,7 @@ async fn given_test_case_with_load_step_and_stages_when_running_then_do_somethin
     });
 
     _ = tokio::spawn(async move {
-        while let Some(_) = rx_internal_step.recv().await {
+        while let Some(_) = rx_event.recv().await {
             sleep(Duration::from_millis(200)).await;
         }
     });
@@ -116,7 +116,7 @@ async fn given_test_case_with_load_step_and_stages_when_running_then_do_somethin
     test_case.with_step(load_step);
 
     _ = test_case
-        .run(&tx_load_action, &tx_load_step, &tx_internal_step)
+        .run(&tx_load_action, &tx_load_step, &tx_event)
         .await;
 
     assert!(test_case.test_context.is_some());
@@ -3,6 +3,7 @@ use loady::core::composition::{TestCase, TestStep, TestStepStage};
 use loady::core::context::TestCaseContext;
 use loady::core::runner::TestRunner;
 use loady::core::stats::Metrics;
+use loady::core::suite::{SuiteMode, TestSuite};
 use tokio::time::Duration;
 
 mod support;
@@ -72,6 +73,66 @@ async fn given_test_with_steps_when_running_test_then_returns_metrics() {
     assert_not_blank_metrics(&test_status.metrics);
 }
 
+#[tokio::test]
+async fn given_suite_with_failing_case_when_running_sequentially_then_fails_suite() {
+    let suite = TestSuite::new(TEST_SUITE, SuiteMode::Sequential)
+        .with_test_case(suite_case(TEST_NAME))
+        .with_test_case(TestCase::<'static, TestCaseContext, EmptyData>::new(
+            "empty test",
+            TEST_SUITE,
+            EmptyData,
+        ));
+    let runner = TestRunner::default();
+
+    let actual = runner.run_suite(suite).await.unwrap();
+
+    assert!(!actual.passed);
+    assert_eq!(actual.test_status.len(), 1);
+    assert_eq!(actual.test_status[0].test_name, TEST_NAME);
+    assert_eq!(actual.failures.len(), 1);
+    assert_eq!(actual.failures[0].test_name, "empty test");
+}
+
+#[tokio::test]
+async fn given_suite_when_running_concurrently_then_aggregates_test_cases() {
+    let suite = TestSuite::new(TEST_SUITE, SuiteMode::Concurrent)
+        .with_test_case(suite_case("first test"))
+        .with_test_case(suite_case("second test"));
+    let runner = TestRunner::default();
+
+    let actual = runner.run_suite(suite).await.unwrap();
+
+    assert!(actual.passed);
+    assert_eq!(actual.test_status.len(), 2);
+    assert!(actual.failures.is_empty());
+    assert_eq!(
+        actual.all_hits,
+        actual
+            .test_status
+            .iter()
+            .map(|status| status.metrics.all_hits)
+            .sum::<u128>()
+    );
+    assert!(actual.duration < 2000);
+}
+
+fn suite_case(test_name: &'static str) -> TestCase<'static, TestCaseContext<'static>, EmptyData> {
+    let mut test_case =
+        TestCase::<'static, TestCaseContext, EmptyData>::new(test_name, TEST_SUITE, EmptyData);
+    let stages = vec![TestStepStage::new(
+        TEST_STAGE_1,
+        Duration::from_secs(1),
+        Duration::from_millis(250),
+        2,
+    )];
+    test_case.with_step(TestStep::<'static, EmptyData>::as_load(
+        TEST_STEP_1,
+        Box::new(load),
+        stages,
+    ));
+    test_case
+}
+
 fn assert_not_blank_metrics(metrics: &Metrics) {
     assert!(metrics.test_duration > 0);
     assert!(metrics.mean_time > 0);